@@ -0,0 +1,21 @@
+//! Runtime API definition for the execution pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use scale_codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_execution::PendingRequest;
+
+sp_api::decl_runtime_apis! {
+    pub trait ExecutionApi<AccountId, AgreementId, Balance, BlockNumber>
+    where
+        AccountId: Codec,
+        AgreementId: Codec,
+        Balance: Codec,
+        BlockNumber: Codec,
+    {
+        /// Requests `provider` has yet to respond to, highest tip first then oldest first.
+        fn pending_requests(provider: AccountId) -> Vec<PendingRequest<AgreementId, Balance, BlockNumber>>;
+    }
+}
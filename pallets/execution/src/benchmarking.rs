@@ -34,6 +34,9 @@ fn create_agreement<T: Config>(
         T::BenchmarkHelper::get_model_id(),
         BalanceOf::<T>::from(1_000u32),
         10,
+        None,
+        None,
+        None,
     ));
 }
 
@@ -45,11 +48,50 @@ fn create_request<T: Config>(
         RawOrigin::Signed(consumer).into(),
         agreement_id,
         T::BenchmarkHelper::get_content_id(),
+        None,
+        None,
     ));
 
     Agreements::<T>::get(&agreement_id).unwrap().requests_count
 }
 
+fn create_response<T: Config>(
+    provider: T::AccountId,
+    agreement_id: T::AgreementId,
+    request_index: RequestsUsize,
+) {
+    // Responses are subject to a storage deposit.
+    prefund_account::<T>(&provider);
+    assert_ok!(AiroExecution::<T>::response_create(
+        RawOrigin::Signed(provider).into(),
+        agreement_id,
+        request_index,
+        T::BenchmarkHelper::get_content_id(),
+        None,
+        0,
+    ));
+}
+
+fn create_subscription_agreement<T: Config>(
+    consumer: T::AccountId,
+    provider: T::AccountId,
+    agreement_id: T::AgreementId,
+) {
+    assert_ok!(AiroExecution::<T>::create_subscription_agreement(
+        consumer,
+        provider,
+        agreement_id,
+        T::BenchmarkHelper::get_model_id(),
+        BalanceOf::<T>::from(1_000u32),
+        10u32.into(),
+        10,
+        None,
+        None,
+        None,
+        None,
+    ));
+}
+
 #[benchmarks]
 mod benchmarks {
     use super::*;
@@ -61,9 +103,17 @@ mod benchmarks {
         let provider = get_account::<T>(1);
         let agreement_id = T::AgreementId::default();
         create_agreement::<T>(caller.clone(), provider, agreement_id);
+        let params: RequestParams<T> =
+            vec![0u8; T::MaxRequestParamsLength::get() as usize].try_into().unwrap();
 
         #[extrinsic_call]
-        _(RawOrigin::Signed(caller), agreement_id, T::BenchmarkHelper::get_content_id());
+        _(
+            RawOrigin::Signed(caller),
+            agreement_id,
+            T::BenchmarkHelper::get_content_id(),
+            Some(params),
+            Some(BalanceOf::<T>::from(1_000u32)),
+        );
     }
 
     #[benchmark]
@@ -71,10 +121,188 @@ mod benchmarks {
         let consumer: T::AccountId = get_account::<T>(1);
         prefund_account::<T>(&consumer);
         let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
         let agreement_id = T::AgreementId::default();
-        create_agreement::<T>(consumer.clone(), caller.clone(), agreement_id);
+
+        // The worst case is the response reaching the quorum of a redundant agreement, which
+        // settles the responses of all the providers.
+        let replicas: Vec<T::AccountId> =
+            (0..T::MaxReplicas::get()).map(|index| get_account::<T>(100 + index)).collect();
+        let providers: Vec<T::AccountId> =
+            core::iter::once(caller.clone()).chain(replicas.iter().cloned()).collect();
+        let quorum = providers.len() as RequestsUsize / 2 + 1;
+        assert_ok!(AiroExecution::<T>::create_redundant_agreement(
+            consumer.clone(),
+            &providers,
+            agreement_id,
+            T::BenchmarkHelper::get_model_id(),
+            BalanceOf::<T>::from(1_000u32),
+            10,
+            quorum,
+            None,
+            None,
+            None,
+        ));
         let request_index = create_request::<T>(consumer, agreement_id);
 
+        let content_id = T::BenchmarkHelper::get_content_id();
+        for (index, replica) in replicas.into_iter().enumerate() {
+            prefund_account::<T>(&replica);
+            // One less than the quorum agrees with the caller, the rest dissents.
+            let output_hash =
+                if (index as RequestsUsize) + 1 < quorum { None } else { Some(T::Hash::default()) };
+            assert_ok!(AiroExecution::<T>::response_create(
+                RawOrigin::Signed(replica).into(),
+                agreement_id,
+                request_index,
+                content_id.clone(),
+                output_hash,
+                0,
+            ));
+        }
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id, request_index, content_id.clone(), None, 0);
+
+        assert_eq!(
+            Responses::<T>::get(agreement_id, request_index).map(|response| response.content_id),
+            Some(content_id)
+        );
+    }
+
+    #[benchmark]
+    fn agreement_close() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let provider = get_account::<T>(1);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(caller.clone(), provider.clone(), agreement_id);
+        let request_index = create_request::<T>(caller.clone(), agreement_id);
+        create_response::<T>(provider, agreement_id, request_index);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id);
+
+        assert!(!Agreements::<T>::contains_key(agreement_id));
+    }
+
+    #[benchmark]
+    fn agreement_transfer() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let provider = get_account::<T>(1);
+        let new_consumer = get_account::<T>(2);
+        prefund_account::<T>(&new_consumer);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(caller.clone(), provider, agreement_id);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id, new_consumer);
+    }
+
+    #[benchmark]
+    fn agreement_transfer_approve() {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let caller: T::AccountId = whitelisted_caller();
+        let new_consumer = get_account::<T>(2);
+        prefund_account::<T>(&new_consumer);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(consumer, caller.clone(), agreement_id);
+        PendingTransfers::<T>::insert(agreement_id, &new_consumer);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id);
+
+        assert!(Agreements::<T>::get(agreement_id).unwrap().is_consumer(&new_consumer));
+    }
+
+    #[benchmark]
+    fn delegate_add() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let provider = get_account::<T>(1);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(caller.clone(), provider, agreement_id);
+        let delegate = get_account::<T>(2);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id, delegate, Some(10));
+    }
+
+    #[benchmark]
+    fn delegate_remove() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let provider = get_account::<T>(1);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(caller.clone(), provider, agreement_id);
+        let delegate = get_account::<T>(2);
+        assert_ok!(AiroExecution::<T>::delegate_add(
+            RawOrigin::Signed(caller.clone()).into(),
+            agreement_id,
+            delegate.clone(),
+            Some(10),
+        ));
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id, delegate);
+    }
+
+    #[benchmark]
+    fn operator_add() {
+        let caller: T::AccountId = whitelisted_caller();
+        let operator = get_account::<T>(1);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), operator);
+    }
+
+    #[benchmark]
+    fn operator_remove() {
+        let caller: T::AccountId = whitelisted_caller();
+        let operator = get_account::<T>(1);
+        assert_ok!(AiroExecution::<T>::operator_add(
+            RawOrigin::Signed(caller.clone()).into(),
+            operator.clone(),
+        ));
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), operator);
+    }
+
+    #[benchmark]
+    fn spending_limits_set() {
+        let caller: T::AccountId = whitelisted_caller();
+        let limits = SpendingLimits::<T> { per_period: Some(1_000u32.into()), per_agreement: None };
+        assert_ok!(AiroExecution::<T>::spending_limits_set(
+            RawOrigin::Signed(caller.clone()).into(),
+            None,
+            limits,
+        ));
+        // Raised limits are the worst case, as they are time locked.
+        let limits = SpendingLimits::<T> { per_period: None, per_agreement: None };
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), None, limits);
+
+        assert!(PendingSpendingLimits::<T>::contains_key(&caller, None::<AssetIdOf<T>>));
+    }
+
+    #[benchmark]
+    fn challenge_create() {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let provider = get_account::<T>(2);
+        prefund_account::<T>(&provider);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(consumer.clone(), provider.clone(), agreement_id);
+        let request_index = create_request::<T>(consumer, agreement_id);
+        create_response::<T>(provider, agreement_id, request_index);
+
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+
         #[extrinsic_call]
         _(
             RawOrigin::Signed(caller),
@@ -82,6 +310,165 @@ mod benchmarks {
             request_index,
             T::BenchmarkHelper::get_content_id(),
         );
+
+        assert!(Challenges::<T>::contains_key(agreement_id, request_index));
+    }
+
+    #[benchmark]
+    fn challenge_resolve() -> Result<(), BenchmarkError> {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let provider = get_account::<T>(2);
+        prefund_account::<T>(&provider);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(consumer.clone(), provider.clone(), agreement_id);
+        let request_index = create_request::<T>(consumer, agreement_id);
+        create_response::<T>(provider, agreement_id, request_index);
+
+        let challenger = get_account::<T>(3);
+        prefund_account::<T>(&challenger);
+        assert_ok!(AiroExecution::<T>::challenge_create(
+            RawOrigin::Signed(challenger).into(),
+            agreement_id,
+            request_index,
+            T::BenchmarkHelper::get_content_id(),
+        ));
+        let origin = T::ChallengeResolverOrigin::try_successful_origin()
+            .map_err(|_| BenchmarkError::Weightless)?;
+
+        #[extrinsic_call]
+        _(origin as T::RuntimeOrigin, agreement_id, request_index, true);
+
+        assert!(!Challenges::<T>::contains_key(agreement_id, request_index));
+        Ok(())
+    }
+
+    #[benchmark]
+    fn challenge_expire() {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let provider = get_account::<T>(2);
+        prefund_account::<T>(&provider);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(consumer.clone(), provider.clone(), agreement_id);
+        let request_index = create_request::<T>(consumer, agreement_id);
+        create_response::<T>(provider, agreement_id, request_index);
+
+        let challenger = get_account::<T>(3);
+        prefund_account::<T>(&challenger);
+        assert_ok!(AiroExecution::<T>::challenge_create(
+            RawOrigin::Signed(challenger).into(),
+            agreement_id,
+            request_index,
+            T::BenchmarkHelper::get_content_id(),
+        ));
+        let expires_at =
+            frame_system::Pallet::<T>::block_number().saturating_add(T::ChallengeExpiry::get());
+        frame_system::Pallet::<T>::set_block_number(expires_at.saturating_add(1u32.into()));
+
+        let caller: T::AccountId = whitelisted_caller();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id, request_index);
+
+        assert!(!Challenges::<T>::contains_key(agreement_id, request_index));
+    }
+
+    #[benchmark]
+    fn subscription_settle() {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let provider = get_account::<T>(2);
+        let agreement_id = T::AgreementId::default();
+        create_subscription_agreement::<T>(consumer, provider, agreement_id);
+        let now = frame_system::Pallet::<T>::block_number();
+        frame_system::Pallet::<T>::set_block_number(now.saturating_add(50u32.into()));
+
+        let caller: T::AccountId = whitelisted_caller();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id);
+
+        let subscription = Agreements::<T>::get(agreement_id).unwrap().subscription.unwrap();
+        assert_eq!(subscription.periods_paid, 5);
+    }
+
+    #[benchmark]
+    fn subscription_terminate() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let provider = get_account::<T>(2);
+        let agreement_id = T::AgreementId::default();
+        create_subscription_agreement::<T>(caller.clone(), provider, agreement_id);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id);
+    }
+
+    #[benchmark]
+    fn agreement_extend() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let provider = get_account::<T>(1);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(caller.clone(), provider, agreement_id);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id, 1);
+    }
+
+    #[benchmark]
+    fn agreement_extend_approve() {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let caller: T::AccountId = whitelisted_caller();
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(consumer, caller.clone(), agreement_id);
+        PendingExtensions::<T>::insert(agreement_id, 1_000);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id);
+
+        assert_eq!(Agreements::<T>::get(agreement_id).unwrap().requests_total, 1_010);
+    }
+
+    #[benchmark]
+    fn request_expire() {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let provider = get_account::<T>(2);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(consumer.clone(), provider, agreement_id);
+        let request_index = create_request::<T>(consumer, agreement_id);
+        let expires_at =
+            frame_system::Pallet::<T>::block_number().saturating_add(T::RequestTimeout::get());
+        frame_system::Pallet::<T>::set_block_number(expires_at.saturating_add(1u32.into()));
+
+        let caller: T::AccountId = whitelisted_caller();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), agreement_id, request_index);
+
+        assert!(!Requests::<T>::contains_key(agreement_id, request_index));
+    }
+
+    #[benchmark]
+    fn prune_request() {
+        let consumer = get_account::<T>(1);
+        prefund_account::<T>(&consumer);
+        let provider = get_account::<T>(2);
+        let agreement_id = T::AgreementId::default();
+        create_agreement::<T>(consumer.clone(), provider.clone(), agreement_id);
+        let request_index = create_request::<T>(consumer, agreement_id);
+        create_response::<T>(provider, agreement_id, request_index);
+
+        #[block]
+        {
+            AiroExecution::<T>::prune_request(agreement_id, request_index);
+        }
+
+        assert!(!Requests::<T>::contains_key(agreement_id, request_index));
+        assert!(!Responses::<T>::contains_key(agreement_id, request_index));
     }
 
     impl_benchmark_test_suite!(AiroExecution, mock::new_test_ext(), mock::Test);
@@ -3,14 +3,31 @@
 // We make sure this pallet uses `no_std` for compiling to Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use airo_primitives::{agreement::AgreementManagement, payment::RoyaltyResolver, RequestsUsize};
+use airo_primitives::{
+    agreement::{AgreementManagement, AgreementRegistry, PipelineStage},
+    model::ModelVersion,
+    payment::RoyaltyResolver,
+    provider::{ExecutionObserver, ProviderBonds, ProviderRegistry},
+    requirements::ExecutionRequirements,
+    RequestsUsize, UnitsUsize,
+};
 use frame_support::{
     pallet_prelude::*,
-    traits::fungible::{hold::Mutate as FunHoldMutate, Inspect as FunInspect, Mutate as FunMutate},
+    storage::with_storage_layer,
+    traits::{
+        fungible::{hold::Mutate as FunHoldMutate, Inspect as FunInspect, Mutate as FunMutate},
+        fungibles::{Inspect as FunsInspect, Mutate as FunsMutate},
+    },
+    weights::WeightMeter,
+    PalletId,
 };
 use frame_system::pallet_prelude::*;
 pub use pallet::*;
-use sp_runtime::{traits::Zero, Saturating};
+use sp_runtime::{
+    traits::{AccountIdConversion, Zero},
+    Perbill, Saturating,
+};
+use sp_std::vec::Vec;
 use storage::*;
 use types::*;
 pub use weights::*;
@@ -31,15 +48,22 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 pub mod benchmarking;
 
+pub mod migrations;
 mod storage;
 mod types;
 pub mod weights;
 
+pub use types::{PendingRequest, SpendingLimits};
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
 
+    /// The in-code storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
@@ -57,6 +81,13 @@ pub mod pallet {
         type Currency: FunMutate<Self::AccountId>
             + FunHoldMutate<Self::AccountId, Reason = Self::RuntimeHoldReason>;
 
+        /// Fungible assets, other than the native currency, agreements can be paid in.
+        type Assets: FunsMutate<Self::AccountId, Balance = BalanceOf<Self>>;
+
+        /// Identifier used to derive the accounts escrowing prepayments made in assets.
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
         /// Agreement ID type.
         type AgreementId: Member + Parameter + MaxEncodedLen + Copy + Default;
 
@@ -66,6 +97,22 @@ pub mod pallet {
         /// Content ID type.
         type ContentId: Member + Parameter + MaxEncodedLen;
 
+        /// Maximum length of the parameters that can be attached to a request.
+        #[pallet::constant]
+        type MaxRequestParamsLength: Get<u32>;
+
+        /// Deposit held per byte of the parameters attached to a request.
+        #[pallet::constant]
+        type RequestParamsDepositPerByte: Get<BalanceOf<Self>>;
+
+        /// Deposit held from the requester for storing a request, until it is pruned.
+        #[pallet::constant]
+        type RequestDeposit: Get<BalanceOf<Self>>;
+
+        /// Deposit held from the responder for storing a response, until it is pruned.
+        #[pallet::constant]
+        type ResponseDeposit: Get<BalanceOf<Self>>;
+
         /// Used to find royalty payment information for a model.
         type RoyaltyResolver: RoyaltyResolver<
             AccountId = Self::AccountId,
@@ -73,6 +120,78 @@ pub mod pallet {
             ModelId = Self::ModelId,
         >;
 
+        /// Number of blocks a provider has to respond to a request before it can be expired.
+        #[pallet::constant]
+        type RequestTimeout: Get<BlockNumberFor<Self>>;
+
+        /// Used to find the number of requests providers can have unanswered at once.
+        type ProviderRegistry: ProviderRegistry<AccountId = Self::AccountId>;
+
+        /// Notified of the outcomes of executions, e.g. to track the reputation of providers.
+        type ExecutionObserver: ExecutionObserver<Self::AccountId, BlockNumberFor<Self>>;
+
+        /// Number of blocks a responded request is kept in storage before it gets pruned.
+        #[pallet::constant]
+        type RetentionPeriod: Get<BlockNumberFor<Self>>;
+
+        /// Whether a provider has to approve the transfer of an agreement to another consumer.
+        #[pallet::constant]
+        type TransferApprovalRequired: Get<bool>;
+
+        /// Extensions of an agreement by more requests than this have to be approved by a provider.
+        #[pallet::constant]
+        type ExtensionApprovalThreshold: Get<RequestsUsize>;
+
+        /// Maximum number of delegates an agreement can have.
+        #[pallet::constant]
+        type MaxDelegates: Get<u32>;
+
+        /// Maximum number of operators a provider can have.
+        #[pallet::constant]
+        type MaxOperators: Get<u32>;
+
+        /// Maximum number of additional providers executing the requests of a redundant agreement.
+        #[pallet::constant]
+        type MaxReplicas: Get<u32>;
+
+        /// Number of blocks after a response is created during which it can be challenged.
+        #[pallet::constant]
+        type ChallengeWindow: Get<BlockNumberFor<Self>>;
+
+        /// Stake held from the challenger while a challenge is open, and slashed from the bond of
+        /// the provider if the challenge is upheld.
+        #[pallet::constant]
+        type ChallengeStake: Get<BalanceOf<Self>>;
+
+        /// Number of blocks a challenge can stay unresolved, after which it can be expired.
+        #[pallet::constant]
+        type ChallengeExpiry: Get<BlockNumberFor<Self>>;
+
+        /// Bonds of the providers, which back their responses when challenged.
+        type ProviderBonds: ProviderBonds<Self::AccountId, BalanceOf<Self>>;
+
+        /// Origin resolving challenges.
+        type ChallengeResolverOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Minimum number of blocks between terminating a subscription and its end.
+        #[pallet::constant]
+        type SubscriptionNoticePeriod: Get<BlockNumberFor<Self>>;
+
+        /// Share of every provider payment and royalty collected as the protocol fee.
+        #[pallet::constant]
+        type ProtocolFee: Get<Perbill>;
+
+        /// Account the protocol fees are collected into.
+        type TreasuryAccount: Get<Self::AccountId>;
+
+        /// Length in blocks of the periods the spending limits of consumers are counted over.
+        #[pallet::constant]
+        type SpendingPeriod: Get<BlockNumberFor<Self>>;
+
+        /// Number of blocks before raised spending limits of a consumer take effect.
+        #[pallet::constant]
+        type SpendingLimitDelay: Get<BlockNumberFor<Self>>;
+
         #[cfg(feature = "runtime-benchmarks")]
         type BenchmarkHelper: benchmarking::ModelFactory<Self::ModelId>
             + benchmarking::ContentFactory<Self::ContentId>;
@@ -104,7 +223,7 @@ pub mod pallet {
         T::AgreementId,
         Blake2_128Concat,
         RequestsUsize,
-        T::ContentId,
+        RequestDetails<T>,
     >;
 
     /// Responses to the requests.
@@ -115,9 +234,142 @@ pub mod pallet {
         T::AgreementId,
         Blake2_128Concat,
         RequestsUsize,
-        T::ContentId,
+        ResponseDetails<T>,
+    >;
+
+    /// Responses of every provider of a redundant agreement.
+    /// The first key is a pair of Agreement ID and Request Index.
+    #[pallet::storage]
+    pub type ReplicaResponses<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        (T::AgreementId, RequestsUsize),
+        Twox64Concat,
+        Provider<T>,
+        ResponseDetails<T>,
+    >;
+
+    /// Accounts allowed to create requests on behalf of an agreement's consumer.
+    #[pallet::storage]
+    pub type AgreementDelegates<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AgreementId,
+        BoundedBTreeMap<T::AccountId, DelegateDetails, T::MaxDelegates>,
+        ValueQuery,
+    >;
+
+    /// Accounts allowed to create responses on behalf of a provider.
+    #[pallet::storage]
+    pub type ProviderOperators<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        Provider<T>,
+        BoundedBTreeSet<T::AccountId, T::MaxOperators>,
+        ValueQuery,
+    >;
+
+    /// Number of requests a provider has yet to respond to, across all its agreements.
+    #[pallet::storage]
+    pub type OpenRequests<T: Config> =
+        StorageMap<_, Twox64Concat, Provider<T>, RequestsUsize, ValueQuery>;
+
+    /// Number of requests a provider has yet to respond to on an agreement, released all at once
+    /// when the agreement is closed.
+    #[pallet::storage]
+    pub type AgreementOpenRequests<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AgreementId,
+        Twox64Concat,
+        Provider<T>,
+        RequestsUsize,
+        ValueQuery,
+    >;
+
+    /// Limits on the prepayments of consumers, in the native currency if no asset is given.
+    #[pallet::storage]
+    pub type ConsumerSpendingLimits<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        Consumer<T>,
+        Blake2_128Concat,
+        Option<AssetIdOf<T>>,
+        SpendingLimits<T>,
+        ValueQuery,
+    >;
+
+    /// Raised spending limits of consumers, and the block they take effect at.
+    #[pallet::storage]
+    pub type PendingSpendingLimits<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        Consumer<T>,
+        Blake2_128Concat,
+        Option<AssetIdOf<T>>,
+        (SpendingLimits<T>, BlockNumberFor<T>),
+    >;
+
+    /// Prepayments of consumers with a period spending limit, in the current period.
+    #[pallet::storage]
+    pub type ConsumerSpending<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        Consumer<T>,
+        Blake2_128Concat,
+        Option<AssetIdOf<T>>,
+        SpendingDetails<T>,
+        ValueQuery,
+    >;
+
+    /// Agreement transfers waiting for the provider's approval.
+    /// The value is the consumer the agreement is being transferred to.
+    #[pallet::storage]
+    pub type PendingTransfers<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AgreementId, Consumer<T>>;
+
+    /// Agreement extensions waiting for the provider's approval.
+    /// The value is the number of extra requests.
+    #[pallet::storage]
+    pub type PendingExtensions<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AgreementId, RequestsUsize>;
+
+    /// Closed agreements, which requests and responses are waiting to be pruned.
+    #[pallet::storage]
+    pub type ClosedAgreements<T: Config> = StorageMap<_, Blake2_128Concat, T::AgreementId, ()>;
+
+    /// Responded requests scheduled for pruning.
+    /// The key is a pair of the block the retention period elapses at and the request key.
+    #[pallet::storage]
+    pub type PruneSchedule<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        Blake2_128Concat,
+        (T::AgreementId, RequestsUsize),
+        (),
+    >;
+
+    /// Closed agreements scheduled for pruning once their responses can't be challenged anymore.
+    #[pallet::storage]
+    pub type ClosingSchedule<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, BlockNumberFor<T>, Blake2_128Concat, T::AgreementId, ()>;
+
+    /// Open challenges of responses.
+    #[pallet::storage]
+    pub type Challenges<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AgreementId,
+        Blake2_128Concat,
+        RequestsUsize,
+        ChallengeDetails<T>,
     >;
 
+    /// The next block of `PruneSchedule` to be processed.
+    #[pallet::storage]
+    pub type PruneCursor<T: Config> = StorageValue<_, BlockNumberFor<T>>;
+
     /// A reason for the Execution pallet placing a hold on funds.
     #[pallet::composite_enum]
     pub enum HoldReason {
@@ -125,6 +377,14 @@ pub mod pallet {
         ProviderPayment,
         /// Royalty payment.
         RoyaltyPayment,
+        /// Deposit for storing a request and its parameters.
+        RequestDeposit,
+        /// Deposit for storing a response.
+        ResponseDeposit,
+        /// Tip attached to a request, paid to the provider of the accepted response.
+        RequestTip,
+        /// Stake of a challenge participant.
+        ChallengeStake,
     }
 
     /// Events.
@@ -144,6 +404,8 @@ pub mod pallet {
             request_index: RequestsUsize,
             /// The content ID.
             content_id: T::ContentId,
+            /// The parameters attached to the request.
+            params: Option<RequestParams<T>>,
         },
         /// A response has been created.
         ResponseCreated {
@@ -154,6 +416,198 @@ pub mod pallet {
             /// The content ID.
             content_id: T::ContentId,
         },
+        /// A delegate has been added to an agreement or its quota has been updated.
+        DelegateAdded {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The delegate.
+            delegate: T::AccountId,
+            /// The maximum number of requests the delegate can create, if limited.
+            quota: Option<RequestsUsize>,
+        },
+        /// A delegate has been removed from an agreement.
+        DelegateRemoved {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The delegate.
+            delegate: T::AccountId,
+        },
+        /// An operator has been added to a provider.
+        OperatorAdded {
+            /// The provider.
+            provider: T::AccountId,
+            /// The operator.
+            operator: T::AccountId,
+        },
+        /// An operator has been removed from a provider.
+        OperatorRemoved {
+            /// The provider.
+            provider: T::AccountId,
+            /// The operator.
+            operator: T::AccountId,
+        },
+        /// A consumer has set its spending limits.
+        SpendingLimitsSet {
+            /// The consumer.
+            consumer: T::AccountId,
+            /// The asset the limits apply to, or the native currency if none.
+            asset: Option<AssetIdOf<T>>,
+            /// The limits in effect.
+            limits: SpendingLimits<T>,
+        },
+        /// A consumer has raised its spending limits, which take effect later.
+        SpendingLimitsScheduled {
+            /// The consumer.
+            consumer: T::AccountId,
+            /// The asset the limits apply to, or the native currency if none.
+            asset: Option<AssetIdOf<T>>,
+            /// The limits.
+            limits: SpendingLimits<T>,
+            /// The block the limits take effect at.
+            effective_at: BlockNumberFor<T>,
+        },
+        /// An agreement transfer is waiting for the provider's approval.
+        AgreementTransferRequested {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The consumer the agreement is being transferred to.
+            new_consumer: T::AccountId,
+        },
+        /// An agreement has been transferred to another consumer.
+        AgreementTransferred {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The new consumer.
+            consumer: T::AccountId,
+        },
+        /// An agreement extension is waiting for the provider's approval.
+        AgreementExtensionRequested {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The number of extra requests.
+            extra_requests: RequestsUsize,
+        },
+        /// An agreement has been extended.
+        AgreementExtended {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The new total number of requests.
+            requests_total: RequestsUsize,
+        },
+        /// A provider of a redundant agreement has responded to a request.
+        ReplicaResponseCreated {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+            /// The provider.
+            provider: T::AccountId,
+            /// The content ID.
+            content_id: T::ContentId,
+        },
+        /// A provider's response does not match the one accepted by the quorum and is not paid.
+        ResponseDissented {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+            /// The provider.
+            provider: T::AccountId,
+        },
+        /// All the providers have responded, but none of the responses reached the quorum.
+        /// The payments for the request are refunded.
+        QuorumNotReached {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+        },
+        /// A request has not been responded to in time. The payments for it are refunded.
+        RequestExpired {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+        },
+        /// The response to a request of a pipeline stage could not be forwarded to the next
+        /// stage, e.g. as it has been closed.
+        ResponseNotForwarded {
+            /// The agreement ID of the next stage.
+            agreement_id: T::AgreementId,
+            /// The content ID of the response.
+            content_id: T::ContentId,
+        },
+        /// A response has been challenged.
+        ChallengeCreated {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+            /// The challenger.
+            challenger: T::AccountId,
+            /// The content ID of the output claimed by the challenger.
+            content_id: T::ContentId,
+        },
+        /// A challenge has been resolved.
+        ChallengeResolved {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+            /// The party the loser's stake has been slashed to.
+            winner: T::AccountId,
+            /// The party which stake has been slashed.
+            loser: T::AccountId,
+            /// The slashed amount.
+            slashed: BalanceOf<T>,
+        },
+        /// A challenge has not been resolved in time, and the challenger's stake has been
+        /// released.
+        ChallengeExpired {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+        },
+        /// Elapsed periods of a subscription have been paid.
+        SubscriptionSettled {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The number of periods paid.
+            periods: u32,
+        },
+        /// A subscription has been terminated.
+        SubscriptionTerminated {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The block the subscription ends at.
+            ends_at: BlockNumberFor<T>,
+        },
+        /// An agreement has been closed.
+        AgreementClosed {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+        },
+        /// A request and its response have been pruned from storage.
+        RequestPruned {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+            /// The content ID of the request.
+            request_content_id: T::ContentId,
+            /// The content ID of the response, if any.
+            response_content_id: Option<T::ContentId>,
+        },
+        /// Funds held for a pruned request could not be released.
+        PruneReleaseFailed {
+            /// The agreement ID.
+            agreement_id: T::AgreementId,
+            /// The request index.
+            request_index: RequestsUsize,
+            /// The error returned by the release.
+            error: DispatchError,
+        },
     }
 
     /// Errors.
@@ -169,88 +623,1060 @@ pub mod pallet {
         RequestNotFound,
         /// Response is already exists.
         ResponseAlreadyExists,
+        /// Request can still be responded to.
+        RequestActive,
+        /// Response is created later than the maximum latency of the agreement allows.
+        LatencyExceeded,
+        /// Agreement has requests waiting for a response.
+        AgreementHasPendingRequests,
+        /// Agreement transfer is not found.
+        TransferNotFound,
+        /// Agreement extension is not found.
+        ExtensionNotFound,
+        /// Delegate is not found.
+        DelegateNotFound,
+        /// Agreement already has the maximum number of delegates.
+        TooManyDelegates,
+        /// Delegate has used all the requests allowed by its quota.
+        DelegateQuotaExceeded,
+        /// Operator already exists.
+        OperatorAlreadyExists,
+        /// Operator is not found.
+        OperatorNotFound,
+        /// Provider already has the maximum number of operators.
+        TooManyOperators,
+        /// Providers or quorum of a redundant agreement are invalid.
+        ReplicationInvalid,
+        /// Response is not found.
+        ResponseNotFound,
+        /// Challenge window of the response has elapsed.
+        ChallengeWindowElapsed,
+        /// Challenge is invalid.
+        ChallengeInvalid,
+        /// Response is already challenged.
+        ChallengeAlreadyExists,
+        /// Challenge is not found.
+        ChallengeNotFound,
+        /// Challenge can still be resolved.
+        ChallengeActive,
+        /// Subscription terms are invalid, or the agreement is not a subscription.
+        SubscriptionInvalid,
+        /// Subscription has ended.
+        SubscriptionEnded,
+        /// Subscription's requests per period are exhausted.
+        SubscriptionQuotaExceeded,
+        /// Subscription has not ended yet.
+        SubscriptionActive,
+        /// Pipeline has less than two stages.
+        PipelineInvalid,
+        /// Batch is empty, larger than the maximum units per request, or the agreement is not
+        /// priced per unit.
+        BatchInvalid,
+        /// Response declares more items processed than the batch has.
+        BatchItemsExceeded,
+        /// Provider has as many unanswered requests as its capacity.
+        ProviderAtCapacity,
+        /// Prepayment exceeds the spending limits of the consumer.
+        SpendingLimitExceeded,
+        /// Agreement still allows new requests, so only the consumer can close it.
+        AgreementActive,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::prune(now, remaining_weight)
+        }
+
+        fn integrity_test() {
+            assert!(
+                T::ChallengeWindow::get() <= T::RetentionPeriod::get(),
+                "Responses must be retained for at least the challenge window"
+            );
+            assert!(!T::SpendingPeriod::get().is_zero(), "Spending period must not be empty");
+        }
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
+        /// Creates a request on an agreement. The `tip`, if any, is held from the requester and
+        /// paid to the provider of the accepted response, or released if there is none. Providers
+        /// see their pending requests ordered by tip.
         #[pallet::call_index(0)]
         #[pallet::weight(T::WeightInfo::request_create())]
         pub fn request_create(
             origin: OriginFor<T>,
             agreement_id: T::AgreementId,
             content_id: T::ContentId,
+            params: Option<RequestParams<T>>,
+            tip: Option<BalanceOf<T>>,
         ) -> DispatchResult {
-            let consumer = ensure_signed(origin)?;
+            let requester = ensure_signed(origin)?;
 
-            let request_index =
-                Agreements::<T>::try_mutate(agreement_id, |agreement| -> Result<_, Error<T>> {
-                    let agreement = agreement.as_mut().ok_or(Error::<T>::AgreementNotFound)?;
-                    ensure!(agreement.is_consumer(&consumer), Error::<T>::AgreementInvalid);
-
-                    let request_index = agreement.next_request_index()?;
-                    Requests::<T>::insert(agreement_id, request_index, content_id.clone());
-                    Ok(request_index)
-                })?;
-
-            Self::deposit_event(Event::<T>::RequestCreated {
-                agreement_id,
-                request_index,
-                content_id,
-            });
-            Ok(())
+            Self::ensure_can_request(agreement_id, &requester)?;
+            let request = RequestDetails::new(requester, content_id, params).with_tip(tip);
+            Self::create_request(agreement_id, request)
         }
 
+        /// The response to a request of a pipeline stage is forwarded as a request of the next
+        /// stage.
         #[pallet::call_index(1)]
-        #[pallet::weight(T::WeightInfo::response_create())]
+        #[pallet::weight(T::WeightInfo::response_create()
+            .saturating_add(T::WeightInfo::request_create()))]
         pub fn response_create(
             origin: OriginFor<T>,
             agreement_id: T::AgreementId,
             #[pallet::compact] request_index: RequestsUsize,
             content_id: T::ContentId,
+            output_hash: Option<T::Hash>,
+            #[pallet::compact] units: UnitsUsize,
+        ) -> DispatchResult {
+            let responder = ensure_signed(origin)?;
+
+            let mut agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            let provider =
+                agreement.responding_provider(&responder).ok_or(Error::<T>::AgreementInvalid)?;
+            let request = Requests::<T>::get(agreement_id, request_index)
+                .ok_or(Error::<T>::RequestNotFound)?;
+            ensure!(agreement.is_in_time(request.created_at), Error::<T>::LatencyExceeded);
+            ensure!(request.allows_units(units), Error::<T>::BatchItemsExceeded);
+
+            let response = ResponseDetails::new(provider.clone(), content_id, output_hash, units)
+                .with_deposit(responder);
+            response.hold_deposit()?;
+            if agreement.is_redundant() {
+                Self::create_replica_response(
+                    agreement_id,
+                    &mut agreement,
+                    request_index,
+                    provider,
+                    response,
+                    &request,
+                )?;
+            } else {
+                ensure!(
+                    !Response::<T>::exists(agreement_id, request_index),
+                    Error::<T>::ResponseAlreadyExists
+                );
+
+                Capacity::<T>::release(agreement_id, &provider);
+                agreement.pay_provider(&provider, units)?;
+                agreement.pay_royalty(units)?;
+                request.pay_tip(&provider)?;
+                agreement.record_response();
+                T::ExecutionObserver::on_response(
+                    &provider,
+                    response.created_at.saturating_sub(request.created_at),
+                );
+                Self::accept_response(agreement_id, &agreement, request_index, response);
+            }
+
+            Agreements::<T>::insert(agreement_id, agreement);
+            Ok(())
+        }
+
+        /// Executed by a consumer to close an agreement and release the unused prepayment.
+        /// Agreements are not closed once all their requests are responded to, so that they can
+        /// still be extended. Providers can close agreements no requests are left in, so that an
+        /// inactive consumer does not keep them from deregistering.
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::agreement_close())]
+        pub fn agreement_close(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            let now = frame_system::Pallet::<T>::block_number();
+            if !agreement.is_consumer(&who) {
+                ensure!(
+                    agreement.providers().any(|provider| *provider == who),
+                    Error::<T>::AgreementInvalid
+                );
+                ensure!(agreement.is_exhausted(now), Error::<T>::AgreementActive);
+            }
+            ensure!(!agreement.has_pending_requests(), Error::<T>::AgreementHasPendingRequests);
+            ensure!(!agreement.is_subscription_active(now), Error::<T>::SubscriptionActive);
+
+            Self::close_agreement(agreement_id, agreement)
+        }
+
+        /// Executed by a consumer to transfer an agreement, along with its remaining prepayment,
+        /// to another consumer.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::agreement_transfer())]
+        pub fn agreement_transfer(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            new_consumer: T::AccountId,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            ensure!(agreement.is_consumer(&consumer), Error::<T>::AgreementInvalid);
+
+            if T::TransferApprovalRequired::get() {
+                PendingTransfers::<T>::insert(agreement_id, &new_consumer);
+                Self::deposit_event(Event::<T>::AgreementTransferRequested {
+                    agreement_id,
+                    new_consumer,
+                });
+                Ok(())
+            } else {
+                Self::transfer_agreement(agreement_id, agreement, new_consumer)
+            }
+        }
+
+        /// Executed by a provider to approve a pending agreement transfer.
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::agreement_transfer_approve())]
+        pub fn agreement_transfer_approve(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
         ) -> DispatchResult {
             let provider = ensure_signed(origin)?;
 
             let agreement =
                 Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
             ensure!(agreement.is_provider(&provider), Error::<T>::AgreementInvalid);
-            ensure!(agreement.request_exists(request_index), Error::<T>::RequestNotFound);
-            ensure!(
-                !Response::<T>::exists(agreement_id, request_index),
-                Error::<T>::ResponseAlreadyExists
-            );
+            let new_consumer =
+                PendingTransfers::<T>::take(agreement_id).ok_or(Error::<T>::TransferNotFound)?;
 
-            agreement.transfer_payments()?;
-            Responses::<T>::insert(agreement_id, request_index, content_id.clone());
+            Self::transfer_agreement(agreement_id, agreement, new_consumer)
+        }
 
-            Self::deposit_event(Event::<T>::ResponseCreated {
-                agreement_id,
-                request_index,
-                content_id,
-            });
+        /// Executed by a consumer to allow another account to create requests on an agreement.
+        /// Updates the quota if the account is already a delegate.
+        #[pallet::call_index(5)]
+        #[pallet::weight(T::WeightInfo::delegate_add())]
+        pub fn delegate_add(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            delegate: T::AccountId,
+            quota: Option<RequestsUsize>,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            ensure!(agreement.is_consumer(&consumer), Error::<T>::AgreementInvalid);
+            Delegate::<T>::insert(agreement_id, delegate.clone(), quota)?;
+
+            Self::deposit_event(Event::<T>::DelegateAdded { agreement_id, delegate, quota });
             Ok(())
         }
-    }
-}
 
-impl<T: Config> AgreementManagement for Pallet<T> {
-    type AccountId = T::AccountId;
-    // Use order ID as the agreement ID for new agreements.
-    type OrderId = T::AgreementId;
-    type ModelId = T::ModelId;
-    type Balance = BalanceOf<T>;
+        /// Executed by a consumer to revoke a delegate of an agreement.
+        #[pallet::call_index(6)]
+        #[pallet::weight(T::WeightInfo::delegate_remove())]
+        pub fn delegate_remove(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            delegate: T::AccountId,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
 
-    fn create_agreement(
-        consumer: Self::AccountId,
-        provider: Self::AccountId,
-        order_id: Self::OrderId,
-        model_id: Self::ModelId,
-        price_per_request: Self::Balance,
-        requests_total: RequestsUsize,
-    ) -> DispatchResult {
-        let royalty_per_request = T::RoyaltyResolver::get_royalty(&model_id)
-            .map(|(_, royalty)| royalty)
-            .unwrap_or(Self::Balance::zero());
+            let agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            ensure!(agreement.is_consumer(&consumer), Error::<T>::AgreementInvalid);
+            Delegate::<T>::remove(agreement_id, &delegate)?;
+
+            Self::deposit_event(Event::<T>::DelegateRemoved { agreement_id, delegate });
+            Ok(())
+        }
+
+        /// Executed by a provider to allow another account to create responses on all its
+        /// agreements. Payments still go to the provider.
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::operator_add())]
+        pub fn operator_add(origin: OriginFor<T>, operator: T::AccountId) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Operator::<T>::insert(&provider, operator.clone())?;
+
+            Self::deposit_event(Event::<T>::OperatorAdded { provider, operator });
+            Ok(())
+        }
+
+        /// Executed by a provider to revoke an operator.
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::operator_remove())]
+        pub fn operator_remove(origin: OriginFor<T>, operator: T::AccountId) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            Operator::<T>::remove(&provider, &operator)?;
+
+            Self::deposit_event(Event::<T>::OperatorRemoved { provider, operator });
+            Ok(())
+        }
+
+        /// Executed by a verifier to challenge a response within the challenge window, claiming
+        /// that re-executing the request on the same model yields a different output.
+        /// The verifier's stake is held until the challenge is resolved, while a provider losing
+        /// the challenge is slashed from its bond.
+        #[pallet::call_index(9)]
+        #[pallet::weight(T::WeightInfo::challenge_create())]
+        pub fn challenge_create(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            #[pallet::compact] request_index: RequestsUsize,
+            content_id: T::ContentId,
+        ) -> DispatchResult {
+            let challenger = ensure_signed(origin)?;
+
+            Self::create_challenge(agreement_id, request_index, challenger, content_id, None)
+        }
+
+        /// Executed by the resolver origin to resolve a challenge. If the challenge is `upheld`,
+        /// the provider's bond is slashed to the challenger, otherwise the challenger's stake is
+        /// slashed to the provider.
+        #[pallet::call_index(10)]
+        #[pallet::weight(T::WeightInfo::challenge_resolve())]
+        pub fn challenge_resolve(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            #[pallet::compact] request_index: RequestsUsize,
+            upheld: bool,
+        ) -> DispatchResult {
+            T::ChallengeResolverOrigin::ensure_origin(origin)?;
+
+            let challenge = Challenges::<T>::take(agreement_id, request_index)
+                .ok_or(Error::<T>::ChallengeNotFound)?;
+            let (winner, loser, slashed) = challenge.settle(upheld)?;
+            if upheld {
+                T::ExecutionObserver::on_challenge_lost(&loser);
+            }
+
+            Self::deposit_event(Event::<T>::ChallengeResolved {
+                agreement_id,
+                request_index,
+                winner,
+                loser,
+                slashed,
+            });
+            Ok(())
+        }
+
+        /// Pays the provider for the elapsed periods of a subscription, and closes the agreement
+        /// once the subscription has ended. Can be executed by anyone.
+        #[pallet::call_index(11)]
+        #[pallet::weight(T::WeightInfo::subscription_settle())]
+        pub fn subscription_settle(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            let mut agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            ensure!(agreement.subscription.is_some(), Error::<T>::SubscriptionInvalid);
+
+            let now = frame_system::Pallet::<T>::block_number();
+            if agreement.is_subscription_active(now) {
+                Self::settle_subscription(agreement_id, &mut agreement, now)?;
+                Agreements::<T>::insert(agreement_id, agreement);
+                Ok(())
+            } else {
+                Self::close_agreement(agreement_id, agreement)
+            }
+        }
+
+        /// Executed by a consumer or a provider to terminate a subscription. The subscription
+        /// ends at the first period boundary after the notice period.
+        #[pallet::call_index(12)]
+        #[pallet::weight(T::WeightInfo::subscription_terminate())]
+        pub fn subscription_terminate(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let ends_at = Agreements::<T>::try_mutate(
+                agreement_id,
+                |agreement| -> Result<_, DispatchError> {
+                    let agreement = agreement.as_mut().ok_or(Error::<T>::AgreementNotFound)?;
+                    ensure!(
+                        agreement.is_consumer(&who) || agreement.is_provider(&who),
+                        Error::<T>::AgreementInvalid
+                    );
+                    let subscription =
+                        agreement.subscription.as_mut().ok_or(Error::<T>::SubscriptionInvalid)?;
+                    let now = frame_system::Pallet::<T>::block_number();
+                    ensure!(!subscription.has_ended(now), Error::<T>::SubscriptionEnded);
+
+                    Ok(subscription.terminate(now))
+                },
+            )?;
+
+            Self::deposit_event(Event::<T>::SubscriptionTerminated { agreement_id, ends_at });
+            Ok(())
+        }
+
+        /// Executed by a consumer to extend an agreement by `extra_requests` at the same price,
+        /// holding the additional prepayment. Large extensions have to be approved by a provider.
+        #[pallet::call_index(13)]
+        #[pallet::weight(T::WeightInfo::agreement_extend())]
+        pub fn agreement_extend(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            #[pallet::compact] extra_requests: RequestsUsize,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            ensure!(agreement.is_consumer(&consumer), Error::<T>::AgreementInvalid);
+            ensure!(
+                extra_requests > 0 && agreement.subscription.is_none(),
+                Error::<T>::AgreementInvalid
+            );
+
+            if extra_requests > T::ExtensionApprovalThreshold::get() {
+                PendingExtensions::<T>::insert(agreement_id, extra_requests);
+                Self::deposit_event(Event::<T>::AgreementExtensionRequested {
+                    agreement_id,
+                    extra_requests,
+                });
+                Ok(())
+            } else {
+                Self::extend_agreement(agreement_id, agreement, extra_requests)
+            }
+        }
+
+        /// Executed by a provider to approve a pending agreement extension.
+        #[pallet::call_index(14)]
+        #[pallet::weight(T::WeightInfo::agreement_extend_approve())]
+        pub fn agreement_extend_approve(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            ensure!(agreement.is_provider(&provider), Error::<T>::AgreementInvalid);
+            let extra_requests =
+                PendingExtensions::<T>::take(agreement_id).ok_or(Error::<T>::ExtensionNotFound)?;
+
+            Self::extend_agreement(agreement_id, agreement, extra_requests)
+        }
+
+        /// Refunds the payments for a request none of the providers has responded to within the
+        /// maximum latency of the agreement, or the request timeout if not set, and prunes it.
+        /// Can be executed by anyone.
+        #[pallet::call_index(15)]
+        #[pallet::weight(T::WeightInfo::request_expire())]
+        pub fn request_expire(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            #[pallet::compact] request_index: RequestsUsize,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            let mut agreement =
+                Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+            let request = Requests::<T>::get(agreement_id, request_index)
+                .ok_or(Error::<T>::RequestNotFound)?;
+            ensure!(
+                !Response::<T>::exists(agreement_id, request_index),
+                Error::<T>::ResponseAlreadyExists
+            );
+            let responded: Vec<_> =
+                ReplicaResponses::<T>::iter_key_prefix((agreement_id, request_index)).collect();
+            // A redundant request all the providers responded to is settled already.
+            ensure!(
+                (responded.len() as RequestsUsize) < agreement.providers_count(),
+                Error::<T>::ResponseAlreadyExists
+            );
+            ensure!(
+                frame_system::Pallet::<T>::block_number()
+                    > agreement.expires_at(request.created_at),
+                Error::<T>::RequestActive
+            );
+
+            for provider in agreement.providers() {
+                if !responded.contains(provider) {
+                    T::ExecutionObserver::on_request_expired(provider);
+                    Capacity::<T>::release(agreement_id, provider);
+                }
+            }
+            for _ in 0..agreement.providers_count() {
+                agreement.refund_provider_payment()?;
+            }
+            agreement.refund_royalty()?;
+            agreement.record_response();
+            Self::prune_request(agreement_id, request_index);
+
+            Self::deposit_event(Event::<T>::RequestExpired { agreement_id, request_index });
+
+            Agreements::<T>::insert(agreement_id, agreement);
+            Ok(())
+        }
+
+        /// Creates a batch request on an agreement priced per unit, referencing the dataset
+        /// manifest `manifest_id` listing `items` items. The provider responds with a manifest of
+        /// the outputs, and is paid per item processed, as declared by the response units.
+        #[pallet::call_index(16)]
+        #[pallet::weight(T::WeightInfo::request_create())]
+        pub fn batch_request_create(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            manifest_id: T::ContentId,
+            #[pallet::compact] items: UnitsUsize,
+        ) -> DispatchResult {
+            let requester = ensure_signed(origin)?;
+
+            let agreement = Self::ensure_can_request(agreement_id, &requester)?;
+            ensure!(
+                items > 0 && agreement.max_units_per_request.is_some_and(|max| items <= max),
+                Error::<T>::BatchInvalid
+            );
+
+            let request = RequestDetails::new(requester, manifest_id, None).with_batch(items);
+            Self::create_request(agreement_id, request)
+        }
+
+        /// Executed by a verifier, typically the consumer sampling the output manifest of a batch
+        /// response, to challenge the output of the `item` of the batch within the challenge
+        /// window, claiming that re-executing it yields `content_id`.
+        #[pallet::call_index(17)]
+        #[pallet::weight(T::WeightInfo::challenge_create())]
+        pub fn batch_item_challenge(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            #[pallet::compact] request_index: RequestsUsize,
+            #[pallet::compact] item: UnitsUsize,
+            content_id: T::ContentId,
+        ) -> DispatchResult {
+            let challenger = ensure_signed(origin)?;
+
+            let request = Requests::<T>::get(agreement_id, request_index)
+                .ok_or(Error::<T>::RequestNotFound)?;
+            ensure!(request.batch_items.is_some(), Error::<T>::ChallengeInvalid);
+            let response = Responses::<T>::get(agreement_id, request_index)
+                .ok_or(Error::<T>::ResponseNotFound)?;
+            // Only the items the provider declares having processed are paid, and challengeable.
+            ensure!(item < response.units, Error::<T>::ChallengeInvalid);
+
+            Self::create_challenge(agreement_id, request_index, challenger, content_id, Some(item))
+        }
+
+        /// Executed by a consumer, e.g. before handing its keys to an automated client, to limit
+        /// the prepayments of its agreements in `asset`, or in the native currency if none.
+        /// Lowered limits apply at once, while raised limits only apply after
+        /// `T::SpendingLimitDelay`, leaving time to react to a compromised key.
+        #[pallet::call_index(18)]
+        #[pallet::weight(T::WeightInfo::spending_limits_set())]
+        pub fn spending_limits_set(
+            origin: OriginFor<T>,
+            asset: Option<AssetIdOf<T>>,
+            limits: SpendingLimits<T>,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let (applied, effective_at) =
+                SpendingLimit::<T>::set(&consumer, &asset, limits.clone());
+            Self::deposit_event(Event::<T>::SpendingLimitsSet {
+                consumer: consumer.clone(),
+                asset: asset.clone(),
+                limits: applied,
+            });
+            if let Some(effective_at) = effective_at {
+                Self::deposit_event(Event::<T>::SpendingLimitsScheduled {
+                    consumer,
+                    asset,
+                    limits,
+                    effective_at,
+                });
+            }
+            Ok(())
+        }
+
+        /// Releases the challenger's stake of a challenge that has not been resolved within the
+        /// challenge expiry, neither party being slashed. Can be executed by anyone.
+        #[pallet::call_index(19)]
+        #[pallet::weight(T::WeightInfo::challenge_expire())]
+        pub fn challenge_expire(
+            origin: OriginFor<T>,
+            agreement_id: T::AgreementId,
+            #[pallet::compact] request_index: RequestsUsize,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            let challenge = Challenges::<T>::get(agreement_id, request_index)
+                .ok_or(Error::<T>::ChallengeNotFound)?;
+            ensure!(challenge.is_expired(), Error::<T>::ChallengeActive);
+
+            challenge.release_stake()?;
+            Challenges::<T>::remove(agreement_id, request_index);
+
+            Self::deposit_event(Event::<T>::ChallengeExpired { agreement_id, request_index });
+            Ok(())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Checks that the requester can create requests on the agreement, using the quota of a
+    /// delegate.
+    fn ensure_can_request(
+        agreement_id: T::AgreementId,
+        requester: &T::AccountId,
+    ) -> Result<AgreementDetails<T>, DispatchError> {
+        let agreement = Agreements::<T>::get(agreement_id).ok_or(Error::<T>::AgreementNotFound)?;
+        // Requests of the later stages of a pipeline are the responses of the previous stage.
+        ensure!(agreement.previous_stage.is_none(), Error::<T>::RequestNotAllowed);
+        if !agreement.is_consumer(requester) {
+            Delegate::<T>::use_request(agreement_id, requester)?;
+        }
+        Ok(agreement)
+    }
+
+    fn create_request(agreement_id: T::AgreementId, request: RequestDetails<T>) -> DispatchResult {
+        let request_index =
+            Agreements::<T>::try_mutate(agreement_id, |agreement| -> Result<_, Error<T>> {
+                let agreement = agreement.as_mut().ok_or(Error::<T>::AgreementNotFound)?;
+                for provider in agreement.providers() {
+                    Capacity::<T>::reserve(agreement_id, provider)?;
+                }
+                agreement.next_request_index()
+            })?;
+
+        request.hold_deposit()?;
+        request.hold_tip()?;
+        let content_id = request.content_id.clone();
+        let params = request.params.clone();
+        Requests::<T>::insert(agreement_id, request_index, request);
+
+        Self::deposit_event(Event::<T>::RequestCreated {
+            agreement_id,
+            request_index,
+            content_id,
+            params,
+        });
+        Ok(())
+    }
+
+    fn accept_response(
+        agreement_id: T::AgreementId,
+        agreement: &AgreementDetails<T>,
+        request_index: RequestsUsize,
+        response: ResponseDetails<T>,
+    ) {
+        let content_id = response.content_id.clone();
+        Response::<T>::insert(agreement_id, request_index, response);
+
+        Self::deposit_event(Event::<T>::ResponseCreated {
+            agreement_id,
+            request_index,
+            content_id: content_id.clone(),
+        });
+
+        if let Some(next_stage) = agreement.next_stage {
+            Self::forward_response(next_stage, content_id);
+        }
+    }
+
+    /// Creates a request of the next stage of a pipeline from an accepted response, on behalf of
+    /// its consumer. Failing to do so does not fail the response, which is paid regardless.
+    fn forward_response(next_stage: T::AgreementId, content_id: T::ContentId) {
+        let forwarded = with_storage_layer(|| -> DispatchResult {
+            let next_agreement =
+                Agreements::<T>::get(next_stage).ok_or(Error::<T>::AgreementNotFound)?;
+            let request = RequestDetails::new(next_agreement.consumer, content_id.clone(), None);
+            Self::create_request(next_stage, request)
+        });
+
+        if forwarded.is_err() {
+            Self::deposit_event(Event::<T>::ResponseNotForwarded {
+                agreement_id: next_stage,
+                content_id,
+            });
+        }
+    }
+
+    fn create_challenge(
+        agreement_id: T::AgreementId,
+        request_index: RequestsUsize,
+        challenger: T::AccountId,
+        content_id: T::ContentId,
+        item: Option<UnitsUsize>,
+    ) -> DispatchResult {
+        let response =
+            Responses::<T>::get(agreement_id, request_index).ok_or(Error::<T>::ResponseNotFound)?;
+        ensure!(response.is_challengeable(), Error::<T>::ChallengeWindowElapsed);
+        ensure!(response.provider != challenger, Error::<T>::ChallengeInvalid);
+        ensure!(
+            !Challenges::<T>::contains_key(agreement_id, request_index),
+            Error::<T>::ChallengeAlreadyExists
+        );
 
+        let challenge =
+            ChallengeDetails::new(challenger.clone(), response.provider, content_id.clone())
+                .with_item(item);
+        challenge.hold_stake()?;
+        Challenges::<T>::insert(agreement_id, request_index, challenge);
+
+        Self::deposit_event(Event::<T>::ChallengeCreated {
+            agreement_id,
+            request_index,
+            challenger,
+            content_id,
+        });
+        Ok(())
+    }
+
+    /// Records a response of one of the providers of a redundant agreement. The request is settled
+    /// as soon as the quorum agrees on the output, or once all the providers have responded. Late
+    /// providers are settled as they respond, until the consumer closes the agreement, refunding
+    /// their payments.
+    fn create_replica_response(
+        agreement_id: T::AgreementId,
+        agreement: &mut AgreementDetails<T>,
+        request_index: RequestsUsize,
+        provider: Provider<T>,
+        response: ResponseDetails<T>,
+        request: &RequestDetails<T>,
+    ) -> DispatchResult {
+        let request_key = (agreement_id, request_index);
+        ensure!(
+            !ReplicaResponses::<T>::contains_key(request_key, &provider),
+            Error::<T>::ResponseAlreadyExists
+        );
+        ReplicaResponses::<T>::insert(request_key, &provider, &response);
+
+        Self::deposit_event(Event::<T>::ReplicaResponseCreated {
+            agreement_id,
+            request_index,
+            provider: provider.clone(),
+            content_id: response.content_id.clone(),
+        });
+
+        // The quorum has been reached before, so only this response is left to settle.
+        if let Some(accepted) = Responses::<T>::get(agreement_id, request_index) {
+            return Self::settle_replica_response(
+                agreement_id,
+                agreement,
+                request_index,
+                &provider,
+                &response,
+                &accepted.output_hash,
+                request.created_at,
+            );
+        }
+
+        Capacity::<T>::release(agreement_id, &provider);
+        let responses: Vec<_> = ReplicaResponses::<T>::iter_prefix(request_key).collect();
+        let matching_count = responses
+            .iter()
+            .filter(|(_, replica_response)| replica_response.output_hash == response.output_hash)
+            .count() as RequestsUsize;
+
+        if matching_count >= agreement.quorum {
+            for (replica, replica_response) in responses.iter() {
+                Self::settle_replica_response(
+                    agreement_id,
+                    agreement,
+                    request_index,
+                    replica,
+                    replica_response,
+                    &response.output_hash,
+                    request.created_at,
+                )?;
+            }
+            agreement.pay_royalty(response.units)?;
+            request.pay_tip(&response.provider)?;
+            agreement.record_response();
+            // Late responses are still settled, but the request can't expire anymore.
+            Self::release_capacity(request_key, agreement);
+            // The deposit is released along with the replica response.
+            Self::accept_response(
+                agreement_id,
+                agreement,
+                request_index,
+                response.without_deposit(),
+            );
+        } else if responses.len() as RequestsUsize == agreement.providers_count() {
+            for _ in responses.iter() {
+                agreement.refund_provider_payment()?;
+            }
+            agreement.refund_royalty()?;
+            agreement.record_response();
+            Request::<T>::schedule_prune(agreement_id, request_index);
+
+            Self::deposit_event(Event::<T>::QuorumNotReached { agreement_id, request_index });
+        }
+
+        Ok(())
+    }
+
+    /// Frees the capacity taken up by a request for the providers that have not responded to it.
+    fn release_capacity(
+        request_key: (T::AgreementId, RequestsUsize),
+        agreement: &AgreementDetails<T>,
+    ) {
+        for provider in agreement.providers() {
+            if !ReplicaResponses::<T>::contains_key(request_key, provider) {
+                Capacity::<T>::release(request_key.0, provider);
+            }
+        }
+    }
+
+    fn settle_replica_response(
+        agreement_id: T::AgreementId,
+        agreement: &mut AgreementDetails<T>,
+        request_index: RequestsUsize,
+        provider: &Provider<T>,
+        response: &ResponseDetails<T>,
+        accepted_output_hash: &T::Hash,
+        requested_at: BlockNumberFor<T>,
+    ) -> DispatchResult {
+        if response.output_hash == *accepted_output_hash {
+            agreement.pay_provider(provider, response.units)?;
+            T::ExecutionObserver::on_response(
+                provider,
+                response.created_at.saturating_sub(requested_at),
+            );
+            Ok(())
+        } else {
+            agreement.refund_provider_payment()?;
+
+            Self::deposit_event(Event::<T>::ResponseDissented {
+                agreement_id,
+                request_index,
+                provider: provider.clone(),
+            });
+            Ok(())
+        }
+    }
+
+    fn insert_agreement(
+        agreement_id: T::AgreementId,
+        agreement: AgreementDetails<T>,
+    ) -> DispatchResult {
+        agreement.hold_consumer_prepayment()?;
+        Agreement::<T>::insert(agreement_id, agreement);
+
+        Self::deposit_event(Event::<T>::AgreementCreated { agreement_id });
+        Ok(())
+    }
+
+    /// Account escrowing the prepayments a consumer makes in assets, as those cannot be held.
+    pub fn escrow_account(consumer: &Consumer<T>) -> T::AccountId {
+        T::PalletId::get().into_sub_account_truncating(consumer)
+    }
+
+    /// Requests `provider` has yet to respond to, the highest tips first, then the oldest.
+    pub fn pending_requests(
+        provider: &Provider<T>,
+    ) -> Vec<PendingRequest<T::AgreementId, BalanceOf<T>, BlockNumberFor<T>>> {
+        let mut pending_requests: Vec<_> = ProviderAgreements::<T>::iter_key_prefix(provider)
+            .flat_map(|agreement_id| {
+                // Providers of a redundant agreement are paid for late responses as well, so a
+                // request is pending until the provider itself has responded.
+                let is_redundant = Agreements::<T>::get(agreement_id)
+                    .is_some_and(|agreement| agreement.is_redundant());
+                Requests::<T>::iter_prefix(agreement_id)
+                    .filter(move |(request_index, _)| {
+                        if is_redundant {
+                            !ReplicaResponses::<T>::contains_key(
+                                (agreement_id, *request_index),
+                                provider,
+                            )
+                        } else {
+                            !Responses::<T>::contains_key(agreement_id, request_index)
+                        }
+                    })
+                    .map(move |(request_index, request)| PendingRequest {
+                        agreement_id,
+                        request_index,
+                        tip: request.tip,
+                        created_at: request.created_at,
+                    })
+            })
+            .collect();
+
+        pending_requests
+            .sort_by(|a, b| b.tip.cmp(&a.tip).then_with(|| a.created_at.cmp(&b.created_at)));
+        pending_requests
+    }
+
+    fn royalty_per_request(model_id: &T::ModelId) -> BalanceOf<T> {
+        T::RoyaltyResolver::get_royalty(model_id)
+            .map(|(_, royalty)| royalty)
+            .unwrap_or(BalanceOf::<T>::zero())
+    }
+
+    fn transfer_agreement(
+        agreement_id: T::AgreementId,
+        mut agreement: AgreementDetails<T>,
+        new_consumer: Consumer<T>,
+    ) -> DispatchResult {
+        agreement.transfer_prepayment(&new_consumer)?;
+        Agreement::<T>::set_consumer(agreement_id, &mut agreement, new_consumer.clone());
+        Agreements::<T>::insert(agreement_id, agreement);
+
+        Self::deposit_event(Event::<T>::AgreementTransferred {
+            agreement_id,
+            consumer: new_consumer,
+        });
+        Ok(())
+    }
+
+    fn extend_agreement(
+        agreement_id: T::AgreementId,
+        mut agreement: AgreementDetails<T>,
+        extra_requests: RequestsUsize,
+    ) -> DispatchResult {
+        agreement.extend(extra_requests)?;
+        let requests_total = agreement.requests_total;
+        Agreements::<T>::insert(agreement_id, agreement);
+
+        Self::deposit_event(Event::<T>::AgreementExtended { agreement_id, requests_total });
+        Ok(())
+    }
+
+    fn settle_subscription(
+        agreement_id: T::AgreementId,
+        agreement: &mut AgreementDetails<T>,
+        now: BlockNumberFor<T>,
+    ) -> DispatchResult {
+        let periods = agreement.settle_subscription(now)?;
+        if periods > 0 {
+            Self::deposit_event(Event::<T>::SubscriptionSettled { agreement_id, periods });
+        }
+        Ok(())
+    }
+
+    fn close_agreement(
+        agreement_id: T::AgreementId,
+        mut agreement: AgreementDetails<T>,
+    ) -> DispatchResult {
+        Self::settle_subscription(
+            agreement_id,
+            &mut agreement,
+            frame_system::Pallet::<T>::block_number(),
+        )?;
+        agreement.release_unused_prepayment()?;
+        for provider in agreement.providers() {
+            Capacity::<T>::release_agreement(agreement_id, provider);
+        }
+        Agreement::<T>::remove(agreement_id, &agreement);
+        Agreement::<T>::schedule_prune(agreement_id);
+
+        Self::deposit_event(Event::<T>::AgreementClosed { agreement_id });
+        Ok(())
+    }
+
+    /// Prunes requests of closed agreements first, then the responded requests which retention
+    /// period has elapsed, for as long as the weight limit allows. Closed agreements which
+    /// challenge window has elapsed are queued for pruning along the way.
+    fn prune(now: BlockNumberFor<T>, limit: Weight) -> Weight {
+        let mut meter = WeightMeter::with_limit(limit);
+        let prune_weight = T::WeightInfo::prune_request();
+
+        while meter.can_consume(prune_weight) {
+            let Some(agreement_id) = ClosedAgreements::<T>::iter_keys().next() else {
+                break;
+            };
+            meter.consume(prune_weight);
+
+            match Requests::<T>::iter_key_prefix(agreement_id).next() {
+                Some(request_index) => Self::prune_request(agreement_id, request_index),
+                None => ClosedAgreements::<T>::remove(agreement_id),
+            }
+        }
+
+        if meter.try_consume(T::DbWeight::get().reads_writes(1, 1)).is_err() {
+            return meter.consumed();
+        }
+
+        let mut cursor = PruneCursor::<T>::get().unwrap_or(now);
+        while cursor <= now && meter.can_consume(prune_weight) {
+            meter.consume(prune_weight);
+
+            if let Some(agreement_id) = ClosingSchedule::<T>::iter_key_prefix(cursor).next() {
+                ClosingSchedule::<T>::remove(cursor, agreement_id);
+                ClosedAgreements::<T>::insert(agreement_id, ());
+                continue;
+            }
+
+            match PruneSchedule::<T>::iter_key_prefix(cursor).next() {
+                Some((agreement_id, request_index)) => {
+                    PruneSchedule::<T>::remove(cursor, (agreement_id, request_index));
+                    Self::prune_request(agreement_id, request_index);
+                },
+                None => cursor.saturating_inc(),
+            }
+        }
+        PruneCursor::<T>::put(cursor);
+
+        meter.consumed()
+    }
+
+    fn prune_request(agreement_id: T::AgreementId, request_index: RequestsUsize) {
+        if let Some(request) = Requests::<T>::take(agreement_id, request_index) {
+            let response = Responses::<T>::take(agreement_id, request_index);
+            // Releasing what is left on hold is the best we can do here, so failures are only
+            // reported.
+            let report = |result: DispatchResult| {
+                if let Err(error) = result {
+                    Self::deposit_event(Event::<T>::PruneReleaseFailed {
+                        agreement_id,
+                        request_index,
+                        error,
+                    });
+                }
+            };
+            report(request.release_deposit());
+            match &response {
+                Some(response) => report(response.release_deposit()),
+                // The tip has been paid along with the accepted response otherwise.
+                None => report(request.release_tip()),
+            }
+            for (_, replica_response) in
+                ReplicaResponses::<T>::drain_prefix((agreement_id, request_index))
+            {
+                report(replica_response.release_deposit());
+            }
+            let response_content_id = response.map(|response| response.content_id);
+
+            Self::deposit_event(Event::<T>::RequestPruned {
+                agreement_id,
+                request_index,
+                request_content_id: request.content_id,
+                response_content_id,
+            });
+        }
+    }
+}
+
+impl<T: Config> AgreementManagement for Pallet<T> {
+    type AccountId = T::AccountId;
+    // Use order ID as the agreement ID for new agreements.
+    type OrderId = T::AgreementId;
+    type ModelId = T::ModelId;
+    type Balance = BalanceOf<T>;
+    type BlockNumber = BlockNumberFor<T>;
+    type AssetId = AssetIdOf<T>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_agreement(
+        consumer: Self::AccountId,
+        provider: Self::AccountId,
+        order_id: Self::OrderId,
+        model_id: Self::ModelId,
+        price_per_request: Self::Balance,
+        requests_total: RequestsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult {
+        let royalty_per_request = Self::royalty_per_request(&model_id);
         let agreement = AgreementDetails::new(
             consumer,
             provider,
@@ -258,11 +1684,155 @@ impl<T: Config> AgreementManagement for Pallet<T> {
             price_per_request,
             royalty_per_request,
             requests_total,
+        )
+        .with_asset(asset)
+        .with_requirements(requirements)
+        .with_model_version(model_version);
+
+        Self::insert_agreement(order_id, agreement)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_redundant_agreement(
+        consumer: Self::AccountId,
+        providers: &[Self::AccountId],
+        order_id: Self::OrderId,
+        model_id: Self::ModelId,
+        price_per_request: Self::Balance,
+        requests_total: RequestsUsize,
+        quorum: RequestsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult {
+        let (provider, replicas) = providers.split_first().ok_or(Error::<T>::ReplicationInvalid)?;
+        let replicas =
+            BoundedVec::try_from(replicas.to_vec()).map_err(|_| Error::<T>::ReplicationInvalid)?;
+
+        let royalty_per_request = Self::royalty_per_request(&model_id);
+        let agreement = AgreementDetails::new(
+            consumer,
+            provider.clone(),
+            model_id,
+            price_per_request,
+            royalty_per_request,
+            requests_total,
+        )
+        .with_replicas(replicas, quorum)
+        .with_asset(asset)
+        .with_requirements(requirements)
+        .with_model_version(model_version);
+        ensure!(agreement.is_valid(), Error::<T>::ReplicationInvalid);
+
+        Self::insert_agreement(order_id, agreement)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_subscription_agreement(
+        consumer: Self::AccountId,
+        provider: Self::AccountId,
+        order_id: Self::OrderId,
+        model_id: Self::ModelId,
+        price_per_period: Self::Balance,
+        period: Self::BlockNumber,
+        periods_total: u32,
+        requests_per_period: Option<RequestsUsize>,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult {
+        let subscription = SubscriptionDetails::new(
+            price_per_period,
+            Self::royalty_per_request(&model_id),
+            period,
+            periods_total,
+            requests_per_period,
         );
-        agreement.hold_consumer_prepayment()?;
-        Agreement::<T>::insert(order_id, agreement);
+        ensure!(subscription.is_valid(), Error::<T>::SubscriptionInvalid);
+
+        let agreement =
+            AgreementDetails::new(consumer, provider, model_id, Zero::zero(), Zero::zero(), 0)
+                .with_subscription(subscription)
+                .with_asset(asset)
+                .with_requirements(requirements)
+                .with_model_version(model_version);
+
+        Self::insert_agreement(order_id, agreement)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_metered_agreement(
+        consumer: Self::AccountId,
+        provider: Self::AccountId,
+        order_id: Self::OrderId,
+        model_id: Self::ModelId,
+        price_per_unit: Self::Balance,
+        requests_total: RequestsUsize,
+        max_units_per_request: UnitsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult {
+        let royalty_per_unit = Self::royalty_per_request(&model_id);
+        let agreement = AgreementDetails::new(
+            consumer,
+            provider,
+            model_id,
+            price_per_unit,
+            royalty_per_unit,
+            requests_total,
+        )
+        .with_metering(max_units_per_request)
+        .with_asset(asset)
+        .with_requirements(requirements)
+        .with_model_version(model_version);
+
+        Self::insert_agreement(order_id, agreement)
+    }
+
+    fn create_pipeline_agreements(
+        consumer: Self::AccountId,
+        stages: &[PipelineStage<Self::AccountId, Self::OrderId, Self::ModelId, Self::Balance>],
+        requests_total: RequestsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+    ) -> DispatchResult {
+        ensure!(stages.len() > 1, Error::<T>::PipelineInvalid);
 
-        Self::deposit_event(Event::<T>::AgreementCreated { agreement_id: order_id });
+        let mut previous_stage = None;
+        for (index, stage) in stages.iter().enumerate() {
+            let next_stage = stages.get(index + 1).map(|next| next.order_id);
+            let royalty_per_request = Self::royalty_per_request(&stage.model_id);
+            let agreement = AgreementDetails::new(
+                consumer.clone(),
+                stage.provider.clone(),
+                stage.model_id.clone(),
+                stage.price_per_request,
+                royalty_per_request,
+                requests_total,
+            )
+            .with_pipeline(previous_stage, next_stage)
+            .with_asset(asset.clone())
+            .with_requirements(requirements.clone())
+            .with_model_version(stage.model_version);
+
+            Self::insert_agreement(stage.order_id, agreement)?;
+            previous_stage = Some(stage.order_id);
+        }
         Ok(())
     }
+
+    fn can_spend(
+        consumer: &Self::AccountId,
+        asset: &Option<Self::AssetId>,
+        amount: Self::Balance,
+    ) -> bool {
+        SpendingLimit::<T>::can_spend(consumer, asset, amount)
+    }
+}
+
+impl<T: Config> AgreementRegistry<T::AccountId> for Pallet<T> {
+    fn has_agreements(provider: &T::AccountId) -> bool {
+        ProviderAgreements::<T>::contains_prefix(provider)
+    }
 }
@@ -0,0 +1,87 @@
+use frame_support::{
+    migrations::VersionedMigration, storage_alias, traits::UncheckedOnRuntimeUpgrade,
+};
+
+use crate::*;
+
+/// Agreements as they were stored before the storage version was set, when requests and
+/// responses were stored as their content ID only.
+pub mod v0 {
+    use super::*;
+
+    #[derive(Encode, Decode)]
+    pub struct OldAgreementDetails<T: Config> {
+        pub consumer: Consumer<T>,
+        pub provider: Provider<T>,
+        pub model_id: T::ModelId,
+        pub price_per_request: BalanceOf<T>,
+        pub royalty_per_request: BalanceOf<T>,
+        #[codec(compact)]
+        pub requests_count: RequestsUsize,
+        #[codec(compact)]
+        pub requests_total: RequestsUsize,
+    }
+
+    #[storage_alias]
+    pub type Agreements<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, <T as Config>::AgreementId, OldAgreementDetails<T>>;
+}
+
+/// Translates the agreements, requests and responses to their current layout. Nothing was
+/// charged for storage before, so the translated requests and responses hold no deposit, and
+/// the responses can't be challenged anymore. Responded requests are scheduled for pruning.
+///
+/// Iterates over every agreement, request and response, so it is only meant for the networks
+/// created before storage versions were set, which have few of them.
+pub struct InnerMigrateV0ToV1<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV0ToV1<T> {
+    fn on_runtime_upgrade() -> Weight {
+        let mut translated = 0u64;
+
+        Requests::<T>::translate::<T::ContentId, _>(|agreement_id, _, content_id| {
+            translated.saturating_inc();
+            let consumer = v0::Agreements::<T>::get(agreement_id)?.consumer;
+            let mut request = RequestDetails::new(consumer, content_id, None);
+            request.deposit = Zero::zero();
+            Some(request)
+        });
+
+        Responses::<T>::translate::<T::ContentId, _>(|agreement_id, request_index, content_id| {
+            translated.saturating_inc();
+            let provider = v0::Agreements::<T>::get(agreement_id)?.provider;
+            let mut response = ResponseDetails::new(provider, content_id, None, 0);
+            response.created_at = Zero::zero();
+            Request::<T>::schedule_prune(agreement_id, request_index);
+            Some(response)
+        });
+
+        Agreements::<T>::translate::<v0::OldAgreementDetails<T>, _>(|agreement_id, old| {
+            translated.saturating_inc();
+            let responses_count = Responses::<T>::iter_key_prefix(agreement_id).count();
+            let mut agreement = AgreementDetails::new(
+                old.consumer,
+                old.provider,
+                old.model_id,
+                old.price_per_request,
+                old.royalty_per_request,
+                old.requests_total,
+            );
+            agreement.requests_count = old.requests_count;
+            agreement.responses_count = responses_count as RequestsUsize;
+            agreement.payments_count = responses_count as RequestsUsize;
+            Some(agreement)
+        });
+
+        T::DbWeight::get().reads_writes(translated.saturating_mul(2), translated.saturating_mul(2))
+    }
+}
+
+/// Migrates the pallet storage from version 0 to 1.
+pub type MigrateV0ToV1<T> = VersionedMigration<
+    0,
+    1,
+    InnerMigrateV0ToV1<T>,
+    Pallet<T>,
+    <T as frame_system::Config>::DbWeight,
+>;
@@ -1,15 +1,20 @@
 use crate as pallet_execution;
-use airo_primitives::payment::RoyaltyResolver;
+use airo_primitives::{
+    payment::RoyaltyResolver,
+    provider::{ExecutionObserver, ProviderBonds, ProviderRegistry},
+    RequestsUsize,
+};
 use frame_support::{
-    derive_impl,
-    traits::{ConstU16, ConstU32, ConstU64},
-    BoundedVec,
+    derive_impl, parameter_types,
+    traits::{fungible::Mutate, AsEnsureOriginWithArg, ConstU16, ConstU32, ConstU64},
+    BoundedVec, PalletId,
 };
+use frame_system::{EnsureRoot, EnsureSigned};
 use sp_core::hexdisplay::AsBytesRef;
 use sp_core::H256;
 use sp_runtime::{
     traits::{BlakeTwo256, IdentityLookup},
-    BuildStorage,
+    BuildStorage, Perbill,
 };
 
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -18,6 +23,7 @@ pub type Balance = u64;
 pub type ModelId = BoundedVec<u8, ConstU32<128>>;
 pub type AgreementId = u32;
 pub type ContentId = H256;
+pub type AssetId = u32;
 
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
@@ -25,6 +31,7 @@ frame_support::construct_runtime!(
     {
         System: frame_system,
         Balances: pallet_balances,
+        Assets: pallet_assets,
         AiroExecution: pallet_execution,
     }
 );
@@ -73,6 +80,14 @@ impl pallet_balances::Config for Test {
     type DoneSlashHandler = ();
 }
 
+#[derive_impl(pallet_assets::config_preludes::TestDefaultConfig)]
+impl pallet_assets::Config for Test {
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type Freezer = ();
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 pub struct AiroExecutionBenchmarkHelper;
 
@@ -106,35 +121,175 @@ impl RoyaltyResolver for TestRoyaltyResolver {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExecutionOutcome {
+    Response(AccountId, u64),
+    RequestExpired(AccountId),
+    ChallengeLost(AccountId),
+}
+
+pub struct TestExecutionObserver {}
+
+impl ExecutionObserver<AccountId, u64> for TestExecutionObserver {
+    fn on_response(provider: &AccountId, latency: u64) {
+        ExecutionOutcomes::mutate(|outcomes| {
+            outcomes.push(ExecutionOutcome::Response(*provider, latency))
+        });
+    }
+
+    fn on_request_expired(provider: &AccountId) {
+        ExecutionOutcomes::mutate(|outcomes| {
+            outcomes.push(ExecutionOutcome::RequestExpired(*provider))
+        });
+    }
+
+    fn on_challenge_lost(provider: &AccountId) {
+        ExecutionOutcomes::mutate(|outcomes| {
+            outcomes.push(ExecutionOutcome::ChallengeLost(*provider))
+        });
+    }
+}
+
+pub struct TestProviderRegistry;
+
+impl ProviderRegistry for TestProviderRegistry {
+    type AccountId = AccountId;
+    type ModelId = ModelId;
+
+    fn serves(_provider: &AccountId, _model_id: &ModelId) -> bool {
+        true
+    }
+
+    fn reputation(_provider: &AccountId) -> Perbill {
+        Perbill::one()
+    }
+
+    fn capacity(_provider: &AccountId) -> Option<RequestsUsize> {
+        ProviderCapacity::get()
+    }
+}
+
+/// Every provider shares the same bond, minted to the beneficiary when slashed.
+impl ProviderBonds<AccountId, Balance> for TestProviderRegistry {
+    fn slash(_provider: &AccountId, beneficiary: &AccountId, amount: Balance) -> Balance {
+        let slashed = amount.min(ProviderBond::get());
+        ProviderBond::set(ProviderBond::get() - slashed);
+        Balances::mint_into(beneficiary, slashed).unwrap();
+        slashed
+    }
+}
+
+parameter_types! {
+    pub static ProviderBond: Balance = PROVIDER_BOND;
+    pub static ExecutionOutcomes: Vec<ExecutionOutcome> = vec![];
+    pub static ProviderCapacity: Option<RequestsUsize> = None;
+    pub static TransferApprovalRequired: bool = false;
+    pub static ChallengeWindow: u64 = 0;
+    pub static ProtocolFee: Perbill = Perbill::zero();
+    pub static RequestDeposit: Balance = 0;
+    pub static ResponseDeposit: Balance = 0;
+    pub const ExecutionPalletId: PalletId = PalletId(*b"airo/exe");
+}
+
 impl pallet_execution::Config for Test {
     type WeightInfo = ();
     type RuntimeEvent = RuntimeEvent;
     type RuntimeHoldReason = RuntimeHoldReason;
     type Currency = Balances;
+    type Assets = Assets;
+    type PalletId = ExecutionPalletId;
     type AgreementId = AgreementId;
     type ModelId = ModelId;
     type ContentId = ContentId;
+    type MaxRequestParamsLength = ConstU32<MAX_REQUEST_PARAMS_LENGTH>;
+    type RequestParamsDepositPerByte = ConstU64<REQUEST_PARAMS_DEPOSIT_PER_BYTE>;
+    type RequestDeposit = RequestDeposit;
+    type ResponseDeposit = ResponseDeposit;
     type RoyaltyResolver = TestRoyaltyResolver;
+    type RequestTimeout = ConstU64<REQUEST_TIMEOUT>;
+    type ProviderRegistry = TestProviderRegistry;
+    type ExecutionObserver = TestExecutionObserver;
+    type RetentionPeriod = ConstU64<RETENTION_PERIOD>;
+    type TransferApprovalRequired = TransferApprovalRequired;
+    type ExtensionApprovalThreshold = ConstU32<EXTENSION_APPROVAL_THRESHOLD>;
+    type MaxDelegates = ConstU32<MAX_DELEGATES>;
+    type MaxOperators = ConstU32<MAX_OPERATORS>;
+    type MaxReplicas = ConstU32<MAX_REPLICAS>;
+    type ChallengeWindow = ChallengeWindow;
+    type ChallengeStake = ConstU64<CHALLENGE_STAKE>;
+    type ChallengeExpiry = ConstU64<CHALLENGE_EXPIRY>;
+    type ProviderBonds = TestProviderRegistry;
+    type ChallengeResolverOrigin = EnsureRoot<AccountId>;
+    type SubscriptionNoticePeriod = ConstU64<SUBSCRIPTION_NOTICE_PERIOD>;
+    type ProtocolFee = ProtocolFee;
+    type TreasuryAccount = ConstU64<TREASURY>;
+    type SpendingPeriod = ConstU64<SPENDING_PERIOD>;
+    type SpendingLimitDelay = ConstU64<SPENDING_LIMIT_DELAY>;
     #[cfg(feature = "runtime-benchmarks")]
     type BenchmarkHelper = AiroExecutionBenchmarkHelper;
 }
 
 pub const INITIAL_BALANCE: Balance = 1_000_000_000;
 pub const ROYALTY: Balance = 100;
+pub const REQUEST_TIMEOUT: u64 = 5;
+pub const RETENTION_PERIOD: u64 = 10;
+pub const MAX_REQUEST_PARAMS_LENGTH: u32 = 16;
+pub const REQUEST_PARAMS_DEPOSIT_PER_BYTE: Balance = 10;
+pub const EXTENSION_APPROVAL_THRESHOLD: u32 = 10;
+pub const MAX_DELEGATES: u32 = 2;
+pub const MAX_OPERATORS: u32 = 1;
+pub const MAX_REPLICAS: u32 = 2;
+pub const CHALLENGE_WINDOW: u64 = 5;
+pub const CHALLENGE_STAKE: Balance = 1_000;
+pub const CHALLENGE_EXPIRY: u64 = 20;
+pub const PROVIDER_BOND: Balance = 1_500;
+pub const SUBSCRIPTION_NOTICE_PERIOD: u64 = 3;
+pub const SPENDING_PERIOD: u64 = 10;
+pub const SPENDING_LIMIT_DELAY: u64 = 5;
 pub const CONSUMER_NO_BALANCE: AccountId = 0;
 pub const CONSUMER_1: AccountId = 1;
 pub const CONSUMER_2: AccountId = 2;
+pub const DELEGATE_1: AccountId = 21;
+pub const DELEGATE_2: AccountId = 22;
+pub const DELEGATE_3: AccountId = 23;
 pub const PROVIDER_1: AccountId = 11;
 pub const PROVIDER_2: AccountId = 12;
+pub const PROVIDER_3: AccountId = 13;
+pub const OPERATOR_1: AccountId = 31;
+pub const OPERATOR_2: AccountId = 32;
+pub const VERIFIER_1: AccountId = 41;
+pub const TREASURY: AccountId = 51;
 pub const OWNER: AccountId = 99;
 pub const OWNED_MODEL: &str = "owned-model";
+pub const ASSET: AssetId = 1;
+pub const ASSET_HIGH_MIN_BALANCE: AssetId = 2;
+pub const HIGH_MIN_BALANCE: Balance = 50;
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
     let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
 
     pallet_balances::GenesisConfig::<Test> {
-        balances: vec![(CONSUMER_1, INITIAL_BALANCE), (CONSUMER_2, INITIAL_BALANCE)],
+        balances: vec![
+            (CONSUMER_1, INITIAL_BALANCE),
+            (CONSUMER_2, INITIAL_BALANCE),
+            (VERIFIER_1, INITIAL_BALANCE),
+        ],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    pallet_assets::GenesisConfig::<Test> {
+        // Sufficient, so that escrow accounts do not need a native balance.
+        assets: vec![
+            (ASSET, OWNER, true, 1),
+            (ASSET_HIGH_MIN_BALANCE, OWNER, true, HIGH_MIN_BALANCE),
+        ],
+        accounts: vec![
+            (ASSET, CONSUMER_1, INITIAL_BALANCE),
+            (ASSET_HIGH_MIN_BALANCE, CONSUMER_1, INITIAL_BALANCE),
+        ],
+        ..Default::default()
     }
     .assimilate_storage(&mut storage)
     .unwrap();
@@ -7,9 +7,168 @@ pub struct Agreement<T>(PhantomData<T>);
 impl<T: Config> Agreement<T> {
     pub fn insert(agreement_id: T::AgreementId, agreement: AgreementDetails<T>) {
         ConsumerAgreements::<T>::insert(&agreement.consumer, agreement_id, ());
-        ProviderAgreements::<T>::insert(&agreement.provider, agreement_id, ());
+        agreement.providers().for_each(|provider| {
+            ProviderAgreements::<T>::insert(provider, agreement_id, ());
+        });
         Agreements::<T>::insert(agreement_id, agreement);
     }
+
+    pub fn set_consumer(
+        agreement_id: T::AgreementId,
+        agreement: &mut AgreementDetails<T>,
+        consumer: Consumer<T>,
+    ) {
+        ConsumerAgreements::<T>::remove(&agreement.consumer, agreement_id);
+        ConsumerAgreements::<T>::insert(&consumer, agreement_id, ());
+        // Delegates and extensions were chosen by the previous consumer.
+        AgreementDelegates::<T>::remove(agreement_id);
+        PendingExtensions::<T>::remove(agreement_id);
+        agreement.consumer = consumer;
+    }
+
+    pub fn remove(agreement_id: T::AgreementId, agreement: &AgreementDetails<T>) {
+        AgreementDelegates::<T>::remove(agreement_id);
+        PendingTransfers::<T>::remove(agreement_id);
+        PendingExtensions::<T>::remove(agreement_id);
+        ConsumerAgreements::<T>::remove(&agreement.consumer, agreement_id);
+        agreement.providers().for_each(|provider| {
+            ProviderAgreements::<T>::remove(provider, agreement_id);
+        });
+        Agreements::<T>::remove(agreement_id);
+    }
+
+    /// Schedules a closed agreement for pruning once its challenge window elapses.
+    pub fn schedule_prune(agreement_id: T::AgreementId) {
+        let challenge_window = T::ChallengeWindow::get();
+        if challenge_window.is_zero() {
+            ClosedAgreements::<T>::insert(agreement_id, ());
+        } else {
+            let prune_at =
+                frame_system::Pallet::<T>::block_number().saturating_add(challenge_window);
+            ClosingSchedule::<T>::insert(prune_at, agreement_id, ());
+        }
+    }
+}
+
+pub struct Delegate<T>(PhantomData<T>);
+
+impl<T: Config> Delegate<T> {
+    pub fn insert(
+        agreement_id: T::AgreementId,
+        delegate: AccountIdOf<T>,
+        quota: Option<RequestsUsize>,
+    ) -> Result<(), Error<T>> {
+        AgreementDelegates::<T>::try_mutate(agreement_id, |delegates| {
+            match delegates.get_mut(&delegate) {
+                Some(details) => {
+                    details.quota = quota;
+                    Ok(())
+                },
+                None => delegates
+                    .try_insert(delegate, DelegateDetails::new(quota))
+                    .map(|_| ())
+                    .map_err(|_| Error::<T>::TooManyDelegates),
+            }
+        })
+    }
+
+    pub fn remove(agreement_id: T::AgreementId, delegate: &AccountIdOf<T>) -> Result<(), Error<T>> {
+        AgreementDelegates::<T>::try_mutate(agreement_id, |delegates| {
+            delegates.remove(delegate).map(|_| ()).ok_or(Error::<T>::DelegateNotFound)
+        })
+    }
+
+    pub fn use_request(
+        agreement_id: T::AgreementId,
+        delegate: &AccountIdOf<T>,
+    ) -> Result<(), Error<T>> {
+        AgreementDelegates::<T>::try_mutate(agreement_id, |delegates| {
+            delegates
+                .get_mut(delegate)
+                .ok_or(Error::<T>::AgreementInvalid)?
+                .use_request::<T>()
+        })
+    }
+}
+
+pub struct Operator<T>(PhantomData<T>);
+
+impl<T: Config> Operator<T> {
+    pub fn exists(provider: &Provider<T>, operator: &AccountIdOf<T>) -> bool {
+        ProviderOperators::<T>::get(provider).contains(operator)
+    }
+
+    pub fn insert(provider: &Provider<T>, operator: AccountIdOf<T>) -> Result<(), Error<T>> {
+        ProviderOperators::<T>::try_mutate(provider, |operators| {
+            match operators.try_insert(operator).map_err(|_| Error::<T>::TooManyOperators)? {
+                true => Ok(()),
+                false => Err(Error::<T>::OperatorAlreadyExists),
+            }
+        })
+    }
+
+    pub fn remove(provider: &Provider<T>, operator: &AccountIdOf<T>) -> Result<(), Error<T>> {
+        ProviderOperators::<T>::try_mutate_exists(provider, |operators| {
+            let set = operators.as_mut().ok_or(Error::<T>::OperatorNotFound)?;
+            ensure!(set.remove(operator), Error::<T>::OperatorNotFound);
+            if set.is_empty() {
+                *operators = None;
+            }
+            Ok(())
+        })
+    }
+}
+
+pub struct Capacity<T>(PhantomData<T>);
+
+impl<T: Config> Capacity<T> {
+    /// Takes up one of the requests `provider` can have left unanswered, for a request on
+    /// `agreement_id`. Providers which are not registered are not limited.
+    pub fn reserve(agreement_id: T::AgreementId, provider: &Provider<T>) -> Result<(), Error<T>> {
+        OpenRequests::<T>::try_mutate(provider, |open_requests| {
+            if let Some(capacity) = T::ProviderRegistry::capacity(provider) {
+                ensure!(*open_requests < capacity, Error::<T>::ProviderAtCapacity);
+            }
+            open_requests.saturating_inc();
+            Ok::<_, Error<T>>(())
+        })?;
+        AgreementOpenRequests::<T>::mutate(agreement_id, provider, |open_requests| {
+            open_requests.saturating_inc()
+        });
+        Ok(())
+    }
+
+    pub fn release(agreement_id: T::AgreementId, provider: &Provider<T>) {
+        OpenRequests::<T>::mutate_exists(provider, |open_requests| {
+            Self::decrease(open_requests, 1)
+        });
+        AgreementOpenRequests::<T>::mutate_exists(agreement_id, provider, |open_requests| {
+            Self::decrease(open_requests, 1)
+        });
+    }
+
+    /// Releases all the requests `provider` has left unanswered on `agreement_id`.
+    pub fn release_agreement(agreement_id: T::AgreementId, provider: &Provider<T>) {
+        let released = AgreementOpenRequests::<T>::take(agreement_id, provider);
+        OpenRequests::<T>::mutate_exists(provider, |open_requests| {
+            Self::decrease(open_requests, released)
+        });
+    }
+
+    fn decrease(open_requests: &mut Option<RequestsUsize>, count: RequestsUsize) {
+        *open_requests =
+            open_requests.map(|open| open.saturating_sub(count)).filter(|open| *open > 0);
+    }
+}
+
+pub struct Request<T>(PhantomData<T>);
+
+impl<T: Config> Request<T> {
+    pub fn schedule_prune(agreement_id: T::AgreementId, request_index: RequestsUsize) {
+        let prune_at =
+            frame_system::Pallet::<T>::block_number().saturating_add(T::RetentionPeriod::get());
+        PruneSchedule::<T>::insert(prune_at, (agreement_id, request_index), ());
+    }
 }
 
 pub struct Response<T>(PhantomData<T>);
@@ -18,4 +177,99 @@ impl<T: Config> Response<T> {
     pub fn exists(agreement_id: T::AgreementId, request_index: RequestsUsize) -> bool {
         Responses::<T>::contains_key(agreement_id, request_index)
     }
+
+    pub fn insert(
+        agreement_id: T::AgreementId,
+        request_index: RequestsUsize,
+        response: ResponseDetails<T>,
+    ) {
+        Request::<T>::schedule_prune(agreement_id, request_index);
+        Responses::<T>::insert(agreement_id, request_index, response);
+    }
+}
+
+pub struct SpendingLimit<T>(PhantomData<T>);
+
+impl<T: Config> SpendingLimit<T> {
+    /// Limits in effect for `consumer` in `asset`, including raised limits which time lock has
+    /// elapsed.
+    pub fn get(consumer: &Consumer<T>, asset: &Option<AssetIdOf<T>>) -> SpendingLimits<T> {
+        match PendingSpendingLimits::<T>::get(consumer, asset) {
+            Some((limits, effective_at))
+                if effective_at <= frame_system::Pallet::<T>::block_number() =>
+            {
+                limits
+            },
+            _ => ConsumerSpendingLimits::<T>::get(consumer, asset),
+        }
+    }
+
+    /// Lowered limits apply at once, while raised ones only apply after `T::SpendingLimitDelay`.
+    /// Returns the limits applied at once, and the block the raised limits apply at, if any.
+    pub fn set(
+        consumer: &Consumer<T>,
+        asset: &Option<AssetIdOf<T>>,
+        limits: SpendingLimits<T>,
+    ) -> (SpendingLimits<T>, Option<BlockNumberFor<T>>) {
+        let current = Self::get(consumer, asset);
+        let applied = limits.without_raises(&current);
+        ConsumerSpendingLimits::<T>::insert(consumer, asset, &applied);
+
+        if limits.raises(&current) {
+            let effective_at = frame_system::Pallet::<T>::block_number()
+                .saturating_add(T::SpendingLimitDelay::get());
+            PendingSpendingLimits::<T>::insert(consumer, asset, (limits, effective_at));
+            (applied, Some(effective_at))
+        } else {
+            PendingSpendingLimits::<T>::remove(consumer, asset);
+            (applied, None)
+        }
+    }
+
+    pub fn can_spend(
+        consumer: &Consumer<T>,
+        asset: &Option<AssetIdOf<T>>,
+        amount: BalanceOf<T>,
+    ) -> bool {
+        Self::spending_after(consumer, asset, amount, amount).is_ok()
+    }
+
+    /// Records `amount` prepaid by `consumer` in `asset` for an agreement which whole prepayment
+    /// is `agreement_prepayment`.
+    pub fn spend(
+        consumer: &Consumer<T>,
+        asset: &Option<AssetIdOf<T>>,
+        amount: BalanceOf<T>,
+        agreement_prepayment: BalanceOf<T>,
+    ) -> Result<(), Error<T>> {
+        if let Some(spending) = Self::spending_after(consumer, asset, amount, agreement_prepayment)?
+        {
+            ConsumerSpending::<T>::insert(consumer, asset, spending);
+        }
+        Ok(())
+    }
+
+    /// Spending of the current period, only tracked while a period limit is set.
+    fn spending_after(
+        consumer: &Consumer<T>,
+        asset: &Option<AssetIdOf<T>>,
+        amount: BalanceOf<T>,
+        agreement_prepayment: BalanceOf<T>,
+    ) -> Result<Option<SpendingDetails<T>>, Error<T>> {
+        let limits = Self::get(consumer, asset);
+        ensure!(limits.allows(agreement_prepayment), Error::<T>::SpendingLimitExceeded);
+        let Some(per_period) = limits.per_period else {
+            return Ok(None);
+        };
+
+        let now = frame_system::Pallet::<T>::block_number();
+        let period_start = now.saturating_sub(now % T::SpendingPeriod::get());
+        let mut spending = ConsumerSpending::<T>::get(consumer, asset);
+        if spending.period_start != period_start {
+            spending = SpendingDetails { period_start, spent: Zero::zero() };
+        }
+        spending.spent.saturating_accrue(amount);
+        ensure!(spending.spent <= per_period, Error::<T>::SpendingLimitExceeded);
+        Ok(Some(spending))
+    }
 }
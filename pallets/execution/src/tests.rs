@@ -1,7 +1,14 @@
-use frame_support::{traits::fungible, *};
+use frame_support::{
+    traits::{fungible, fungibles, OnRuntimeUpgrade},
+    *,
+};
+use sp_core::H256;
 use sp_runtime::TokenError;
 
-use airo_primitives::RequestsUsize;
+use airo_primitives::{
+    agreement::{AgreementRegistry, PipelineStage},
+    RequestsUsize, UnitsUsize,
+};
 
 use crate::{mock::*, *};
 
@@ -20,6 +27,9 @@ fn create_agreement(
         model_id,
         price_per_request,
         requests_total,
+        None,
+        None,
+        None,
     ));
 }
 
@@ -27,12 +37,63 @@ fn create_request(consumer: AccountId, agreement_id: AgreementId) -> RequestsUsi
     assert_ok!(Pallet::<Test>::request_create(
         RuntimeOrigin::signed(consumer),
         agreement_id,
-        ContentId::default()
+        ContentId::default(),
+        None,
+        None
     ));
 
     Agreements::<Test>::get(agreement_id).unwrap().requests_count
 }
 
+fn create_redundant_agreement(
+    agreement_id: AgreementId,
+    providers: &[AccountId],
+    price_per_request: Balance,
+    requests_total: RequestsUsize,
+    quorum: RequestsUsize,
+) {
+    assert_ok!(Pallet::<Test>::create_redundant_agreement(
+        CONSUMER_1,
+        providers,
+        agreement_id,
+        ModelId::default(),
+        price_per_request,
+        requests_total,
+        quorum,
+        None,
+        None,
+        None,
+    ));
+}
+
+fn create_replica_response(
+    provider: AccountId,
+    agreement_id: AgreementId,
+    request_index: RequestsUsize,
+    content_id: ContentId,
+    output_hash: Option<H256>,
+) {
+    assert_ok!(Pallet::<Test>::response_create(
+        RuntimeOrigin::signed(provider),
+        agreement_id,
+        request_index,
+        content_id,
+        output_hash,
+        0,
+    ));
+}
+
+fn create_response(provider: AccountId, agreement_id: AgreementId, request_index: RequestsUsize) {
+    assert_ok!(Pallet::<Test>::response_create(
+        RuntimeOrigin::signed(provider),
+        agreement_id,
+        request_index,
+        ContentId::default(),
+        None,
+        0
+    ));
+}
+
 #[test]
 fn can_create_agreement() {
     new_test_ext().execute_with(|| {
@@ -48,6 +109,9 @@ fn can_create_agreement() {
             model_id.clone(),
             price_per_request,
             requests_total,
+            None,
+            None,
+            None,
         ));
 
         let expected_agreement = AgreementDetails::new(
@@ -84,6 +148,25 @@ fn can_create_agreement() {
     });
 }
 
+#[test]
+fn agreement_records_model_version() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Pallet::<Test>::create_agreement(
+            CONSUMER_1,
+            PROVIDER_1,
+            1,
+            ModelId::default(),
+            100,
+            5,
+            None,
+            None,
+            Some(2),
+        ));
+
+        assert_eq!(Agreements::<Test>::get(1).unwrap().model_version, Some(2));
+    });
+}
+
 #[test]
 fn fail_create_agreement_no_funds() {
     new_test_ext().execute_with(|| {
@@ -95,6 +178,9 @@ fn fail_create_agreement_no_funds() {
                 ModelId::default(),
                 100,
                 5,
+                None,
+                None,
+                None,
             ),
             TokenError::FundsUnavailable
         );
@@ -112,13 +198,73 @@ fn can_request() {
             RuntimeOrigin::signed(CONSUMER_1),
             agreement_id,
             content_id,
+            None,
+            None,
         ));
 
-        assert_eq!(Requests::<Test>::get(agreement_id, 1), Some(content_id));
+        assert_eq!(
+            Requests::<Test>::get(agreement_id, 1),
+            Some(RequestDetails::new(CONSUMER_1, content_id, None))
+        );
         assert_eq!(Agreements::<Test>::get(agreement_id).unwrap().requests_count, 1);
 
         System::assert_last_event(
-            Event::RequestCreated { agreement_id, request_index: 1, content_id }.into(),
+            Event::RequestCreated { agreement_id, request_index: 1, content_id, params: None }
+                .into(),
+        );
+    });
+}
+
+#[test]
+fn can_request_with_params() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 1);
+        let params: RequestParams<Test> = BoundedVec::try_from(b"max_tokens=64".to_vec()).unwrap();
+        let deposit = REQUEST_PARAMS_DEPOSIT_PER_BYTE * params.len() as Balance;
+
+        let content_id = ContentId::random();
+        assert_ok!(Pallet::<Test>::request_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            content_id,
+            Some(params.clone()),
+            None,
+        ));
+
+        let request = Requests::<Test>::get(agreement_id, 1).unwrap();
+        assert_eq!(request.params, Some(params.clone()));
+        assert_eq!(request.deposit, deposit);
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::RequestDeposit.into(),
+                &CONSUMER_1
+            ),
+            deposit
+        );
+        System::assert_last_event(
+            Event::RequestCreated {
+                agreement_id,
+                request_index: 1,
+                content_id,
+                params: Some(params),
+            }
+            .into(),
+        );
+
+        // The deposit is released once the request is pruned.
+        create_response(PROVIDER_1, agreement_id, 1);
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+        Pallet::<Test>::on_idle(System::block_number(), Weight::MAX);
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::RequestDeposit.into(),
+                &CONSUMER_1
+            ),
+            0
         );
     });
 }
@@ -131,6 +277,8 @@ fn fail_request_missing_agreement() {
                 RuntimeOrigin::signed(CONSUMER_1),
                 1,
                 ContentId::default(),
+                None,
+                None,
             ),
             Error::<Test>::AgreementNotFound
         );
@@ -147,6 +295,8 @@ fn fail_request_non_owned_agreement() {
                 RuntimeOrigin::signed(CONSUMER_2),
                 agreement_id,
                 ContentId::default(),
+                None,
+                None,
             ),
             Error::<Test>::AgreementInvalid
         );
@@ -166,6 +316,8 @@ fn fail_request_exceed_limit() {
                 RuntimeOrigin::signed(CONSUMER_1),
                 agreement_id,
                 ContentId::default(),
+                None,
+                None,
             ),
             Error::<Test>::RequestNotAllowed
         );
@@ -193,9 +345,14 @@ fn can_respond() {
             agreement_id,
             request_index,
             content_id,
+            None,
+            0,
         ));
 
-        assert_eq!(Responses::<Test>::get(agreement_id, request_index), Some(content_id));
+        assert_eq!(
+            Responses::<Test>::get(agreement_id, request_index),
+            Some(ResponseDetails::new(PROVIDER_1, content_id, None, 0))
+        );
         assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), price_per_request);
 
         System::assert_last_event(
@@ -225,6 +382,8 @@ fn royalty_transferred() {
             agreement_id,
             request_index,
             content_id,
+            None,
+            0,
         ));
 
         assert_eq!(<Balances as fungible::Inspect<_>>::balance(&OWNER), ROYALTY);
@@ -240,6 +399,8 @@ fn fail_respond_missing_agreement() {
                 1,
                 1,
                 ContentId::default(),
+                None,
+                0,
             ),
             Error::<Test>::AgreementNotFound
         );
@@ -259,6 +420,8 @@ fn fail_respond_non_owned_agreement() {
                 agreement_id,
                 request_index,
                 ContentId::default(),
+                None,
+                0,
             ),
             Error::<Test>::AgreementInvalid
         );
@@ -277,6 +440,8 @@ fn fail_respond_missing_request() {
                 agreement_id,
                 1,
                 ContentId::default(),
+                None,
+                0,
             ),
             Error::<Test>::RequestNotFound
         );
@@ -295,6 +460,8 @@ fn fail_respond_same_request() {
             agreement_id,
             request_index,
             ContentId::default(),
+            None,
+            0,
         ));
         assert_noop!(
             Pallet::<Test>::response_create(
@@ -302,8 +469,2479 @@ fn fail_respond_same_request() {
                 agreement_id,
                 request_index,
                 ContentId::default(),
+                None,
+                0,
             ),
             Error::<Test>::ResponseAlreadyExists
         );
     });
 }
+
+#[test]
+fn can_close_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_agreement(
+            agreement_id,
+            ModelId::default(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            10,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+
+        assert!(!Agreements::<Test>::contains_key(agreement_id));
+        assert!(!ConsumerAgreements::<Test>::contains_key(CONSUMER_1, agreement_id));
+        assert!(!ProviderAgreements::<Test>::contains_key(PROVIDER_1, agreement_id));
+        assert!(ClosedAgreements::<Test>::contains_key(agreement_id));
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&CONSUMER_1),
+            INITIAL_BALANCE - price_per_request
+        );
+
+        System::assert_last_event(Event::AgreementClosed { agreement_id }.into());
+    });
+}
+
+#[test]
+fn fail_close_non_owned_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+
+        assert_noop!(
+            Pallet::<Test>::agreement_close(RuntimeOrigin::signed(CONSUMER_2), agreement_id),
+            Error::<Test>::AgreementInvalid
+        );
+    });
+}
+
+#[test]
+fn fail_close_agreement_with_pending_requests() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+        create_request(CONSUMER_1, agreement_id);
+
+        assert_noop!(
+            Pallet::<Test>::agreement_close(RuntimeOrigin::signed(CONSUMER_1), agreement_id),
+            Error::<Test>::AgreementHasPendingRequests
+        );
+    });
+}
+
+#[test]
+fn provider_can_close_exhausted_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 1);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        assert!(<Pallet<Test> as AgreementRegistry<_>>::has_agreements(&PROVIDER_1));
+
+        // The consumer never closes the agreement, so the provider does.
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(PROVIDER_1),
+            agreement_id
+        ));
+
+        System::assert_last_event(Event::AgreementClosed { agreement_id }.into());
+        assert!(!<Pallet<Test> as AgreementRegistry<_>>::has_agreements(&PROVIDER_1));
+    });
+}
+
+#[test]
+fn fail_provider_close_active_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        assert_noop!(
+            Pallet::<Test>::agreement_close(RuntimeOrigin::signed(PROVIDER_1), agreement_id),
+            Error::<Test>::AgreementActive
+        );
+        assert_noop!(
+            Pallet::<Test>::agreement_close(RuntimeOrigin::signed(PROVIDER_2), agreement_id),
+            Error::<Test>::AgreementInvalid
+        );
+    });
+}
+
+#[test]
+fn can_extend_exhausted_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 1);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        assert!(Agreements::<Test>::contains_key(agreement_id));
+
+        assert_ok!(Pallet::<Test>::agreement_extend(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            1
+        ));
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        assert_eq!(request_index, 2);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), 200);
+    });
+}
+
+#[test]
+fn closed_agreement_pruned_on_idle() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 2);
+        let request_1 = create_request(CONSUMER_1, agreement_id);
+        let request_2 = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_1);
+        create_response(PROVIDER_1, agreement_id, request_2);
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+
+        Pallet::<Test>::on_idle(System::block_number(), Weight::MAX);
+
+        assert!(!Requests::<Test>::contains_prefix(agreement_id));
+        assert!(!Responses::<Test>::contains_prefix(agreement_id));
+        assert!(!ClosedAgreements::<Test>::contains_key(agreement_id));
+        System::assert_has_event(
+            Event::RequestPruned {
+                agreement_id,
+                request_index: request_2,
+                request_content_id: ContentId::default(),
+                response_content_id: Some(ContentId::default()),
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn pruning_respects_weight_limit() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 2);
+        let request_1 = create_request(CONSUMER_1, agreement_id);
+        let request_2 = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_1);
+        create_response(PROVIDER_1, agreement_id, request_2);
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+
+        let weight = <() as crate::WeightInfo>::prune_request();
+        assert_eq!(Pallet::<Test>::on_idle(System::block_number(), weight), weight);
+
+        assert_eq!(Requests::<Test>::iter_prefix(agreement_id).count(), 1);
+        assert!(ClosedAgreements::<Test>::contains_key(agreement_id));
+    });
+}
+
+#[test]
+fn responded_request_pruned_after_retention_period() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+        let request_1 = create_request(CONSUMER_1, agreement_id);
+        let request_2 = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_1);
+
+        Pallet::<Test>::on_idle(System::block_number(), Weight::MAX);
+        assert!(Requests::<Test>::contains_key(agreement_id, request_1));
+
+        let prune_at = System::block_number() + RETENTION_PERIOD;
+        System::set_block_number(prune_at);
+        Pallet::<Test>::on_idle(prune_at, Weight::MAX);
+
+        assert!(!Requests::<Test>::contains_key(agreement_id, request_1));
+        assert!(!Responses::<Test>::contains_key(agreement_id, request_1));
+        assert!(Requests::<Test>::contains_key(agreement_id, request_2));
+        assert_eq!(PruneCursor::<Test>::get(), Some(prune_at + 1));
+        System::assert_last_event(
+            Event::RequestPruned {
+                agreement_id,
+                request_index: request_1,
+                request_content_id: ContentId::default(),
+                response_content_id: Some(ContentId::default()),
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Pallet::<Test>::response_create(
+                RuntimeOrigin::signed(PROVIDER_1),
+                agreement_id,
+                request_1,
+                ContentId::default(),
+                None,
+                0,
+            ),
+            Error::<Test>::RequestNotFound
+        );
+    });
+}
+
+#[test]
+fn can_transfer_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        let requests_total = 10;
+        create_agreement(
+            agreement_id,
+            BoundedVec::try_from(OWNED_MODEL.as_bytes().to_vec()).unwrap(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            requests_total,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        create_request(CONSUMER_1, agreement_id);
+
+        assert_ok!(Pallet::<Test>::agreement_transfer(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            CONSUMER_2,
+        ));
+
+        let agreement = Agreements::<Test>::get(agreement_id).unwrap();
+        assert!(agreement.is_consumer(&CONSUMER_2));
+        assert!(!ConsumerAgreements::<Test>::contains_key(CONSUMER_1, agreement_id));
+        assert!(ConsumerAgreements::<Test>::contains_key(CONSUMER_2, agreement_id));
+
+        let requests_unpaid = (requests_total - 1) as Balance;
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ProviderPayment.into(),
+                &CONSUMER_2
+            ),
+            price_per_request * requests_unpaid
+        );
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::RoyaltyPayment.into(),
+                &CONSUMER_2
+            ),
+            ROYALTY * requests_unpaid
+        );
+        assert_eq!(<Balances as fungible::hold::Inspect<_>>::total_balance_on_hold(&CONSUMER_1), 0);
+
+        System::assert_last_event(
+            Event::AgreementTransferred { agreement_id, consumer: CONSUMER_2 }.into(),
+        );
+
+        assert_noop!(
+            Pallet::<Test>::request_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                ContentId::default(),
+                None,
+                None,
+            ),
+            Error::<Test>::AgreementInvalid
+        );
+        create_request(CONSUMER_2, agreement_id);
+    });
+}
+
+#[test]
+fn fail_transfer_non_owned_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+
+        assert_noop!(
+            Pallet::<Test>::agreement_transfer(
+                RuntimeOrigin::signed(CONSUMER_2),
+                agreement_id,
+                CONSUMER_2,
+            ),
+            Error::<Test>::AgreementInvalid
+        );
+    });
+}
+
+#[test]
+fn can_transfer_agreement_with_approval() {
+    new_test_ext().execute_with(|| {
+        TransferApprovalRequired::set(true);
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+
+        assert_ok!(Pallet::<Test>::agreement_transfer(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            CONSUMER_2,
+        ));
+
+        assert_eq!(PendingTransfers::<Test>::get(agreement_id), Some(CONSUMER_2));
+        assert!(Agreements::<Test>::get(agreement_id).unwrap().is_consumer(&CONSUMER_1));
+        System::assert_last_event(
+            Event::AgreementTransferRequested { agreement_id, new_consumer: CONSUMER_2 }.into(),
+        );
+
+        assert_noop!(
+            Pallet::<Test>::agreement_transfer_approve(
+                RuntimeOrigin::signed(PROVIDER_2),
+                agreement_id
+            ),
+            Error::<Test>::AgreementInvalid
+        );
+        assert_ok!(Pallet::<Test>::agreement_transfer_approve(
+            RuntimeOrigin::signed(PROVIDER_1),
+            agreement_id
+        ));
+
+        assert!(!PendingTransfers::<Test>::contains_key(agreement_id));
+        assert!(Agreements::<Test>::get(agreement_id).unwrap().is_consumer(&CONSUMER_2));
+        System::assert_last_event(
+            Event::AgreementTransferred { agreement_id, consumer: CONSUMER_2 }.into(),
+        );
+    });
+}
+
+#[test]
+fn fail_approve_missing_transfer() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+
+        assert_noop!(
+            Pallet::<Test>::agreement_transfer_approve(
+                RuntimeOrigin::signed(PROVIDER_1),
+                agreement_id
+            ),
+            Error::<Test>::TransferNotFound
+        );
+    });
+}
+
+#[test]
+fn can_add_delegate() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+
+        assert_ok!(Pallet::<Test>::delegate_add(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            DELEGATE_1,
+            Some(1),
+        ));
+
+        assert_eq!(
+            AgreementDelegates::<Test>::get(agreement_id).get(&DELEGATE_1),
+            Some(&DelegateDetails::new(Some(1)))
+        );
+        System::assert_last_event(
+            Event::DelegateAdded { agreement_id, delegate: DELEGATE_1, quota: Some(1) }.into(),
+        );
+    });
+}
+
+#[test]
+fn fail_add_delegate_non_owned_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+
+        assert_noop!(
+            Pallet::<Test>::delegate_add(
+                RuntimeOrigin::signed(CONSUMER_2),
+                agreement_id,
+                DELEGATE_1,
+                None,
+            ),
+            Error::<Test>::AgreementInvalid
+        );
+    });
+}
+
+#[test]
+fn fail_add_too_many_delegates() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+        for delegate in [DELEGATE_1, DELEGATE_2] {
+            assert_ok!(Pallet::<Test>::delegate_add(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                delegate,
+                None,
+            ));
+        }
+
+        assert_noop!(
+            Pallet::<Test>::delegate_add(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                DELEGATE_3,
+                None,
+            ),
+            Error::<Test>::TooManyDelegates
+        );
+    });
+}
+
+#[test]
+fn delegate_can_request_within_quota() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+        assert_ok!(Pallet::<Test>::delegate_add(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            DELEGATE_1,
+            Some(1),
+        ));
+
+        let request_index = create_request(DELEGATE_1, agreement_id);
+        assert!(Requests::<Test>::contains_key(agreement_id, request_index));
+
+        assert_noop!(
+            Pallet::<Test>::request_create(
+                RuntimeOrigin::signed(DELEGATE_1),
+                agreement_id,
+                ContentId::default(),
+                None,
+                None,
+            ),
+            Error::<Test>::DelegateQuotaExceeded
+        );
+        assert_noop!(
+            Pallet::<Test>::agreement_close(RuntimeOrigin::signed(DELEGATE_1), agreement_id),
+            Error::<Test>::AgreementInvalid
+        );
+    });
+}
+
+#[test]
+fn can_remove_delegate() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+        assert_ok!(Pallet::<Test>::delegate_add(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            DELEGATE_1,
+            None,
+        ));
+
+        assert_ok!(Pallet::<Test>::delegate_remove(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            DELEGATE_1,
+        ));
+
+        assert!(AgreementDelegates::<Test>::get(agreement_id).is_empty());
+        System::assert_last_event(
+            Event::DelegateRemoved { agreement_id, delegate: DELEGATE_1 }.into(),
+        );
+        assert_noop!(
+            Pallet::<Test>::request_create(
+                RuntimeOrigin::signed(DELEGATE_1),
+                agreement_id,
+                ContentId::default(),
+                None,
+                None,
+            ),
+            Error::<Test>::AgreementInvalid
+        );
+    });
+}
+
+#[test]
+fn fail_remove_missing_delegate() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+
+        assert_noop!(
+            Pallet::<Test>::delegate_remove(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                DELEGATE_1,
+            ),
+            Error::<Test>::DelegateNotFound
+        );
+    });
+}
+
+#[test]
+fn can_add_operator() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Pallet::<Test>::operator_add(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1));
+
+        assert!(ProviderOperators::<Test>::get(PROVIDER_1).contains(&OPERATOR_1));
+        System::assert_last_event(
+            Event::OperatorAdded { provider: PROVIDER_1, operator: OPERATOR_1 }.into(),
+        );
+    });
+}
+
+#[test]
+fn fail_add_operator() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Pallet::<Test>::operator_add(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1));
+
+        assert_noop!(
+            Pallet::<Test>::operator_add(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1),
+            Error::<Test>::OperatorAlreadyExists
+        );
+        assert_noop!(
+            Pallet::<Test>::operator_add(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_2),
+            Error::<Test>::TooManyOperators
+        );
+    });
+}
+
+#[test]
+fn operator_can_respond() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_agreement(
+            agreement_id,
+            ModelId::default(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            10,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        assert_ok!(Pallet::<Test>::operator_add(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1));
+
+        create_response(OPERATOR_1, agreement_id, request_index);
+
+        assert!(Responses::<Test>::contains_key(agreement_id, request_index));
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), price_per_request);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&OPERATOR_1), 0);
+    });
+}
+
+#[test]
+fn can_remove_operator() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 10);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        assert_ok!(Pallet::<Test>::operator_add(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1));
+
+        assert_ok!(Pallet::<Test>::operator_remove(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1));
+
+        assert!(!ProviderOperators::<Test>::contains_key(PROVIDER_1));
+        System::assert_last_event(
+            Event::OperatorRemoved { provider: PROVIDER_1, operator: OPERATOR_1 }.into(),
+        );
+        assert_noop!(
+            Pallet::<Test>::response_create(
+                RuntimeOrigin::signed(OPERATOR_1),
+                agreement_id,
+                request_index,
+                ContentId::default(),
+                None,
+                0,
+            ),
+            Error::<Test>::AgreementInvalid
+        );
+    });
+}
+
+#[test]
+fn fail_remove_missing_operator() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Pallet::<Test>::operator_remove(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1),
+            Error::<Test>::OperatorNotFound
+        );
+    });
+}
+
+#[test]
+fn can_create_redundant_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        let requests_total = 5;
+        create_redundant_agreement(
+            agreement_id,
+            &[PROVIDER_1, PROVIDER_2, PROVIDER_3],
+            price_per_request,
+            requests_total,
+            2,
+        );
+
+        let agreement = Agreements::<Test>::get(agreement_id).unwrap();
+        assert_eq!(agreement.provider, PROVIDER_1);
+        assert_eq!(agreement.replicas.into_inner(), vec![PROVIDER_2, PROVIDER_3]);
+        assert_eq!(agreement.quorum, 2);
+        for provider in [PROVIDER_1, PROVIDER_2, PROVIDER_3] {
+            assert!(ProviderAgreements::<Test>::contains_key(provider, agreement_id));
+        }
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ProviderPayment.into(),
+                &CONSUMER_1
+            ),
+            price_per_request * requests_total as Balance * 3
+        );
+    });
+}
+
+#[test]
+fn fail_create_invalid_redundant_agreement() {
+    new_test_ext().execute_with(|| {
+        let create = |providers: &[AccountId], quorum| {
+            Pallet::<Test>::create_redundant_agreement(
+                CONSUMER_1,
+                providers,
+                1,
+                ModelId::default(),
+                100,
+                5,
+                quorum,
+                None,
+                None,
+                None,
+            )
+        };
+
+        // Quorum is not a majority.
+        assert_noop!(
+            create(&[PROVIDER_1, PROVIDER_2, PROVIDER_3], 1),
+            Error::<Test>::ReplicationInvalid
+        );
+        // Quorum exceeds the number of providers.
+        assert_noop!(create(&[PROVIDER_1, PROVIDER_2], 3), Error::<Test>::ReplicationInvalid);
+        // Providers are not distinct.
+        assert_noop!(
+            create(&[PROVIDER_1, PROVIDER_2, PROVIDER_1], 2),
+            Error::<Test>::ReplicationInvalid
+        );
+        // Too many providers.
+        assert_noop!(
+            create(&[PROVIDER_1, PROVIDER_2, PROVIDER_3, OPERATOR_1], 3),
+            Error::<Test>::ReplicationInvalid
+        );
+    });
+}
+
+#[test]
+fn redundant_response_accepted_by_quorum() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_redundant_agreement(
+            agreement_id,
+            &[PROVIDER_1, PROVIDER_2, PROVIDER_3],
+            price_per_request,
+            2,
+            2,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        let content_id = ContentId::random();
+
+        create_replica_response(PROVIDER_1, agreement_id, request_index, content_id, None);
+        create_replica_response(PROVIDER_2, agreement_id, request_index, ContentId::random(), None);
+        assert!(!Responses::<Test>::contains_key(agreement_id, request_index));
+
+        create_replica_response(PROVIDER_3, agreement_id, request_index, content_id, None);
+
+        assert_eq!(
+            Responses::<Test>::get(agreement_id, request_index),
+            Some(ResponseDetails::new(PROVIDER_3, content_id, None, 0))
+        );
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), price_per_request);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_2), 0);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_3), price_per_request);
+        // The dissenting provider's payment is returned to the consumer.
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ProviderPayment.into(),
+                &CONSUMER_1
+            ),
+            price_per_request * 3
+        );
+        System::assert_has_event(
+            Event::ResponseDissented { agreement_id, request_index, provider: PROVIDER_2 }.into(),
+        );
+        System::assert_last_event(
+            Event::ResponseCreated { agreement_id, request_index, content_id }.into(),
+        );
+    });
+}
+
+#[test]
+fn redundant_response_compared_by_output_hash() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_redundant_agreement(agreement_id, &[PROVIDER_1, PROVIDER_2], 100, 2, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        let output_hash = H256::random();
+
+        let content_id = ContentId::random();
+        create_replica_response(
+            PROVIDER_1,
+            agreement_id,
+            request_index,
+            content_id,
+            Some(output_hash),
+        );
+        create_replica_response(
+            PROVIDER_2,
+            agreement_id,
+            request_index,
+            ContentId::random(),
+            Some(output_hash),
+        );
+
+        assert_eq!(
+            Responses::<Test>::get(agreement_id, request_index),
+            Some(ResponseDetails::new(PROVIDER_2, content_id, Some(output_hash), 0))
+        );
+    });
+}
+
+#[test]
+fn late_replica_response_settled() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_redundant_agreement(
+            agreement_id,
+            &[PROVIDER_1, PROVIDER_2, PROVIDER_3],
+            price_per_request,
+            2,
+            2,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        let content_id = ContentId::random();
+        create_replica_response(PROVIDER_1, agreement_id, request_index, content_id, None);
+        create_replica_response(PROVIDER_2, agreement_id, request_index, content_id, None);
+
+        create_replica_response(PROVIDER_3, agreement_id, request_index, ContentId::random(), None);
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_3), 0);
+        System::assert_last_event(
+            Event::ResponseDissented { agreement_id, request_index, provider: PROVIDER_3 }.into(),
+        );
+        assert_noop!(
+            Pallet::<Test>::response_create(
+                RuntimeOrigin::signed(PROVIDER_3),
+                agreement_id,
+                request_index,
+                content_id,
+                None,
+                0,
+            ),
+            Error::<Test>::ResponseAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn late_replica_response_settled_on_last_request() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_redundant_agreement(
+            agreement_id,
+            &[PROVIDER_1, PROVIDER_2, PROVIDER_3],
+            price_per_request,
+            1,
+            2,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        let content_id = ContentId::random();
+        create_replica_response(PROVIDER_1, agreement_id, request_index, content_id, None);
+        create_replica_response(PROVIDER_2, agreement_id, request_index, content_id, None);
+        assert!(Agreements::<Test>::contains_key(agreement_id));
+
+        create_replica_response(PROVIDER_3, agreement_id, request_index, content_id, None);
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_3), price_per_request);
+        assert_eq!(Agreements::<Test>::get(agreement_id).unwrap().payments_count, 3);
+    });
+}
+
+#[test]
+fn redundant_payments_refunded_without_quorum() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_redundant_agreement(agreement_id, &[PROVIDER_1, PROVIDER_2, PROVIDER_3], 100, 2, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+
+        for provider in [PROVIDER_1, PROVIDER_2, PROVIDER_3] {
+            create_replica_response(
+                provider,
+                agreement_id,
+                request_index,
+                ContentId::random(),
+                None,
+            );
+        }
+
+        assert!(!Responses::<Test>::contains_key(agreement_id, request_index));
+        assert_eq!(Agreements::<Test>::get(agreement_id).unwrap().responses_count, 1);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&CONSUMER_1), INITIAL_BALANCE - 300);
+        System::assert_last_event(Event::QuorumNotReached { agreement_id, request_index }.into());
+    });
+}
+
+fn create_challenge(
+    agreement_id: AgreementId,
+    request_index: RequestsUsize,
+    content_id: ContentId,
+) {
+    assert_ok!(Pallet::<Test>::challenge_create(
+        RuntimeOrigin::signed(VERIFIER_1),
+        agreement_id,
+        request_index,
+        content_id,
+    ));
+}
+
+#[test]
+fn can_create_challenge() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(CHALLENGE_WINDOW);
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_agreement(
+            agreement_id,
+            ModelId::default(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            5,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        System::set_block_number(System::block_number() + CHALLENGE_WINDOW);
+        let content_id = ContentId::random();
+        create_challenge(agreement_id, request_index, content_id);
+
+        let expected_challenge = ChallengeDetails {
+            challenger: VERIFIER_1,
+            provider: PROVIDER_1,
+            content_id,
+            challenger_stake: CHALLENGE_STAKE,
+            item: None,
+            created_at: System::block_number(),
+        };
+        assert_eq!(Challenges::<Test>::get(agreement_id, request_index), Some(expected_challenge));
+        // The provider's side is backed by its bond instead.
+        for (account, stake) in [(VERIFIER_1, CHALLENGE_STAKE), (PROVIDER_1, 0)] {
+            assert_eq!(
+                <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                    &HoldReason::ChallengeStake.into(),
+                    &account
+                ),
+                stake
+            );
+        }
+        System::assert_last_event(
+            Event::ChallengeCreated {
+                agreement_id,
+                request_index,
+                challenger: VERIFIER_1,
+                content_id,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn fail_create_challenge() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(CHALLENGE_WINDOW);
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 5);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+
+        assert_noop!(
+            Pallet::<Test>::challenge_create(
+                RuntimeOrigin::signed(VERIFIER_1),
+                agreement_id,
+                request_index,
+                ContentId::random(),
+            ),
+            Error::<Test>::ResponseNotFound
+        );
+
+        create_response(PROVIDER_1, agreement_id, request_index);
+        assert_noop!(
+            Pallet::<Test>::challenge_create(
+                RuntimeOrigin::signed(PROVIDER_1),
+                agreement_id,
+                request_index,
+                ContentId::random(),
+            ),
+            Error::<Test>::ChallengeInvalid
+        );
+
+        create_challenge(agreement_id, request_index, ContentId::random());
+        assert_noop!(
+            Pallet::<Test>::challenge_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                request_index,
+                ContentId::random(),
+            ),
+            Error::<Test>::ChallengeAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn fail_create_challenge_after_window() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(CHALLENGE_WINDOW);
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 5);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        System::set_block_number(System::block_number() + CHALLENGE_WINDOW + 1);
+
+        assert_noop!(
+            Pallet::<Test>::challenge_create(
+                RuntimeOrigin::signed(VERIFIER_1),
+                agreement_id,
+                request_index,
+                ContentId::random(),
+            ),
+            Error::<Test>::ChallengeWindowElapsed
+        );
+    });
+}
+
+#[test]
+fn can_resolve_upheld_challenge() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_agreement(
+            agreement_id,
+            ModelId::default(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            5,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        create_challenge(agreement_id, request_index, ContentId::random());
+
+        assert_ok!(Pallet::<Test>::challenge_resolve(
+            RuntimeOrigin::root(),
+            agreement_id,
+            request_index,
+            true
+        ));
+
+        // The provider's stake is slashed from its bond, even if it has spent its balance.
+        assert!(!Challenges::<Test>::contains_key(agreement_id, request_index));
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&VERIFIER_1),
+            INITIAL_BALANCE + CHALLENGE_STAKE
+        );
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), price_per_request);
+        assert_eq!(ProviderBond::get(), PROVIDER_BOND - CHALLENGE_STAKE);
+        System::assert_last_event(
+            Event::ChallengeResolved {
+                agreement_id,
+                request_index,
+                winner: VERIFIER_1,
+                loser: PROVIDER_1,
+                slashed: CHALLENGE_STAKE,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn can_resolve_rejected_challenge() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_agreement(
+            agreement_id,
+            ModelId::default(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            5,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        create_challenge(agreement_id, request_index, ContentId::random());
+
+        assert_noop!(
+            Pallet::<Test>::challenge_resolve(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                request_index,
+                false
+            ),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(Pallet::<Test>::challenge_resolve(
+            RuntimeOrigin::root(),
+            agreement_id,
+            request_index,
+            false
+        ));
+
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&VERIFIER_1),
+            INITIAL_BALANCE - CHALLENGE_STAKE
+        );
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&PROVIDER_1),
+            price_per_request + CHALLENGE_STAKE
+        );
+        System::assert_last_event(
+            Event::ChallengeResolved {
+                agreement_id,
+                request_index,
+                winner: PROVIDER_1,
+                loser: VERIFIER_1,
+                slashed: CHALLENGE_STAKE,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn can_expire_challenge() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 5);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        create_challenge(agreement_id, request_index, ContentId::random());
+
+        System::set_block_number(System::block_number() + CHALLENGE_EXPIRY);
+        assert_noop!(
+            Pallet::<Test>::challenge_expire(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                request_index
+            ),
+            Error::<Test>::ChallengeActive
+        );
+        System::set_block_number(System::block_number() + 1);
+        assert_ok!(Pallet::<Test>::challenge_expire(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            request_index
+        ));
+
+        assert!(!Challenges::<Test>::contains_key(agreement_id, request_index));
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&VERIFIER_1), INITIAL_BALANCE);
+        assert_eq!(ProviderBond::get(), PROVIDER_BOND);
+        System::assert_last_event(Event::ChallengeExpired { agreement_id, request_index }.into());
+    });
+}
+
+#[test]
+fn closed_agreement_pruned_after_challenge_window() {
+    new_test_ext().execute_with(|| {
+        ChallengeWindow::set(CHALLENGE_WINDOW);
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 1);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+
+        Pallet::<Test>::on_idle(System::block_number(), Weight::MAX);
+        assert!(Responses::<Test>::contains_key(agreement_id, request_index));
+
+        let prune_at = System::block_number() + CHALLENGE_WINDOW;
+        System::set_block_number(prune_at);
+        Pallet::<Test>::on_idle(prune_at, Weight::MAX);
+        assert!(ClosedAgreements::<Test>::contains_key(agreement_id));
+
+        Pallet::<Test>::on_idle(prune_at, Weight::MAX);
+        assert!(!Requests::<Test>::contains_key(agreement_id, request_index));
+        assert!(!Responses::<Test>::contains_key(agreement_id, request_index));
+    });
+}
+
+fn create_subscription(
+    agreement_id: AgreementId,
+    price_per_period: Balance,
+    period: u64,
+    periods_total: u32,
+    requests_per_period: Option<RequestsUsize>,
+) {
+    assert_ok!(Pallet::<Test>::create_subscription_agreement(
+        CONSUMER_1,
+        PROVIDER_1,
+        agreement_id,
+        ModelId::default(),
+        price_per_period,
+        period,
+        periods_total,
+        requests_per_period,
+        None,
+        None,
+        None,
+    ));
+}
+
+#[test]
+fn can_create_subscription_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_subscription(agreement_id, 100, 10, 5, Some(2));
+
+        let agreement = Agreements::<Test>::get(agreement_id).unwrap();
+        let subscription = agreement.subscription.unwrap();
+        assert_eq!(subscription.starts_at, 1);
+        assert_eq!(subscription.ends_at, 51);
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ProviderPayment.into(),
+                &CONSUMER_1
+            ),
+            500
+        );
+        System::assert_last_event(Event::AgreementCreated { agreement_id }.into());
+    });
+}
+
+#[test]
+fn fail_create_invalid_subscription_agreement() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Pallet::<Test>::create_subscription_agreement(
+                CONSUMER_1,
+                PROVIDER_1,
+                1,
+                ModelId::default(),
+                100,
+                0,
+                5,
+                None,
+                None,
+                None,
+                None,
+            ),
+            Error::<Test>::SubscriptionInvalid
+        );
+    });
+}
+
+#[test]
+fn subscription_requests_limited_per_period() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_subscription(agreement_id, 100, 10, 5, Some(2));
+        create_request(CONSUMER_1, agreement_id);
+        create_request(CONSUMER_1, agreement_id);
+
+        assert_noop!(
+            Pallet::<Test>::request_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                ContentId::default(),
+                None,
+                None,
+            ),
+            Error::<Test>::SubscriptionQuotaExceeded
+        );
+
+        System::set_block_number(11);
+        create_request(CONSUMER_1, agreement_id);
+
+        System::set_block_number(51);
+        assert_noop!(
+            Pallet::<Test>::request_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                ContentId::default(),
+                None,
+                None,
+            ),
+            Error::<Test>::SubscriptionEnded
+        );
+    });
+}
+
+#[test]
+fn subscription_responses_not_paid_per_request() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_subscription(agreement_id, 100, 10, 5, None);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), 0);
+        assert!(Agreements::<Test>::contains_key(agreement_id));
+    });
+}
+
+#[test]
+fn can_settle_subscription() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_period = 100;
+        create_subscription(agreement_id, price_per_period, 10, 5, None);
+
+        System::set_block_number(25);
+        assert_ok!(Pallet::<Test>::subscription_settle(
+            RuntimeOrigin::signed(PROVIDER_1),
+            agreement_id
+        ));
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), 2 * price_per_period);
+        System::assert_last_event(Event::SubscriptionSettled { agreement_id, periods: 2 }.into());
+
+        // The agreement is closed once the subscription ends.
+        System::set_block_number(60);
+        assert_ok!(Pallet::<Test>::subscription_settle(
+            RuntimeOrigin::signed(PROVIDER_1),
+            agreement_id
+        ));
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), 5 * price_per_period);
+        assert!(!Agreements::<Test>::contains_key(agreement_id));
+        System::assert_last_event(Event::AgreementClosed { agreement_id }.into());
+    });
+}
+
+#[test]
+fn can_terminate_subscription() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_period = 100;
+        create_subscription(agreement_id, price_per_period, 10, 5, None);
+
+        System::set_block_number(9);
+        assert_ok!(Pallet::<Test>::subscription_terminate(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+
+        // The notice period elapses at block 12, so the subscription ends with the second period.
+        let ends_at = 21;
+        let subscription = Agreements::<Test>::get(agreement_id).unwrap().subscription.unwrap();
+        assert_eq!(subscription.ends_at, ends_at);
+        System::assert_last_event(Event::SubscriptionTerminated { agreement_id, ends_at }.into());
+
+        assert_noop!(
+            Pallet::<Test>::agreement_close(RuntimeOrigin::signed(CONSUMER_1), agreement_id),
+            Error::<Test>::SubscriptionActive
+        );
+
+        System::set_block_number(ends_at);
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), 2 * price_per_period);
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&CONSUMER_1),
+            INITIAL_BALANCE - 2 * price_per_period
+        );
+    });
+}
+
+#[test]
+fn fail_terminate_subscription() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_subscription(agreement_id, 100, 10, 5, None);
+
+        assert_noop!(
+            Pallet::<Test>::subscription_terminate(RuntimeOrigin::signed(CONSUMER_2), agreement_id),
+            Error::<Test>::AgreementInvalid
+        );
+
+        create_agreement(2, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 5);
+        assert_noop!(
+            Pallet::<Test>::subscription_terminate(RuntimeOrigin::signed(CONSUMER_1), 2),
+            Error::<Test>::SubscriptionInvalid
+        );
+    });
+}
+
+fn create_metered_response(
+    agreement_id: AgreementId,
+    request_index: RequestsUsize,
+    units: UnitsUsize,
+) {
+    assert_ok!(Pallet::<Test>::response_create(
+        RuntimeOrigin::signed(PROVIDER_1),
+        agreement_id,
+        request_index,
+        ContentId::default(),
+        None,
+        units,
+    ));
+}
+
+#[test]
+fn can_pay_metered_responses() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_unit = 2;
+        let max_units = 100;
+        assert_ok!(Pallet::<Test>::create_metered_agreement(
+            CONSUMER_1,
+            PROVIDER_1,
+            agreement_id,
+            ModelId::default(),
+            price_per_unit,
+            2,
+            max_units,
+            None,
+            None,
+            None,
+        ));
+        let provider_payment_on_hold = || {
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ProviderPayment.into(),
+                &CONSUMER_1,
+            )
+        };
+        assert_eq!(provider_payment_on_hold(), price_per_unit * max_units as Balance * 2);
+
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_metered_response(agreement_id, request_index, 30);
+
+        // Only the consumed units are charged, the rest is released.
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), price_per_unit * 30);
+        assert_eq!(provider_payment_on_hold(), price_per_unit * max_units as Balance);
+
+        // The charged units are capped.
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_metered_response(agreement_id, request_index, max_units + 1);
+
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&PROVIDER_1),
+            price_per_unit * (30 + max_units as Balance)
+        );
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&CONSUMER_1),
+            INITIAL_BALANCE - price_per_unit * (30 + max_units as Balance)
+        );
+    });
+}
+
+#[test]
+fn can_pay_metered_royalty() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let max_units = 10;
+        assert_ok!(Pallet::<Test>::create_metered_agreement(
+            CONSUMER_1,
+            PROVIDER_1,
+            agreement_id,
+            BoundedVec::try_from(OWNED_MODEL.as_bytes().to_vec()).unwrap(),
+            1,
+            1,
+            max_units,
+            None,
+            None,
+            None,
+        ));
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::RoyaltyPayment.into(),
+                &CONSUMER_1
+            ),
+            ROYALTY * max_units as Balance
+        );
+
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_metered_response(agreement_id, request_index, 4);
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&OWNER), ROYALTY * 4);
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::RoyaltyPayment.into(),
+                &CONSUMER_1
+            ),
+            0
+        );
+    });
+}
+
+#[test]
+fn can_extend_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_agreement(
+            agreement_id,
+            ModelId::default(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            1,
+        );
+        create_request(CONSUMER_1, agreement_id);
+
+        assert_ok!(Pallet::<Test>::agreement_extend(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            EXTENSION_APPROVAL_THRESHOLD
+        ));
+
+        let requests_total = 1 + EXTENSION_APPROVAL_THRESHOLD;
+        assert_eq!(Agreements::<Test>::get(agreement_id).unwrap().requests_total, requests_total);
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ProviderPayment.into(),
+                &CONSUMER_1
+            ),
+            price_per_request * requests_total as Balance
+        );
+        System::assert_last_event(Event::AgreementExtended { agreement_id, requests_total }.into());
+
+        // More requests can be created.
+        create_request(CONSUMER_1, agreement_id);
+    });
+}
+
+#[test]
+fn can_extend_agreement_with_approval() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 1);
+        let extra_requests = EXTENSION_APPROVAL_THRESHOLD + 1;
+
+        assert_ok!(Pallet::<Test>::agreement_extend(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            extra_requests
+        ));
+
+        assert_eq!(PendingExtensions::<Test>::get(agreement_id), Some(extra_requests));
+        assert_eq!(Agreements::<Test>::get(agreement_id).unwrap().requests_total, 1);
+        System::assert_last_event(
+            Event::AgreementExtensionRequested { agreement_id, extra_requests }.into(),
+        );
+
+        assert_noop!(
+            Pallet::<Test>::agreement_extend_approve(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id
+            ),
+            Error::<Test>::AgreementInvalid
+        );
+        assert_ok!(Pallet::<Test>::agreement_extend_approve(
+            RuntimeOrigin::signed(PROVIDER_1),
+            agreement_id
+        ));
+
+        let requests_total = 1 + extra_requests;
+        assert!(!PendingExtensions::<Test>::contains_key(agreement_id));
+        assert_eq!(Agreements::<Test>::get(agreement_id).unwrap().requests_total, requests_total);
+        System::assert_last_event(Event::AgreementExtended { agreement_id, requests_total }.into());
+    });
+}
+
+#[test]
+fn fail_extend_agreement() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 1);
+
+        assert_noop!(
+            Pallet::<Test>::agreement_extend(RuntimeOrigin::signed(CONSUMER_2), agreement_id, 1),
+            Error::<Test>::AgreementInvalid
+        );
+        assert_noop!(
+            Pallet::<Test>::agreement_extend(RuntimeOrigin::signed(CONSUMER_1), agreement_id, 0),
+            Error::<Test>::AgreementInvalid
+        );
+        assert_noop!(
+            Pallet::<Test>::agreement_extend_approve(
+                RuntimeOrigin::signed(PROVIDER_1),
+                agreement_id
+            ),
+            Error::<Test>::ExtensionNotFound
+        );
+    });
+}
+
+fn asset_balance(account: AccountId) -> Balance {
+    <Assets as fungibles::Inspect<_>>::balance(ASSET, &account)
+}
+
+#[test]
+fn can_pay_agreement_in_asset() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_request = 100;
+        assert_ok!(Pallet::<Test>::create_agreement(
+            CONSUMER_1,
+            PROVIDER_1,
+            agreement_id,
+            BoundedVec::try_from(OWNED_MODEL.as_bytes().to_vec()).unwrap(),
+            price_per_request,
+            10,
+            Some(ASSET),
+            None,
+            None,
+        ));
+
+        // Prepayments in assets are escrowed rather than held.
+        let escrow = Pallet::<Test>::escrow_account(&CONSUMER_1);
+        let prepayment = 10 * (price_per_request + ROYALTY);
+        assert_eq!(asset_balance(CONSUMER_1), INITIAL_BALANCE - prepayment);
+        assert_eq!(asset_balance(escrow), prepayment);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&CONSUMER_1), INITIAL_BALANCE);
+
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        assert_eq!(asset_balance(PROVIDER_1), price_per_request);
+        assert_eq!(asset_balance(OWNER), ROYALTY);
+
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+        assert_eq!(asset_balance(CONSUMER_1), INITIAL_BALANCE - price_per_request - ROYALTY);
+        assert_eq!(asset_balance(escrow), 0);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), 0);
+    });
+}
+
+#[test]
+fn fail_create_agreement_no_asset_funds() {
+    new_test_ext().execute_with(|| {
+        assert!(Pallet::<Test>::create_agreement(
+            CONSUMER_2,
+            PROVIDER_1,
+            1,
+            ModelId::default(),
+            100,
+            10,
+            Some(ASSET),
+            None,
+            None,
+        )
+        .is_err());
+        assert!(!Agreements::<Test>::contains_key(1));
+    });
+}
+
+#[test]
+fn protocol_fee_collected() {
+    new_test_ext().execute_with(|| {
+        ProtocolFee::set(Perbill::from_percent(10));
+        let agreement_id = 1;
+        let price_per_request = 100;
+        create_agreement(
+            agreement_id,
+            BoundedVec::try_from(OWNED_MODEL.as_bytes().to_vec()).unwrap(),
+            CONSUMER_1,
+            PROVIDER_1,
+            price_per_request,
+            10,
+        );
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), 90);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&OWNER), ROYALTY - 10);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&TREASURY), 20);
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::total_balance(&CONSUMER_1),
+            INITIAL_BALANCE - price_per_request - ROYALTY
+        );
+    });
+}
+
+#[test]
+fn asset_protocol_fee_left_to_payee_below_minimum_balance() {
+    new_test_ext().execute_with(|| {
+        ProtocolFee::set(Perbill::from_percent(10));
+        let agreement_id = 1;
+        let price_per_request = 100;
+        assert_ok!(Pallet::<Test>::create_agreement(
+            CONSUMER_1,
+            PROVIDER_1,
+            agreement_id,
+            ModelId::default(),
+            price_per_request,
+            10,
+            Some(ASSET_HIGH_MIN_BALANCE),
+            None,
+            None,
+        ));
+        let request_index = create_request(CONSUMER_1, agreement_id);
+
+        // The treasury holds none of the asset, so it can't receive a fee of 10, which is below
+        // the minimum balance.
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        let balance =
+            |account| <Assets as fungibles::Inspect<_>>::balance(ASSET_HIGH_MIN_BALANCE, &account);
+        assert_eq!(balance(TREASURY), 0);
+        assert_eq!(balance(PROVIDER_1), price_per_request);
+    });
+}
+
+#[test]
+fn storage_deposits_released_on_prune() {
+    new_test_ext().execute_with(|| {
+        RequestDeposit::set(50);
+        ResponseDeposit::set(30);
+        <Balances as fungible::Mutate<_>>::set_balance(&OPERATOR_1, INITIAL_BALANCE);
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 1);
+        assert_ok!(Pallet::<Test>::operator_add(RuntimeOrigin::signed(PROVIDER_1), OPERATOR_1));
+
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        assert_eq!(Requests::<Test>::get(agreement_id, request_index).unwrap().deposit, 50);
+        create_response(OPERATOR_1, agreement_id, request_index);
+
+        // The deposit of a response is held from the account that wrote it.
+        let response = Responses::<Test>::get(agreement_id, request_index).unwrap();
+        assert_eq!((response.depositor, response.deposit), (OPERATOR_1, 30));
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ResponseDeposit.into(),
+                &OPERATOR_1
+            ),
+            30
+        );
+
+        // Once the agreement is closed, its entries are pruned.
+        assert_ok!(Pallet::<Test>::agreement_close(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id
+        ));
+        Pallet::<Test>::on_idle(System::block_number(), Weight::MAX);
+        assert!(!Responses::<Test>::contains_key(agreement_id, request_index));
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::RequestDeposit.into(),
+                &CONSUMER_1
+            ),
+            0
+        );
+        assert_eq!(
+            <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+                &HoldReason::ResponseDeposit.into(),
+                &OPERATOR_1
+            ),
+            0
+        );
+    });
+}
+
+#[test]
+fn can_expire_request() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+
+        System::set_block_number(1 + REQUEST_TIMEOUT);
+        assert_noop!(
+            Pallet::<Test>::request_expire(RuntimeOrigin::signed(CONSUMER_2), agreement_id, 2),
+            Error::<Test>::RequestNotFound
+        );
+        assert_noop!(
+            Pallet::<Test>::request_expire(
+                RuntimeOrigin::signed(CONSUMER_2),
+                agreement_id,
+                request_index
+            ),
+            Error::<Test>::RequestActive
+        );
+
+        System::set_block_number(2 + REQUEST_TIMEOUT);
+        assert_ok!(Pallet::<Test>::request_expire(
+            RuntimeOrigin::signed(CONSUMER_2),
+            agreement_id,
+            request_index
+        ));
+
+        assert!(!Requests::<Test>::contains_key(agreement_id, request_index));
+        assert_eq!(Agreements::<Test>::get(agreement_id).unwrap().responses_count, 1);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&CONSUMER_1), INITIAL_BALANCE - 100);
+        assert_eq!(ExecutionOutcomes::get(), vec![ExecutionOutcome::RequestExpired(PROVIDER_1)]);
+        System::assert_last_event(Event::RequestExpired { agreement_id, request_index }.into());
+    });
+}
+
+#[test]
+fn fail_expire_responded_request() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_response(PROVIDER_1, agreement_id, request_index);
+
+        System::set_block_number(2 + REQUEST_TIMEOUT);
+        assert_noop!(
+            Pallet::<Test>::request_expire(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                request_index
+            ),
+            Error::<Test>::ResponseAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn redundant_request_expired_for_missing_providers() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_redundant_agreement(agreement_id, &[PROVIDER_1, PROVIDER_2, PROVIDER_3], 100, 2, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_replica_response(PROVIDER_2, agreement_id, request_index, ContentId::random(), None);
+        ExecutionOutcomes::take();
+
+        System::set_block_number(2 + REQUEST_TIMEOUT);
+        assert_ok!(Pallet::<Test>::request_expire(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            request_index
+        ));
+
+        assert!(!ReplicaResponses::<Test>::contains_key((agreement_id, request_index), PROVIDER_2));
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&CONSUMER_1), INITIAL_BALANCE - 300);
+        assert_eq!(
+            ExecutionOutcomes::get(),
+            vec![
+                ExecutionOutcome::RequestExpired(PROVIDER_1),
+                ExecutionOutcome::RequestExpired(PROVIDER_3)
+            ]
+        );
+    });
+}
+
+#[test]
+fn execution_outcomes_reported() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 5);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+
+        System::set_block_number(4);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        create_challenge(agreement_id, request_index, ContentId::random());
+        assert_ok!(Pallet::<Test>::challenge_resolve(
+            RuntimeOrigin::root(),
+            agreement_id,
+            request_index,
+            true
+        ));
+
+        assert_eq!(
+            ExecutionOutcomes::get(),
+            vec![
+                ExecutionOutcome::Response(PROVIDER_1, 3),
+                ExecutionOutcome::ChallengeLost(PROVIDER_1)
+            ]
+        );
+    });
+}
+
+#[test]
+fn max_latency_enforced() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let requirements = ExecutionRequirements {
+            max_latency: Some(2),
+            min_hardware_class: None,
+            region: None,
+            retention: None,
+        };
+        assert_ok!(Pallet::<Test>::create_agreement(
+            CONSUMER_1,
+            PROVIDER_1,
+            agreement_id,
+            ModelId::default(),
+            100,
+            2,
+            None,
+            Some(requirements),
+            None,
+        ));
+        let request_index = create_request(CONSUMER_1, agreement_id);
+
+        System::set_block_number(4);
+        assert_noop!(
+            Pallet::<Test>::response_create(
+                RuntimeOrigin::signed(PROVIDER_1),
+                agreement_id,
+                request_index,
+                ContentId::default(),
+                None,
+                0
+            ),
+            Error::<Test>::LatencyExceeded
+        );
+
+        // The request can be expired before the default request timeout.
+        assert_ok!(Pallet::<Test>::request_expire(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            request_index
+        ));
+
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        System::set_block_number(6);
+        create_response(PROVIDER_1, agreement_id, request_index);
+    });
+}
+
+fn create_pipeline_agreements(first_stage: AgreementId, providers: &[AccountId]) {
+    let stages: Vec<_> = providers
+        .iter()
+        .enumerate()
+        .map(|(index, provider)| PipelineStage {
+            provider: *provider,
+            order_id: first_stage + index as AgreementId,
+            model_id: ModelId::default(),
+            model_version: None,
+            price_per_request: 100,
+        })
+        .collect();
+    assert_ok!(Pallet::<Test>::create_pipeline_agreements(CONSUMER_1, &stages, 2, None, None));
+}
+
+#[test]
+fn response_forwarded_to_next_pipeline_stage() {
+    new_test_ext().execute_with(|| {
+        create_pipeline_agreements(1, &[PROVIDER_1, PROVIDER_2]);
+        assert_eq!(Agreements::<Test>::get(1).unwrap().next_stage, Some(2));
+        assert_eq!(Agreements::<Test>::get(2).unwrap().previous_stage, Some(1));
+
+        // Only the first stage takes requests from the consumer
+        assert_noop!(
+            Pallet::<Test>::request_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                2,
+                ContentId::default(),
+                None,
+                None
+            ),
+            Error::<Test>::RequestNotAllowed
+        );
+
+        let request_index = create_request(CONSUMER_1, 1);
+        let content_id = ContentId::repeat_byte(1);
+        create_replica_response(PROVIDER_1, 1, request_index, content_id, None);
+
+        let request = Requests::<Test>::get(2, 1).unwrap();
+        assert_eq!(request.content_id, content_id);
+        assert_eq!(request.requester, CONSUMER_1);
+        System::assert_last_event(
+            Event::RequestCreated { agreement_id: 2, request_index: 1, content_id, params: None }
+                .into(),
+        );
+
+        create_response(PROVIDER_2, 2, 1);
+        assert!(Responses::<Test>::contains_key(2, 1));
+    });
+}
+
+#[test]
+fn response_not_forwarded_to_closed_pipeline_stage() {
+    new_test_ext().execute_with(|| {
+        create_pipeline_agreements(1, &[PROVIDER_1, PROVIDER_2]);
+        assert_ok!(Pallet::<Test>::agreement_close(RuntimeOrigin::signed(CONSUMER_1), 2));
+
+        let request_index = create_request(CONSUMER_1, 1);
+        let content_id = ContentId::repeat_byte(1);
+        // The response is still accepted and paid
+        create_replica_response(PROVIDER_1, 1, request_index, content_id, None);
+
+        assert!(Responses::<Test>::contains_key(1, request_index));
+        System::assert_last_event(
+            Event::ResponseNotForwarded { agreement_id: 2, content_id }.into(),
+        );
+    });
+}
+
+#[test]
+fn fail_create_single_stage_pipeline() {
+    new_test_ext().execute_with(|| {
+        let stages = [PipelineStage {
+            provider: PROVIDER_1,
+            order_id: 1,
+            model_id: ModelId::default(),
+            model_version: None,
+            price_per_request: 100,
+        }];
+
+        assert_noop!(
+            Pallet::<Test>::create_pipeline_agreements(CONSUMER_1, &stages, 2, None, None),
+            Error::<Test>::PipelineInvalid
+        );
+    });
+}
+
+fn create_batch_agreement(
+    agreement_id: AgreementId,
+    price_per_item: Balance,
+    max_items: UnitsUsize,
+) {
+    assert_ok!(Pallet::<Test>::create_metered_agreement(
+        CONSUMER_1,
+        PROVIDER_1,
+        agreement_id,
+        ModelId::default(),
+        price_per_item,
+        2,
+        max_items,
+        None,
+        None,
+        None,
+    ));
+}
+
+#[test]
+fn can_pay_batch_per_item() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let price_per_item = 2;
+        create_batch_agreement(agreement_id, price_per_item, 100);
+
+        let manifest_id = ContentId::repeat_byte(1);
+        assert_ok!(Pallet::<Test>::batch_request_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            manifest_id,
+            50
+        ));
+        let request_index = Agreements::<Test>::get(agreement_id).unwrap().requests_count;
+        let request = Requests::<Test>::get(agreement_id, request_index).unwrap();
+        assert_eq!(request.content_id, manifest_id);
+        assert_eq!(request.batch_items, Some(50));
+
+        // More items than the batch has can't be processed
+        assert_noop!(
+            Pallet::<Test>::response_create(
+                RuntimeOrigin::signed(PROVIDER_1),
+                agreement_id,
+                request_index,
+                ContentId::repeat_byte(2),
+                None,
+                51,
+            ),
+            Error::<Test>::BatchItemsExceeded
+        );
+
+        create_metered_response(agreement_id, request_index, 40);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&PROVIDER_1), price_per_item * 40);
+    });
+}
+
+#[test]
+fn fail_create_invalid_batch_request() {
+    new_test_ext().execute_with(|| {
+        create_batch_agreement(1, 2, 100);
+        create_agreement(2, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 2);
+
+        for (agreement_id, items) in [(1, 0), (1, 101), (2, 10)] {
+            assert_noop!(
+                Pallet::<Test>::batch_request_create(
+                    RuntimeOrigin::signed(CONSUMER_1),
+                    agreement_id,
+                    ContentId::default(),
+                    items
+                ),
+                Error::<Test>::BatchInvalid
+            );
+        }
+    });
+}
+
+#[test]
+fn can_challenge_sampled_batch_item() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_batch_agreement(agreement_id, 2, 100);
+        assert_ok!(Pallet::<Test>::batch_request_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            ContentId::default(),
+            50
+        ));
+        let request_index = Agreements::<Test>::get(agreement_id).unwrap().requests_count;
+        create_metered_response(agreement_id, request_index, 40);
+
+        // Only the processed items can be challenged
+        assert_noop!(
+            Pallet::<Test>::batch_item_challenge(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                request_index,
+                40,
+                ContentId::repeat_byte(3)
+            ),
+            Error::<Test>::ChallengeInvalid
+        );
+        assert_ok!(Pallet::<Test>::batch_item_challenge(
+            RuntimeOrigin::signed(CONSUMER_1),
+            agreement_id,
+            request_index,
+            3,
+            ContentId::repeat_byte(3)
+        ));
+
+        let challenge = Challenges::<Test>::get(agreement_id, request_index).unwrap();
+        assert_eq!(challenge.challenger, CONSUMER_1);
+        assert_eq!(challenge.item, Some(3));
+    });
+}
+
+#[test]
+fn fail_challenge_item_of_non_batch_request() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_batch_agreement(agreement_id, 2, 100);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_metered_response(agreement_id, request_index, 40);
+
+        assert_noop!(
+            Pallet::<Test>::batch_item_challenge(
+                RuntimeOrigin::signed(CONSUMER_1),
+                agreement_id,
+                request_index,
+                3,
+                ContentId::repeat_byte(3)
+            ),
+            Error::<Test>::ChallengeInvalid
+        );
+    });
+}
+
+fn create_tipped_request(
+    consumer: AccountId,
+    agreement_id: AgreementId,
+    tip: Balance,
+) -> RequestsUsize {
+    assert_ok!(Pallet::<Test>::request_create(
+        RuntimeOrigin::signed(consumer),
+        agreement_id,
+        ContentId::default(),
+        None,
+        Some(tip)
+    ));
+
+    Agreements::<Test>::get(agreement_id).unwrap().requests_count
+}
+
+fn tip_on_hold(account: AccountId) -> Balance {
+    <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+        &HoldReason::RequestTip.into(),
+        &account,
+    )
+}
+
+#[test]
+fn tip_paid_to_provider() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 3);
+        let tipped_request = create_tipped_request(CONSUMER_1, agreement_id, 50);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        assert_eq!(tip_on_hold(CONSUMER_1), 50);
+
+        let balance = <Balances as fungible::Inspect<_>>::balance(&PROVIDER_1);
+        create_response(PROVIDER_1, agreement_id, request_index);
+        let payment = <Balances as fungible::Inspect<_>>::balance(&PROVIDER_1) - balance;
+
+        let balance = <Balances as fungible::Inspect<_>>::balance(&PROVIDER_1);
+        create_response(PROVIDER_1, agreement_id, tipped_request);
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&PROVIDER_1) - balance,
+            payment + 50
+        );
+        assert_eq!(tip_on_hold(CONSUMER_1), 0);
+    });
+}
+
+#[test]
+fn tip_released_on_expired_request() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_agreement(agreement_id, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 2);
+        let request_index = create_tipped_request(CONSUMER_1, agreement_id, 50);
+        assert_eq!(tip_on_hold(CONSUMER_1), 50);
+
+        System::set_block_number(2 + REQUEST_TIMEOUT);
+        assert_ok!(Pallet::<Test>::request_expire(
+            RuntimeOrigin::signed(CONSUMER_2),
+            agreement_id,
+            request_index
+        ));
+
+        assert_eq!(tip_on_hold(CONSUMER_1), 0);
+        assert_eq!(<Balances as fungible::Inspect<_>>::balance(&CONSUMER_1), INITIAL_BALANCE - 100);
+    });
+}
+
+#[test]
+fn pending_requests_ordered_by_tip_and_age() {
+    new_test_ext().execute_with(|| {
+        create_agreement(1, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 3);
+        create_agreement(2, ModelId::default(), CONSUMER_2, PROVIDER_1, 100, 3);
+        let responded = create_tipped_request(CONSUMER_1, 1, 100);
+        create_response(PROVIDER_1, 1, responded);
+        let oldest = create_request(CONSUMER_1, 1);
+        System::set_block_number(2);
+        let newest = create_request(CONSUMER_2, 2);
+        let tipped = create_tipped_request(CONSUMER_2, 2, 10);
+
+        let pending: Vec<_> = Pallet::<Test>::pending_requests(&PROVIDER_1)
+            .into_iter()
+            .map(|pending| (pending.agreement_id, pending.request_index, pending.tip))
+            .collect();
+        assert_eq!(pending, vec![(2, tipped, 10), (1, oldest, 0), (2, newest, 0)]);
+        assert!(Pallet::<Test>::pending_requests(&PROVIDER_2).is_empty());
+    });
+}
+
+#[test]
+fn pending_requests_include_late_replicas() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_redundant_agreement(agreement_id, &[PROVIDER_1, PROVIDER_2, PROVIDER_3], 100, 2, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        let content_id = ContentId::random();
+        create_replica_response(PROVIDER_1, agreement_id, request_index, content_id, None);
+        create_replica_response(PROVIDER_2, agreement_id, request_index, content_id, None);
+
+        assert!(Pallet::<Test>::pending_requests(&PROVIDER_1).is_empty());
+        let pending: Vec<_> = Pallet::<Test>::pending_requests(&PROVIDER_3)
+            .into_iter()
+            .map(|pending| (pending.agreement_id, pending.request_index))
+            .collect();
+        assert_eq!(pending, vec![(agreement_id, request_index)]);
+    });
+}
+
+#[test]
+fn provider_capacity_limits_open_requests() {
+    new_test_ext().execute_with(|| {
+        create_agreement(1, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 3);
+        create_agreement(2, ModelId::default(), CONSUMER_2, PROVIDER_1, 100, 3);
+        ProviderCapacity::set(Some(2));
+
+        let request_index = create_request(CONSUMER_1, 1);
+        create_request(CONSUMER_2, 2);
+        assert_eq!(OpenRequests::<Test>::get(PROVIDER_1), 2);
+        assert_noop!(
+            Pallet::<Test>::request_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                1,
+                ContentId::default(),
+                None,
+                None
+            ),
+            Error::<Test>::ProviderAtCapacity
+        );
+
+        create_response(PROVIDER_1, 1, request_index);
+        assert_eq!(OpenRequests::<Test>::get(PROVIDER_1), 1);
+        create_request(CONSUMER_1, 1);
+
+        ProviderCapacity::set(None);
+        create_request(CONSUMER_2, 2);
+        assert_eq!(OpenRequests::<Test>::get(PROVIDER_1), 3);
+    });
+}
+
+#[test]
+fn provider_capacity_released_on_expired_request() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_redundant_agreement(agreement_id, &[PROVIDER_1, PROVIDER_2], 100, 2, 2);
+        let request_index = create_request(CONSUMER_1, agreement_id);
+        create_replica_response(
+            PROVIDER_1,
+            agreement_id,
+            request_index,
+            ContentId::default(),
+            None,
+        );
+        assert_eq!(OpenRequests::<Test>::get(PROVIDER_1), 0);
+        assert_eq!(OpenRequests::<Test>::get(PROVIDER_2), 1);
+
+        System::set_block_number(2 + REQUEST_TIMEOUT);
+        assert_ok!(Pallet::<Test>::request_expire(
+            RuntimeOrigin::signed(CONSUMER_2),
+            agreement_id,
+            request_index
+        ));
+
+        assert!(!OpenRequests::<Test>::contains_key(PROVIDER_2));
+    });
+}
+
+#[test]
+fn provider_capacity_released_on_close() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        create_subscription(agreement_id, 100, 10, 1, None);
+        create_agreement(2, ModelId::default(), CONSUMER_2, PROVIDER_1, 100, 3);
+        create_request(CONSUMER_1, agreement_id);
+        create_request(CONSUMER_1, agreement_id);
+        create_request(CONSUMER_2, 2);
+        assert_eq!(OpenRequests::<Test>::get(PROVIDER_1), 3);
+        assert_eq!(AgreementOpenRequests::<Test>::get(agreement_id, PROVIDER_1), 2);
+
+        // The requests left unanswered are released at once when the subscription ends.
+        System::set_block_number(20);
+        assert_ok!(Pallet::<Test>::subscription_settle(
+            RuntimeOrigin::signed(PROVIDER_1),
+            agreement_id
+        ));
+
+        assert!(!Agreements::<Test>::contains_key(agreement_id));
+        assert_eq!(OpenRequests::<Test>::get(PROVIDER_1), 1);
+        assert!(!AgreementOpenRequests::<Test>::contains_key(agreement_id, PROVIDER_1));
+        assert_eq!(AgreementOpenRequests::<Test>::get(2, PROVIDER_1), 1);
+    });
+}
+
+fn set_spending_limits(
+    consumer: AccountId,
+    asset: Option<AssetId>,
+    per_period: Option<Balance>,
+    per_agreement: Option<Balance>,
+) {
+    assert_ok!(Pallet::<Test>::spending_limits_set(
+        RuntimeOrigin::signed(consumer),
+        asset,
+        SpendingLimits { per_period, per_agreement }
+    ));
+}
+
+#[test]
+fn spending_limits_enforced_on_prepayment() {
+    new_test_ext().execute_with(|| {
+        set_spending_limits(CONSUMER_1, None, Some(1_000), Some(600));
+        System::assert_last_event(
+            Event::SpendingLimitsSet {
+                consumer: CONSUMER_1,
+                asset: None,
+                limits: SpendingLimits { per_period: Some(1_000), per_agreement: Some(600) },
+            }
+            .into(),
+        );
+
+        create_agreement(1, ModelId::default(), CONSUMER_1, PROVIDER_1, 200, 3);
+        assert_noop!(
+            Pallet::<Test>::create_agreement(
+                CONSUMER_1,
+                PROVIDER_1,
+                2,
+                ModelId::default(),
+                200,
+                4,
+                None,
+                None,
+                None
+            ),
+            Error::<Test>::SpendingLimitExceeded
+        );
+        create_agreement(2, ModelId::default(), CONSUMER_1, PROVIDER_1, 200, 2);
+        assert_eq!(ConsumerSpending::<Test>::get(CONSUMER_1, None::<AssetId>).spent, 1_000);
+        assert_noop!(
+            Pallet::<Test>::agreement_extend(RuntimeOrigin::signed(CONSUMER_1), 2, 1),
+            Error::<Test>::SpendingLimitExceeded
+        );
+
+        System::set_block_number(SPENDING_PERIOD);
+        assert_ok!(Pallet::<Test>::agreement_extend(RuntimeOrigin::signed(CONSUMER_1), 2, 1));
+        create_agreement(3, ModelId::default(), CONSUMER_2, PROVIDER_1, 100, 10);
+    });
+}
+
+#[test]
+fn spending_limits_enforced_per_asset() {
+    new_test_ext().execute_with(|| {
+        let create_asset_agreement = |agreement_id, requests_total| {
+            Pallet::<Test>::create_agreement(
+                CONSUMER_1,
+                PROVIDER_1,
+                agreement_id,
+                BoundedVec::try_from(OWNED_MODEL.as_bytes().to_vec()).unwrap(),
+                100,
+                requests_total,
+                Some(ASSET),
+                None,
+                None,
+            )
+        };
+        set_spending_limits(CONSUMER_1, Some(ASSET), None, Some(1_000));
+
+        assert_noop!(create_asset_agreement(1, 10), Error::<Test>::SpendingLimitExceeded);
+        assert_ok!(create_asset_agreement(1, 5));
+        // The limits of the asset don't apply to the native currency.
+        create_agreement(2, ModelId::default(), CONSUMER_1, PROVIDER_1, 200, 10);
+        assert_eq!(
+            ConsumerSpendingLimits::<Test>::get(CONSUMER_1, None::<AssetId>),
+            SpendingLimits::default()
+        );
+    });
+}
+
+#[test]
+fn raised_spending_limits_time_locked() {
+    new_test_ext().execute_with(|| {
+        set_spending_limits(CONSUMER_1, None, None, Some(400));
+        set_spending_limits(CONSUMER_1, None, None, Some(1_000));
+        System::assert_last_event(
+            Event::SpendingLimitsScheduled {
+                consumer: CONSUMER_1,
+                asset: None,
+                limits: SpendingLimits { per_period: None, per_agreement: Some(1_000) },
+                effective_at: 1 + SPENDING_LIMIT_DELAY,
+            }
+            .into(),
+        );
+        assert_noop!(
+            Pallet::<Test>::create_agreement(
+                CONSUMER_1,
+                PROVIDER_1,
+                1,
+                ModelId::default(),
+                200,
+                3,
+                None,
+                None,
+                None
+            ),
+            Error::<Test>::SpendingLimitExceeded
+        );
+
+        System::set_block_number(1 + SPENDING_LIMIT_DELAY);
+        create_agreement(1, ModelId::default(), CONSUMER_1, PROVIDER_1, 200, 3);
+
+        set_spending_limits(CONSUMER_1, None, None, Some(200));
+        assert!(!PendingSpendingLimits::<Test>::contains_key(CONSUMER_1, None::<AssetId>));
+        assert_eq!(
+            ConsumerSpendingLimits::<Test>::get(CONSUMER_1, None::<AssetId>).per_agreement,
+            Some(200)
+        );
+    });
+}
+
+#[test]
+fn lowered_spending_limits_apply_when_others_raised() {
+    new_test_ext().execute_with(|| {
+        set_spending_limits(CONSUMER_1, None, Some(1_000), Some(600));
+        // The period limit is lowered while the agreement limit is raised.
+        set_spending_limits(CONSUMER_1, None, Some(500), Some(2_000));
+
+        let applied = SpendingLimits { per_period: Some(500), per_agreement: Some(600) };
+        System::assert_has_event(
+            Event::SpendingLimitsSet { consumer: CONSUMER_1, asset: None, limits: applied.clone() }
+                .into(),
+        );
+        assert_eq!(ConsumerSpendingLimits::<Test>::get(CONSUMER_1, None::<AssetId>), applied);
+        assert_noop!(
+            Pallet::<Test>::create_agreement(
+                CONSUMER_1,
+                PROVIDER_1,
+                1,
+                ModelId::default(),
+                200,
+                3,
+                None,
+                None,
+                None
+            ),
+            Error::<Test>::SpendingLimitExceeded
+        );
+
+        System::set_block_number(1 + SPENDING_LIMIT_DELAY);
+        assert_eq!(
+            SpendingLimit::<Test>::get(&CONSUMER_1, &None),
+            SpendingLimits { per_period: Some(500), per_agreement: Some(2_000) }
+        );
+        create_agreement(1, ModelId::default(), CONSUMER_1, PROVIDER_1, 100, 5);
+    });
+}
+
+#[test]
+fn migrate_v0_to_v1() {
+    new_test_ext().execute_with(|| {
+        let agreement_id = 1;
+        let model_id: ModelId = BoundedVec::try_from(OWNED_MODEL.as_bytes().to_vec()).unwrap();
+        let request_content_id = H256::repeat_byte(1);
+        let response_content_id = H256::repeat_byte(2);
+        StorageVersion::new(0).put::<Pallet<Test>>();
+        migrations::v0::Agreements::<Test>::insert(
+            agreement_id,
+            migrations::v0::OldAgreementDetails {
+                consumer: CONSUMER_1,
+                provider: PROVIDER_1,
+                model_id: model_id.clone(),
+                price_per_request: 100,
+                royalty_per_request: ROYALTY,
+                requests_count: 2,
+                requests_total: 5,
+            },
+        );
+        for request_index in 0..2 {
+            storage::unhashed::put(
+                &Requests::<Test>::hashed_key_for(agreement_id, request_index),
+                &request_content_id,
+            );
+        }
+        storage::unhashed::put(
+            &Responses::<Test>::hashed_key_for(agreement_id, 0),
+            &response_content_id,
+        );
+
+        migrations::MigrateV0ToV1::<Test>::on_runtime_upgrade();
+
+        let mut expected_agreement =
+            AgreementDetails::new(CONSUMER_1, PROVIDER_1, model_id, 100, ROYALTY, 5);
+        expected_agreement.requests_count = 2;
+        expected_agreement.responses_count = 1;
+        expected_agreement.payments_count = 1;
+        assert_eq!(Agreements::<Test>::get(agreement_id), Some(expected_agreement));
+        let request = Requests::<Test>::get(agreement_id, 1).unwrap();
+        assert_eq!(request.requester, CONSUMER_1);
+        assert_eq!(request.content_id, request_content_id);
+        assert_eq!(request.deposit, 0);
+        let response = Responses::<Test>::get(agreement_id, 0).unwrap();
+        assert_eq!(response.provider, PROVIDER_1);
+        assert_eq!(response.content_id, response_content_id);
+        assert_eq!(response.created_at, 0);
+        assert_eq!(StorageVersion::get::<Pallet<Test>>(), 1);
+    });
+}
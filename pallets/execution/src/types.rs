@@ -1,5 +1,13 @@
-use airo_primitives::RequestsUsize;
-use frame_support::traits::tokens::{Fortitude::Polite, Precision::BestEffort, Restriction::Free};
+use airo_primitives::{model::ModelVersion, RequestsUsize, UnitsUsize};
+use frame_support::traits::tokens::{
+    DepositConsequence,
+    Fortitude::Polite,
+    Precision::BestEffort,
+    Preservation::{Expendable, Preserve},
+    Provenance::Extant,
+    Restriction::{Free, OnHold},
+};
+use sp_runtime::{traits::Hash as HashT, ArithmeticError, SaturatedConversion};
 
 use crate::*;
 
@@ -7,9 +15,14 @@ use crate::*;
 pub type BalanceOf<T> =
     <<T as Config>::Currency as FunInspect<<T as frame_system::Config>::AccountId>>::Balance;
 
+/// Type alias for the identifier of the assets agreements can be paid in.
+pub type AssetIdOf<T> =
+    <<T as Config>::Assets as FunsInspect<<T as frame_system::Config>::AccountId>>::AssetId;
+
 pub type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
 pub type Consumer<T> = AccountIdOf<T>;
 pub type Provider<T> = AccountIdOf<T>;
+pub type RequestParams<T> = BoundedVec<u8, <T as Config>::MaxRequestParamsLength>;
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
@@ -23,6 +36,33 @@ pub struct AgreementDetails<T: Config> {
     pub requests_count: RequestsUsize,
     #[codec(compact)]
     pub requests_total: RequestsUsize,
+    #[codec(compact)]
+    pub responses_count: RequestsUsize,
+    /// Additional providers executing every request of a redundant agreement.
+    pub replicas: BoundedVec<Provider<T>, T::MaxReplicas>,
+    /// Number of providers that have to agree on a response for it to be accepted.
+    #[codec(compact)]
+    pub quorum: RequestsUsize,
+    /// Number of provider payments that have been either paid or refunded.
+    #[codec(compact)]
+    pub payments_count: RequestsUsize,
+    /// Terms of a subscription agreement, which is paid per period rather than per request.
+    pub subscription: Option<SubscriptionDetails<T>>,
+    /// Set for agreements priced per usage unit, in which case the price and the royalty are per
+    /// unit, and a request is charged for at most this many units.
+    pub max_units_per_request: Option<UnitsUsize>,
+    /// Asset the prices are in and the agreement is paid in, or the native currency if none.
+    pub asset: Option<AssetIdOf<T>>,
+    /// Requirements on how the requests are executed, acknowledged by the providers.
+    pub requirements: Option<ExecutionRequirements<BlockNumberFor<T>>>,
+    /// Version of the model the providers run, resolved from the order, if the model is
+    /// versioned.
+    pub model_version: Option<ModelVersion>,
+    /// Agreement of the previous stage of a pipeline, which responses are the requests of this
+    /// one.
+    pub previous_stage: Option<T::AgreementId>,
+    /// Agreement of the next stage of a pipeline, which the responses are forwarded to.
+    pub next_stage: Option<T::AgreementId>,
 }
 
 impl<T: Config> AgreementDetails<T> {
@@ -42,9 +82,82 @@ impl<T: Config> AgreementDetails<T> {
             royalty_per_request,
             requests_count: 0,
             requests_total,
+            responses_count: 0,
+            replicas: BoundedVec::default(),
+            quorum: 1,
+            payments_count: 0,
+            subscription: None,
+            max_units_per_request: None,
+            asset: None,
+            requirements: None,
+            model_version: None,
+            previous_stage: None,
+            next_stage: None,
         }
     }
 
+    pub fn with_pipeline(
+        mut self,
+        previous_stage: Option<T::AgreementId>,
+        next_stage: Option<T::AgreementId>,
+    ) -> Self {
+        self.previous_stage = previous_stage;
+        self.next_stage = next_stage;
+        self
+    }
+
+    pub fn with_requirements(
+        mut self,
+        requirements: Option<ExecutionRequirements<BlockNumberFor<T>>>,
+    ) -> Self {
+        self.requirements = requirements;
+        self
+    }
+
+    pub fn with_model_version(mut self, model_version: Option<ModelVersion>) -> Self {
+        self.model_version = model_version;
+        self
+    }
+
+    pub fn with_asset(mut self, asset: Option<AssetIdOf<T>>) -> Self {
+        self.asset = asset;
+        self
+    }
+
+    pub fn with_metering(mut self, max_units_per_request: UnitsUsize) -> Self {
+        self.max_units_per_request = Some(max_units_per_request);
+        self
+    }
+
+    /// Requests of a subscription agreement are only limited by the subscription terms.
+    pub fn with_subscription(mut self, subscription: SubscriptionDetails<T>) -> Self {
+        self.requests_total = RequestsUsize::MAX;
+        self.subscription = Some(subscription);
+        self
+    }
+
+    pub fn with_replicas(
+        mut self,
+        replicas: BoundedVec<Provider<T>, T::MaxReplicas>,
+        quorum: RequestsUsize,
+    ) -> Self {
+        self.replicas = replicas;
+        self.quorum = quorum;
+        self
+    }
+
+    /// A quorum has to be a majority of the providers, and every provider has to be distinct.
+    pub fn is_valid(&self) -> bool {
+        let providers_count = self.providers_count();
+        let distinct = self.replicas.iter().enumerate().all(|(index, replica)| {
+            *replica != self.provider && !self.replicas[..index].contains(replica)
+        });
+
+        distinct
+            && self.quorum <= providers_count
+            && self.quorum.saturating_mul(2) > providers_count
+    }
+
     pub fn is_consumer(&self, consumer: &Consumer<T>) -> bool {
         self.consumer == *consumer
     }
@@ -53,73 +166,837 @@ impl<T: Config> AgreementDetails<T> {
         self.provider == *provider
     }
 
+    pub fn is_redundant(&self) -> bool {
+        !self.replicas.is_empty()
+    }
+
+    pub fn providers(&self) -> impl Iterator<Item = &Provider<T>> {
+        core::iter::once(&self.provider).chain(self.replicas.iter())
+    }
+
+    pub fn providers_count(&self) -> RequestsUsize {
+        (self.replicas.len() as RequestsUsize).saturating_add(1)
+    }
+
+    /// Finds the provider the responder acts for, either directly or as an operator.
+    pub fn responding_provider(&self, responder: &AccountIdOf<T>) -> Option<Provider<T>> {
+        self.providers()
+            .find(|provider| *provider == responder || Operator::<T>::exists(provider, responder))
+            .cloned()
+    }
+
     pub fn next_request_index(&mut self) -> Result<RequestsUsize, Error<T>> {
         if self.requests_count == self.requests_total {
             Err(Error::<T>::RequestNotAllowed)
         } else {
+            if let Some(subscription) = self.subscription.as_mut() {
+                subscription.use_request(frame_system::Pallet::<T>::block_number())?;
+            }
             self.requests_count += 1;
             Ok(self.requests_count)
         }
     }
 
-    pub fn request_exists(&self, request_index: RequestsUsize) -> bool {
-        request_index <= self.requests_count
+    pub fn record_response(&mut self) {
+        self.responses_count.saturating_inc();
+    }
+
+    pub fn has_pending_requests(&self) -> bool {
+        self.responses_count < self.requests_count
+    }
+
+    /// Whether no more requests can be created, unless the agreement is extended.
+    pub fn is_exhausted(&self, now: BlockNumberFor<T>) -> bool {
+        match &self.subscription {
+            Some(subscription) => subscription.has_ended(now),
+            None => self.requests_count == self.requests_total,
+        }
+    }
+
+    fn max_latency(&self) -> Option<BlockNumberFor<T>> {
+        self.requirements.as_ref().and_then(|requirements| requirements.max_latency)
+    }
+
+    /// Whether a request created at `requested_at` can still be responded to.
+    pub fn is_in_time(&self, requested_at: BlockNumberFor<T>) -> bool {
+        let now = frame_system::Pallet::<T>::block_number();
+        self.max_latency()
+            .is_none_or(|max_latency| now <= requested_at.saturating_add(max_latency))
+    }
+
+    /// The block after which a request created at `requested_at` can be expired.
+    pub fn expires_at(&self, requested_at: BlockNumberFor<T>) -> BlockNumberFor<T> {
+        requested_at.saturating_add(self.max_latency().unwrap_or_else(T::RequestTimeout::get))
+    }
+
+    pub fn is_subscription_active(&self, now: BlockNumberFor<T>) -> bool {
+        self.subscription
+            .as_ref()
+            .is_some_and(|subscription| !subscription.has_ended(now))
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct SubscriptionDetails<T: Config> {
+    pub price_per_period: BalanceOf<T>,
+    /// Royalty is paid per period, as the number of requests is not fixed.
+    pub royalty_per_period: BalanceOf<T>,
+    /// Length of a period in blocks.
+    pub period: BlockNumberFor<T>,
+    /// Number of periods paid in advance.
+    #[codec(compact)]
+    pub periods_total: u32,
+    #[codec(compact)]
+    pub periods_paid: u32,
+    pub requests_per_period: Option<RequestsUsize>,
+    pub starts_at: BlockNumberFor<T>,
+    /// Brought forward when the subscription is terminated.
+    pub ends_at: BlockNumberFor<T>,
+    /// Period the requests are currently counted for.
+    #[codec(compact)]
+    pub current_period: u32,
+    #[codec(compact)]
+    pub period_requests_count: RequestsUsize,
+}
+
+impl<T: Config> SubscriptionDetails<T> {
+    pub fn new(
+        price_per_period: BalanceOf<T>,
+        royalty_per_period: BalanceOf<T>,
+        period: BlockNumberFor<T>,
+        periods_total: u32,
+        requests_per_period: Option<RequestsUsize>,
+    ) -> Self {
+        let starts_at = frame_system::Pallet::<T>::block_number();
+        let ends_at = starts_at.saturating_add(period.saturating_mul(periods_total.into()));
+        Self {
+            price_per_period,
+            royalty_per_period,
+            period,
+            periods_total,
+            periods_paid: 0,
+            requests_per_period,
+            starts_at,
+            ends_at,
+            current_period: 0,
+            period_requests_count: 0,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.period.is_zero() && self.periods_total > 0
+    }
+
+    pub fn has_ended(&self, now: BlockNumberFor<T>) -> bool {
+        now >= self.ends_at
+    }
+
+    /// Number of whole periods elapsed since the subscription started.
+    fn periods_at(&self, block: BlockNumberFor<T>) -> u32 {
+        (block.saturating_sub(self.starts_at) / self.period).saturated_into()
+    }
+
+    /// Number of periods that have elapsed but are not paid yet.
+    pub fn periods_due(&self, now: BlockNumberFor<T>) -> u32 {
+        self.periods_at(now.min(self.ends_at)).saturating_sub(self.periods_paid)
+    }
+
+    pub fn use_request(&mut self, now: BlockNumberFor<T>) -> Result<(), Error<T>> {
+        ensure!(!self.has_ended(now), Error::<T>::SubscriptionEnded);
+
+        let current_period = self.periods_at(now);
+        if current_period != self.current_period {
+            self.current_period = current_period;
+            self.period_requests_count = 0;
+        }
+        if self
+            .requests_per_period
+            .is_some_and(|quota| self.period_requests_count >= quota)
+        {
+            return Err(Error::<T>::SubscriptionQuotaExceeded);
+        }
+        self.period_requests_count.saturating_inc();
+
+        Ok(())
+    }
+
+    /// Ends the subscription at the first period boundary after the notice period.
+    pub fn terminate(&mut self, now: BlockNumberFor<T>) -> BlockNumberFor<T> {
+        let notice_elapsed_at = now.saturating_add(T::SubscriptionNoticePeriod::get());
+        let mut periods = self.periods_at(notice_elapsed_at);
+        let period_boundary =
+            self.starts_at.saturating_add(self.period.saturating_mul(periods.into()));
+        if period_boundary < notice_elapsed_at {
+            periods.saturating_inc();
+        }
+
+        let ends_at = self.starts_at.saturating_add(self.period.saturating_mul(periods.into()));
+        self.ends_at = self.ends_at.min(ends_at);
+        self.ends_at
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct RequestDetails<T: Config> {
+    pub requester: AccountIdOf<T>,
+    pub content_id: T::ContentId,
+    pub params: Option<RequestParams<T>>,
+    pub deposit: BalanceOf<T>,
+    pub created_at: BlockNumberFor<T>,
+    /// Number of items of the dataset manifest a batch request references, which are paid per
+    /// item processed.
+    pub batch_items: Option<UnitsUsize>,
+    /// Held from the requester, and paid to the provider of the accepted response.
+    pub tip: BalanceOf<T>,
+}
+
+impl<T: Config> RequestDetails<T> {
+    pub fn new(
+        requester: AccountIdOf<T>,
+        content_id: T::ContentId,
+        params: Option<RequestParams<T>>,
+    ) -> Self {
+        let params_length = params.as_ref().map_or(0, |params| params.len() as u32);
+        let deposit = T::RequestParamsDepositPerByte::get()
+            .saturating_mul(params_length.into())
+            .saturating_add(T::RequestDeposit::get());
+        let created_at = frame_system::Pallet::<T>::block_number();
+        Self {
+            requester,
+            content_id,
+            params,
+            deposit,
+            created_at,
+            batch_items: None,
+            tip: Zero::zero(),
+        }
+    }
+
+    pub fn with_tip(mut self, tip: Option<BalanceOf<T>>) -> Self {
+        self.tip = tip.unwrap_or_else(Zero::zero);
+        self
+    }
+
+    pub fn with_batch(mut self, items: UnitsUsize) -> Self {
+        self.batch_items = Some(items);
+        self
+    }
+
+    /// Whether a response declaring `units` processed does not process more items than the
+    /// batch has.
+    pub fn allows_units(&self, units: UnitsUsize) -> bool {
+        self.batch_items.is_none_or(|items| units <= items)
+    }
+
+    pub fn hold_deposit(&self) -> DispatchResult {
+        if self.deposit != BalanceOf::<T>::zero() {
+            T::Currency::hold(&HoldReason::RequestDeposit.into(), &self.requester, self.deposit)?;
+        }
+        Ok(())
+    }
+
+    pub fn release_deposit(&self) -> DispatchResult {
+        if self.deposit != BalanceOf::<T>::zero() {
+            T::Currency::release(
+                &HoldReason::RequestDeposit.into(),
+                &self.requester,
+                self.deposit,
+                BestEffort,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn hold_tip(&self) -> DispatchResult {
+        if self.tip != BalanceOf::<T>::zero() {
+            T::Currency::hold(&HoldReason::RequestTip.into(), &self.requester, self.tip)?;
+        }
+        Ok(())
+    }
+
+    pub fn pay_tip(&self, provider: &Provider<T>) -> DispatchResult {
+        if self.tip != BalanceOf::<T>::zero() {
+            T::Currency::transfer_on_hold(
+                &HoldReason::RequestTip.into(),
+                &self.requester,
+                provider,
+                self.tip,
+                BestEffort,
+                Free,
+                Polite,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn release_tip(&self) -> DispatchResult {
+        if self.tip != BalanceOf::<T>::zero() {
+            T::Currency::release(
+                &HoldReason::RequestTip.into(),
+                &self.requester,
+                self.tip,
+                BestEffort,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A request a provider has yet to respond to.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, TypeInfo)]
+pub struct PendingRequest<AgreementId, Balance, BlockNumber> {
+    pub agreement_id: AgreementId,
+    pub request_index: RequestsUsize,
+    pub tip: Balance,
+    pub created_at: BlockNumber,
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ResponseDetails<T: Config> {
+    pub provider: Provider<T>,
+    pub content_id: T::ContentId,
+    /// Hash of the output, used to compare the responses of a redundant agreement.
+    pub output_hash: T::Hash,
+    /// Usage units consumed, charged by agreements priced per unit.
+    #[codec(compact)]
+    pub units: UnitsUsize,
+    pub created_at: BlockNumberFor<T>,
+    /// Account the storage deposit is held from.
+    pub depositor: AccountIdOf<T>,
+    pub deposit: BalanceOf<T>,
+}
+
+impl<T: Config> ResponseDetails<T> {
+    /// Unless the output hash is declared, the content ID is hashed instead.
+    pub fn new(
+        provider: Provider<T>,
+        content_id: T::ContentId,
+        output_hash: Option<T::Hash>,
+        units: UnitsUsize,
+    ) -> Self {
+        let output_hash = output_hash.unwrap_or_else(|| T::Hashing::hash_of(&content_id));
+        let created_at = frame_system::Pallet::<T>::block_number();
+        Self {
+            depositor: provider.clone(),
+            provider,
+            content_id,
+            output_hash,
+            units,
+            created_at,
+            deposit: Zero::zero(),
+        }
+    }
+
+    pub fn with_deposit(mut self, depositor: AccountIdOf<T>) -> Self {
+        self.depositor = depositor;
+        self.deposit = T::ResponseDeposit::get();
+        self
+    }
+
+    pub fn without_deposit(mut self) -> Self {
+        self.deposit = Zero::zero();
+        self
+    }
+
+    pub fn hold_deposit(&self) -> DispatchResult {
+        if self.deposit != BalanceOf::<T>::zero() {
+            T::Currency::hold(&HoldReason::ResponseDeposit.into(), &self.depositor, self.deposit)?;
+        }
+        Ok(())
+    }
+
+    pub fn release_deposit(&self) -> DispatchResult {
+        if self.deposit != BalanceOf::<T>::zero() {
+            T::Currency::release(
+                &HoldReason::ResponseDeposit.into(),
+                &self.depositor,
+                self.deposit,
+                BestEffort,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn is_challengeable(&self) -> bool {
+        let challenge_end = self.created_at.saturating_add(T::ChallengeWindow::get());
+        frame_system::Pallet::<T>::block_number() <= challenge_end
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+pub struct DelegateDetails {
+    pub quota: Option<RequestsUsize>,
+    #[codec(compact)]
+    pub requests_count: RequestsUsize,
+}
+
+impl DelegateDetails {
+    pub fn new(quota: Option<RequestsUsize>) -> Self {
+        Self { quota, requests_count: 0 }
+    }
+
+    pub fn use_request<T: Config>(&mut self) -> Result<(), Error<T>> {
+        if self.quota.is_some_and(|quota| self.requests_count >= quota) {
+            Err(Error::<T>::DelegateQuotaExceeded)
+        } else {
+            self.requests_count.saturating_inc();
+            Ok(())
+        }
     }
 }
 
 // Payments
 impl<T: Config> AgreementDetails<T> {
     pub fn hold_consumer_prepayment(&self) -> DispatchResult {
-        T::Currency::hold(
-            &HoldReason::ProviderPayment.into(),
+        // Nothing is settled yet, so the whole prepayment is held.
+        let (payments, royalties) = self.unsettled_prepayment();
+        self.spend(payments.saturating_add(royalties))?;
+        self.hold(HoldReason::ProviderPayment, payments)?;
+        self.hold(HoldReason::RoyaltyPayment, royalties)
+    }
+
+    /// Raises the total number of requests, holding the prepayment for the extra ones.
+    pub fn extend(&mut self, extra_requests: RequestsUsize) -> DispatchResult {
+        self.requests_total = self
+            .requests_total
+            .checked_add(extra_requests)
+            .ok_or(ArithmeticError::Overflow)?;
+
+        let payments_total = extra_requests.saturating_mul(self.providers_count());
+        let payments =
+            self.charge(self.price_per_request, None).saturating_mul(payments_total.into());
+        let royalties = self
+            .charge(self.royalty_per_request, None)
+            .saturating_mul(extra_requests.into());
+        self.spend(payments.saturating_add(royalties))?;
+        self.hold(HoldReason::ProviderPayment, payments)?;
+        self.hold(HoldReason::RoyaltyPayment, royalties)
+    }
+
+    /// Counts `amount` more prepaid against the spending limits of the consumer in the asset of
+    /// the agreement.
+    fn spend(&self, amount: BalanceOf<T>) -> DispatchResult {
+        let (payments, royalties) = self.unsettled_prepayment();
+        SpendingLimit::<T>::spend(
             &self.consumer,
-            self.price_per_request.saturating_mul(self.requests_total.into()),
+            &self.asset,
+            amount,
+            payments.saturating_add(royalties),
         )?;
+        Ok(())
+    }
 
-        if self.royalty_per_request != BalanceOf::<T>::zero() {
-            T::Currency::hold(
-                &HoldReason::RoyaltyPayment.into(),
-                &self.consumer,
-                self.royalty_per_request.saturating_mul(self.requests_total.into()),
-            )?;
+    /// Amount charged for a request consuming `units`, or the maximum amount when `units` are not
+    /// known yet. Only agreements priced per unit depend on the units.
+    fn charge(&self, price: BalanceOf<T>, units: Option<UnitsUsize>) -> BalanceOf<T> {
+        match self.max_units_per_request {
+            Some(max_units) => {
+                let units = units.map_or(max_units, |units| units.min(max_units));
+                price.saturating_mul(units.into())
+            },
+            None => price,
         }
+    }
+
+    /// Releases what is held for a request above the amount charged.
+    fn release_uncharged(
+        &self,
+        reason: HoldReason,
+        price: BalanceOf<T>,
+        charged: BalanceOf<T>,
+    ) -> DispatchResult {
+        self.release(reason, self.charge(price, None).saturating_sub(charged))
+    }
+
+    pub fn pay_provider(&mut self, provider: &Provider<T>, units: UnitsUsize) -> DispatchResult {
+        // Providers of subscription agreements are paid per period instead.
+        let payment = self.charge(self.price_per_request, Some(units));
+        self.pay(HoldReason::ProviderPayment, provider, payment)?;
+        self.release_uncharged(HoldReason::ProviderPayment, self.price_per_request, payment)?;
+        self.payments_count.saturating_inc();
 
         Ok(())
     }
 
-    pub fn transfer_payments(&self) -> DispatchResult {
-        T::Currency::transfer_on_hold(
-            &HoldReason::ProviderPayment.into(),
-            &self.consumer,
-            &self.provider,
-            self.price_per_request,
-            BestEffort,
-            Free,
-            Polite,
-        )?;
+    /// Pays the provider and the royalty for the subscription periods that have elapsed.
+    /// Returns the number of periods paid.
+    pub fn settle_subscription(&mut self, now: BlockNumberFor<T>) -> Result<u32, DispatchError> {
+        let Some(subscription) = &self.subscription else {
+            return Ok(0);
+        };
+        let periods = subscription.periods_due(now);
+        if periods == 0 {
+            return Ok(0);
+        }
+
+        let payment = subscription.price_per_period.saturating_mul(periods.into());
+        let royalties = subscription.royalty_per_period.saturating_mul(periods.into());
+        self.pay(HoldReason::ProviderPayment, &self.provider, payment)?;
+        if let Some((owner, _)) = T::RoyaltyResolver::get_royalty(&self.model_id) {
+            self.pay(HoldReason::RoyaltyPayment, &owner, royalties)?;
+        } else {
+            // Model was made free
+            self.release(HoldReason::RoyaltyPayment, royalties)?;
+        }
+
+        if let Some(subscription) = self.subscription.as_mut() {
+            subscription.periods_paid.saturating_accrue(periods);
+        }
+
+        Ok(periods)
+    }
+
+    pub fn refund_provider_payment(&mut self) -> DispatchResult {
+        self.release(HoldReason::ProviderPayment, self.charge(self.price_per_request, None))?;
+        self.payments_count.saturating_inc();
+
+        Ok(())
+    }
 
+    pub fn pay_royalty(&self, units: UnitsUsize) -> DispatchResult {
         if self.royalty_per_request != BalanceOf::<T>::zero() {
             if let Some((owner, _)) = T::RoyaltyResolver::get_royalty(&self.model_id) {
-                T::Currency::transfer_on_hold(
-                    &HoldReason::RoyaltyPayment.into(),
-                    &self.consumer,
-                    &owner,
+                let royalty = self.charge(self.royalty_per_request, Some(units));
+                self.pay(HoldReason::RoyaltyPayment, &owner, royalty)?;
+                self.release_uncharged(
+                    HoldReason::RoyaltyPayment,
                     self.royalty_per_request,
-                    BestEffort,
-                    Free,
-                    Polite,
+                    royalty,
                 )?;
             } else {
                 // Model was made free
-                T::Currency::release(
-                    &HoldReason::RoyaltyPayment.into(),
-                    &self.consumer,
-                    self.royalty_per_request,
-                    BestEffort,
-                )?;
+                self.refund_royalty()?;
             }
         }
 
         Ok(())
     }
+
+    pub fn refund_royalty(&self) -> DispatchResult {
+        self.release(HoldReason::RoyaltyPayment, self.charge(self.royalty_per_request, None))
+    }
+
+    /// Provider payments and royalties still held for requests that are not settled yet.
+    fn unsettled_prepayment(&self) -> (BalanceOf<T>, BalanceOf<T>) {
+        let payments_unsettled = self
+            .requests_total
+            .saturating_mul(self.providers_count())
+            .saturating_sub(self.payments_count);
+        let royalties_unsettled = self.requests_total.saturating_sub(self.responses_count);
+        let mut payments = self
+            .charge(self.price_per_request, None)
+            .saturating_mul(payments_unsettled.into());
+        let mut royalties = self
+            .charge(self.royalty_per_request, None)
+            .saturating_mul(royalties_unsettled.into());
+
+        if let Some(subscription) = &self.subscription {
+            let periods_unsettled =
+                subscription.periods_total.saturating_sub(subscription.periods_paid);
+            payments.saturating_accrue(
+                subscription.price_per_period.saturating_mul(periods_unsettled.into()),
+            );
+            royalties.saturating_accrue(
+                subscription.royalty_per_period.saturating_mul(periods_unsettled.into()),
+            );
+        }
+
+        (payments, royalties)
+    }
+
+    pub fn transfer_prepayment(&self, new_consumer: &Consumer<T>) -> DispatchResult {
+        let (payments, royalties) = self.unsettled_prepayment();
+        self.transfer_held(HoldReason::ProviderPayment, new_consumer, payments)?;
+        self.transfer_held(HoldReason::RoyaltyPayment, new_consumer, royalties)
+    }
+
+    pub fn release_unused_prepayment(&self) -> DispatchResult {
+        let (payments, royalties) = self.unsettled_prepayment();
+        self.release(HoldReason::ProviderPayment, payments)?;
+        self.release(HoldReason::RoyaltyPayment, royalties)
+    }
+
+    /// Sets aside part of the prepayment. Native prepayments are held, while those made in an
+    /// asset are moved to the escrow account of the consumer.
+    fn hold(&self, reason: HoldReason, amount: BalanceOf<T>) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        match &self.asset {
+            None => T::Currency::hold(&reason.into(), &self.consumer, amount),
+            Some(asset) => T::Assets::transfer(
+                asset.clone(),
+                &self.consumer,
+                &Pallet::<T>::escrow_account(&self.consumer),
+                amount,
+                Preserve,
+            )
+            .map(|_| ()),
+        }
+    }
+
+    /// Returns part of the prepayment to the consumer.
+    fn release(&self, reason: HoldReason, amount: BalanceOf<T>) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        match &self.asset {
+            None => {
+                T::Currency::release(&reason.into(), &self.consumer, amount, BestEffort).map(|_| ())
+            },
+            Some(asset) => T::Assets::transfer(
+                asset.clone(),
+                &Pallet::<T>::escrow_account(&self.consumer),
+                &self.consumer,
+                amount,
+                Expendable,
+            )
+            .map(|_| ()),
+        }
+    }
+
+    /// Pays part of the prepayment to `beneficiary`, less the protocol fee which is collected
+    /// into the treasury.
+    fn pay(
+        &self,
+        reason: HoldReason,
+        beneficiary: &AccountIdOf<T>,
+        amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        let fee = self.protocol_fee(amount);
+        self.transfer(reason, &T::TreasuryAccount::get(), fee)?;
+        self.transfer(reason, beneficiary, amount.saturating_sub(fee))
+    }
+
+    /// The protocol fee on `amount`, unless the treasury can't receive it, e.g. a fee below the
+    /// minimum balance of an asset the treasury does not hold yet, which is left to the payee.
+    fn protocol_fee(&self, amount: BalanceOf<T>) -> BalanceOf<T> {
+        let fee = T::ProtocolFee::get() * amount;
+        if fee.is_zero() {
+            return fee;
+        }
+
+        let treasury = T::TreasuryAccount::get();
+        let consequence = match &self.asset {
+            None => T::Currency::can_deposit(&treasury, fee, Extant),
+            Some(asset) => T::Assets::can_deposit(asset.clone(), &treasury, fee, Extant),
+        };
+        if consequence == DepositConsequence::Success {
+            fee
+        } else {
+            Zero::zero()
+        }
+    }
+
+    fn transfer(
+        &self,
+        reason: HoldReason,
+        beneficiary: &AccountIdOf<T>,
+        amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        match &self.asset {
+            None => T::Currency::transfer_on_hold(
+                &reason.into(),
+                &self.consumer,
+                beneficiary,
+                amount,
+                BestEffort,
+                Free,
+                Polite,
+            )
+            .map(|_| ()),
+            Some(asset) => T::Assets::transfer(
+                asset.clone(),
+                &Pallet::<T>::escrow_account(&self.consumer),
+                beneficiary,
+                amount,
+                Expendable,
+            )
+            .map(|_| ()),
+        }
+    }
+
+    /// Moves part of the prepayment to `new_consumer`, keeping it set aside.
+    fn transfer_held(
+        &self,
+        reason: HoldReason,
+        new_consumer: &Consumer<T>,
+        amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        match &self.asset {
+            None => T::Currency::transfer_on_hold(
+                &reason.into(),
+                &self.consumer,
+                new_consumer,
+                amount,
+                BestEffort,
+                OnHold,
+                Polite,
+            )
+            .map(|_| ()),
+            Some(asset) => T::Assets::transfer(
+                asset.clone(),
+                &Pallet::<T>::escrow_account(&self.consumer),
+                &Pallet::<T>::escrow_account(new_consumer),
+                amount,
+                Expendable,
+            )
+            .map(|_| ()),
+        }
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChallengeDetails<T: Config> {
+    pub challenger: AccountIdOf<T>,
+    pub provider: Provider<T>,
+    /// Content ID of the output the challenger claims to get by re-executing the request.
+    pub content_id: T::ContentId,
+    pub challenger_stake: BalanceOf<T>,
+    /// Index of the item of a batch response, found wrong by sampling the output manifest.
+    pub item: Option<UnitsUsize>,
+    pub created_at: BlockNumberFor<T>,
+}
+
+impl<T: Config> ChallengeDetails<T> {
+    pub fn new(
+        challenger: AccountIdOf<T>,
+        provider: Provider<T>,
+        content_id: T::ContentId,
+    ) -> Self {
+        Self {
+            challenger,
+            provider,
+            content_id,
+            challenger_stake: T::ChallengeStake::get(),
+            item: None,
+            created_at: frame_system::Pallet::<T>::block_number(),
+        }
+    }
+
+    pub fn with_item(mut self, item: Option<UnitsUsize>) -> Self {
+        self.item = item;
+        self
+    }
+
+    pub fn hold_stake(&self) -> DispatchResult {
+        T::Currency::hold(
+            &HoldReason::ChallengeStake.into(),
+            &self.challenger,
+            self.challenger_stake,
+        )
+    }
+
+    pub fn release_stake(&self) -> DispatchResult {
+        T::Currency::release(
+            &HoldReason::ChallengeStake.into(),
+            &self.challenger,
+            self.challenger_stake,
+            BestEffort,
+        )?;
+        Ok(())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        frame_system::Pallet::<T>::block_number()
+            > self.created_at.saturating_add(T::ChallengeExpiry::get())
+    }
+
+    /// Slashes the provider's bond to the challenger if the challenge is upheld, releasing the
+    /// challenger's stake, or slashes the challenger's stake to the provider otherwise. The
+    /// provider's side is taken from its bond, so it can't avoid it by spending its balance.
+    /// Returns the winner, the loser and the slashed amount.
+    pub fn settle(
+        self,
+        upheld: bool,
+    ) -> Result<(AccountIdOf<T>, AccountIdOf<T>, BalanceOf<T>), DispatchError> {
+        if upheld {
+            let slashed =
+                T::ProviderBonds::slash(&self.provider, &self.challenger, T::ChallengeStake::get());
+            self.release_stake()?;
+            Ok((self.challenger, self.provider, slashed))
+        } else {
+            let slashed = T::Currency::transfer_on_hold(
+                &HoldReason::ChallengeStake.into(),
+                &self.challenger,
+                &self.provider,
+                self.challenger_stake,
+                BestEffort,
+                Free,
+                Polite,
+            )?;
+            Ok((self.provider, self.challenger, slashed))
+        }
+    }
+}
+
+/// Limits on the prepayments a consumer makes for its agreements in a currency, native or asset.
+#[derive(
+    CloneNoBound,
+    Encode,
+    Decode,
+    EqNoBound,
+    PartialEqNoBound,
+    RuntimeDebugNoBound,
+    DefaultNoBound,
+    MaxEncodedLen,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct SpendingLimits<T: Config> {
+    /// Maximum amount prepaid per `T::SpendingPeriod`, or unlimited if none.
+    pub per_period: Option<BalanceOf<T>>,
+    /// Maximum prepayment of a single agreement, or unlimited if none.
+    pub per_agreement: Option<BalanceOf<T>>,
+}
+
+impl<T: Config> SpendingLimits<T> {
+    /// Whether any of the limits is higher than in `current`, which takes a time lock.
+    pub fn raises(&self, current: &Self) -> bool {
+        Self::is_raised(self.per_period, current.per_period)
+            || Self::is_raised(self.per_agreement, current.per_agreement)
+    }
+
+    /// The limits which are not higher than in `current`, the others keeping their current value.
+    pub fn without_raises(&self, current: &Self) -> Self {
+        let lowered =
+            |limit, current| if Self::is_raised(limit, current) { current } else { limit };
+        Self {
+            per_period: lowered(self.per_period, current.per_period),
+            per_agreement: lowered(self.per_agreement, current.per_agreement),
+        }
+    }
+
+    fn is_raised(limit: Option<BalanceOf<T>>, current: Option<BalanceOf<T>>) -> bool {
+        match current {
+            Some(current) => limit.is_none_or(|limit| limit > current),
+            None => false,
+        }
+    }
+
+    pub fn allows(&self, agreement_prepayment: BalanceOf<T>) -> bool {
+        self.per_agreement.is_none_or(|limit| agreement_prepayment <= limit)
+    }
+}
+
+/// Amount a consumer has prepaid during the current spending period.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, DefaultNoBound, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct SpendingDetails<T: Config> {
+    pub period_start: BlockNumberFor<T>,
+    pub spent: BalanceOf<T>,
 }
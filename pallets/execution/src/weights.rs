@@ -4,6 +4,23 @@ use frame_support::weights::Weight;
 pub trait WeightInfo {
     fn request_create() -> Weight;
     fn response_create() -> Weight;
+    fn agreement_close() -> Weight;
+    fn agreement_transfer() -> Weight;
+    fn agreement_transfer_approve() -> Weight;
+    fn delegate_add() -> Weight;
+    fn delegate_remove() -> Weight;
+    fn operator_add() -> Weight;
+    fn operator_remove() -> Weight;
+    fn challenge_create() -> Weight;
+    fn challenge_resolve() -> Weight;
+    fn challenge_expire() -> Weight;
+    fn subscription_settle() -> Weight;
+    fn subscription_terminate() -> Weight;
+    fn agreement_extend() -> Weight;
+    fn agreement_extend_approve() -> Weight;
+    fn request_expire() -> Weight;
+    fn spending_limits_set() -> Weight;
+    fn prune_request() -> Weight;
 }
 
 /// Weights used for tests only.
@@ -15,4 +32,72 @@ impl WeightInfo for () {
     fn response_create() -> Weight {
         Weight::from_parts(10_000_000, 0)
     }
+
+    fn agreement_close() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn agreement_transfer() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn agreement_transfer_approve() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn delegate_add() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn delegate_remove() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn operator_add() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn operator_remove() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn challenge_create() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn challenge_resolve() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn challenge_expire() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn subscription_settle() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn subscription_terminate() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn agreement_extend() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn agreement_extend_approve() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn request_expire() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn spending_limits_set() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn prune_request() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
 }
@@ -5,7 +5,9 @@ use frame_support::assert_ok;
 use frame_system::RawOrigin;
 use sp_std::prelude::*;
 
-pub use airo_primitives::benchmarking::ModelFactory;
+pub use airo_primitives::benchmarking::{
+    ModelFactory, ModelVersionFactory, ProviderFactory, SignerFactory,
+};
 
 #[allow(unused)]
 use crate::Pallet as AiroMarket;
@@ -23,16 +25,24 @@ fn create_order<T: Config>(consumer: T::AccountId) -> T::OrderId {
         RawOrigin::Signed(consumer).into(),
         T::BenchmarkHelper::get_model_id(),
         10_000,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     ));
     CurrentOrderId::<T>::get()
 }
 
 fn create_bid<T: Config>(provider: T::AccountId, order_id: T::OrderId) {
+    T::BenchmarkHelper::register_provider(&provider, T::BenchmarkHelper::get_model_id());
     let price_per_request = BalanceOf::<T>::from(10u32);
     assert_ok!(AiroMarket::<T>::bid_create(
         RawOrigin::Signed(provider).into(),
         order_id,
-        price_per_request
+        price_per_request,
+        None
     ));
 }
 
@@ -47,7 +57,17 @@ mod benchmarks {
         let requests_total = 100;
 
         #[extrinsic_call]
-        _(RawOrigin::Signed(caller), model_id, requests_total);
+        _(
+            RawOrigin::Signed(caller),
+            model_id,
+            requests_total,
+            Some(1_000),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
     }
 
     #[benchmark]
@@ -56,10 +76,11 @@ mod benchmarks {
         let order_id = create_order::<T>(consumer);
 
         let caller: T::AccountId = whitelisted_caller();
+        T::BenchmarkHelper::register_provider(&caller, T::BenchmarkHelper::get_model_id());
         let price_per_request = BalanceOf::<T>::from(100u32);
 
         #[extrinsic_call]
-        _(RawOrigin::Signed(caller), order_id, price_per_request);
+        _(RawOrigin::Signed(caller), order_id, price_per_request, None);
     }
 
     #[benchmark]
@@ -74,5 +95,196 @@ mod benchmarks {
         _(RawOrigin::Signed(consumer), order_id, provider);
     }
 
+    #[benchmark]
+    fn bids_accept(p: Linear<1, { T::MaxAgreementProviders::get() }>) {
+        let consumer: T::AccountId = whitelisted_caller();
+        let order_id = create_order::<T>(consumer.clone());
+
+        let providers: Vec<T::AccountId> =
+            (0..p).map(|index| get_account::<T>(2 + index)).collect();
+        for provider in providers.iter() {
+            create_bid::<T>(provider.clone(), order_id);
+        }
+        let providers = BoundedVec::try_from(providers).unwrap();
+        let quorum = p / 2 + 1;
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(consumer), order_id, providers, quorum);
+    }
+
+    #[benchmark]
+    fn subscription_order_create() {
+        let caller: T::AccountId = whitelisted_caller();
+        let model_id = T::BenchmarkHelper::get_model_id();
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(caller),
+            model_id,
+            100u32.into(),
+            10,
+            Some(100),
+            None,
+            None,
+            None,
+            None,
+        );
+    }
+
+    #[benchmark]
+    fn order_invite() {
+        let caller: T::AccountId = whitelisted_caller();
+        assert_ok!(AiroMarket::<T>::order_create(
+            RawOrigin::Signed(caller.clone()).into(),
+            T::BenchmarkHelper::get_model_id(),
+            10_000,
+            None,
+            None,
+            None,
+            Some(BoundedVec::default()),
+            None,
+            None,
+        ));
+        let order_id = CurrentOrderId::<T>::get();
+        let provider = get_account::<T>(2);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), order_id, provider.clone());
+
+        assert!(Orders::<T>::get(order_id).unwrap().allows(&provider));
+    }
+
+    #[benchmark]
+    fn auction_order_create() {
+        let caller: T::AccountId = whitelisted_caller();
+        let model_id = T::BenchmarkHelper::get_model_id();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), model_id, 100, 1u32.into(), 1u32.into(), 100u32.into(), None);
+    }
+
+    #[benchmark]
+    fn order_match() {
+        let consumer = get_account::<T>(1);
+        assert_ok!(AiroMarket::<T>::auction_order_create(
+            RawOrigin::Signed(consumer).into(),
+            T::BenchmarkHelper::get_model_id(),
+            10_000,
+            1u32.into(),
+            1u32.into(),
+            100u32.into(),
+            None,
+        ));
+        let order_id = CurrentOrderId::<T>::get();
+        let provider: T::AccountId = whitelisted_caller();
+        T::BenchmarkHelper::register_provider(&provider, T::BenchmarkHelper::get_model_id());
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(provider), order_id, None);
+
+        assert!(Orders::<T>::get(order_id).is_none());
+    }
+
+    #[benchmark]
+    fn settle_match() {
+        let model_id = T::BenchmarkHelper::get_model_id();
+        let expires_at = frame_system::Pallet::<T>::block_number() + 10u32.into();
+        let (consumer_public, consumer) = T::BenchmarkHelper::signer(1);
+        let (provider_public, provider) = T::BenchmarkHelper::signer(2);
+        T::BenchmarkHelper::register_provider(&provider, model_id.clone());
+
+        let order = OffchainOrder::<T> {
+            consumer,
+            model_id: model_id.clone(),
+            requests_total: 10_000,
+            max_price_per_request: 10u32.into(),
+            asset: None,
+            requirements: None,
+            model_version: None,
+            nonce: 0,
+            expires_at,
+        };
+        let order_signature = T::BenchmarkHelper::sign(&consumer_public, &order.signing_payload());
+        let bid = OffchainBid::<T> {
+            provider: provider.clone(),
+            order_hash: order.hash(),
+            model_id,
+            model_version: order.resolve_model_version(),
+            requests_total: order.requests_total,
+            price_per_request: 10u32.into(),
+            asset: None,
+            requirements: None,
+            nonce: 0,
+            expires_at,
+        };
+        let bid_signature = T::BenchmarkHelper::sign(&provider_public, &bid.signing_payload());
+        let caller: T::AccountId = whitelisted_caller();
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(caller),
+            Box::new(order),
+            order_signature,
+            Box::new(bid),
+            bid_signature,
+        );
+
+        assert!(OffchainNonces::<T>::contains_key(&provider, 0));
+    }
+
+    #[benchmark]
+    fn pipeline_order_create(s: Linear<2, { T::MaxPipelineStages::get() }>) {
+        let caller: T::AccountId = whitelisted_caller();
+        let stages: Vec<_> = (0..s).map(|_| T::BenchmarkHelper::get_model_id()).collect();
+        let stages = BoundedVec::try_from(stages).unwrap();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), stages, 100, None, None);
+    }
+
+    #[benchmark]
+    fn pipeline_bids_accept(p: Linear<2, { T::MaxPipelineStages::get() }>) {
+        let consumer: T::AccountId = whitelisted_caller();
+        let order_id = CurrentOrderId::<T>::get() + One::one();
+        let stages: Vec<_> = (0..p).map(|_| T::BenchmarkHelper::get_model_id()).collect();
+        assert_ok!(AiroMarket::<T>::pipeline_order_create(
+            RawOrigin::Signed(consumer.clone()).into(),
+            BoundedVec::try_from(stages).unwrap(),
+            10_000,
+            None,
+            None,
+        ));
+
+        let mut stage_order = Some(order_id);
+        let providers: Vec<T::AccountId> = (0..p)
+            .map(|index| {
+                let stage_order_id = stage_order.unwrap();
+                let provider = get_account::<T>(2 + index);
+                create_bid::<T>(provider.clone(), stage_order_id);
+                stage_order =
+                    Orders::<T>::get(stage_order_id).unwrap().pipeline.unwrap().next_stage;
+                provider
+            })
+            .collect();
+        let providers = BoundedVec::try_from(providers).unwrap();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(consumer), order_id, providers);
+
+        assert!(Orders::<T>::get(order_id).is_none());
+    }
+
+    #[benchmark]
+    fn order_version_pin() {
+        let caller: T::AccountId = whitelisted_caller();
+        let order_id = create_order::<T>(caller.clone());
+        let version = T::BenchmarkHelper::create_version(&T::BenchmarkHelper::get_model_id());
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), order_id, Some(version));
+
+        assert_eq!(Orders::<T>::get(order_id).unwrap().model_version, Some(version));
+    }
+
     impl_benchmark_test_suite!(AiroMarket, mock::new_test_ext(), mock::Test);
 }
@@ -8,9 +8,20 @@ use frame_support::{
     traits::fungible::{hold::Mutate as FunHoldMutate, Inspect as FunInspect, Mutate as FunMutate},
 };
 use frame_system::pallet_prelude::*;
-use sp_runtime::traits::{One, Zero};
-
-use airo_primitives::{agreement::AgreementManagement, RequestsUsize};
+use sp_runtime::{
+    traits::{IdentifyAccount, One, Saturating, Verify, Zero},
+    Perbill, SaturatedConversion,
+};
+use sp_std::{boxed::Box, vec::Vec};
+
+use airo_primitives::{
+    agreement::{AgreementManagement, PipelineStage},
+    model::{ModelRegistry, ModelVersion},
+    payment::AssetRegistry,
+    provider::ProviderRegistry,
+    requirements::ExecutionRequirements,
+    RequestsUsize, UnitsUsize,
+};
 pub use pallet::*;
 use storage::*;
 use types::*;
@@ -32,6 +43,7 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 pub mod benchmarking;
 
+pub mod migrations;
 mod storage;
 mod types;
 pub mod weights;
@@ -40,7 +52,11 @@ pub mod weights;
 pub mod pallet {
     use super::*;
 
+    /// The in-code storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
@@ -64,16 +80,59 @@ pub mod pallet {
         /// Order ID type.
         type OrderId: Member + Parameter + MaxEncodedLen + One + Zero + Default + Copy;
 
+        /// Asset ID type, of the assets orders can be paid in.
+        type AssetId: Member + Parameter + MaxEncodedLen;
+
+        /// The maximum number of providers an agreement can be made with.
+        #[pallet::constant]
+        type MaxAgreementProviders: Get<u32>;
+
+        /// The maximum number of providers allowed to bid on a private order.
+        #[pallet::constant]
+        type MaxAllowedProviders: Get<u32>;
+
+        /// The maximum number of stages of a pipeline order.
+        #[pallet::constant]
+        type MaxPipelineStages: Get<u32>;
+
         /// Used to operate on agreements.
         type AgreementManagement: AgreementManagement<
             AccountId = Self::AccountId,
             OrderId = Self::OrderId,
             ModelId = Self::ModelId,
             Balance = BalanceOf<Self>,
+            BlockNumber = BlockNumberFor<Self>,
+            AssetId = Self::AssetId,
+        >;
+
+        /// Used to check that bidders are registered providers of the ordered model.
+        type ProviderRegistry: ProviderRegistry<
+            AccountId = Self::AccountId,
+            ModelId = Self::ModelId,
         >;
 
+        /// Used to find the versions of the models orders are pinned to.
+        type ModelRegistry: ModelRegistry<ModelId = Self::ModelId>;
+
+        /// Used to check that orders are paid in sufficient assets, which the escrow accounts of
+        /// the agreements can receive.
+        type AssetRegistry: AssetRegistry<Self::AssetId>;
+
+        /// Off-chain signature type, of the orders and bids signed off-chain.
+        type OffchainSignature: Verify<Signer = Self::OffchainPublic> + Parameter;
+
+        /// Off-chain public key type, identifying the signer of an order or bid.
+        type OffchainPublic: IdentifyAccount<AccountId = Self::AccountId>;
+
         #[cfg(feature = "runtime-benchmarks")]
-        type BenchmarkHelper: benchmarking::ModelFactory<Self::ModelId>;
+        type BenchmarkHelper: benchmarking::ModelFactory<Self::ModelId>
+            + benchmarking::ModelVersionFactory<Self::ModelId>
+            + benchmarking::ProviderFactory<Self::AccountId, Self::ModelId>
+            + benchmarking::SignerFactory<
+                Self::OffchainPublic,
+                Self::AccountId,
+                Self::OffchainSignature,
+            >;
     }
 
     /// The current order ID. This is incremented when a new order is created.
@@ -101,6 +160,11 @@ pub mod pallet {
     pub type OrderBids<T: Config> =
         StorageDoubleMap<_, Blake2_128Concat, T::OrderId, Twox64Concat, Provider<T>, BidDetails<T>>;
 
+    /// Nonces of the off-chain orders and bids already settled, by signer.
+    #[pallet::storage]
+    pub type OffchainNonces<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, T::AccountId, Twox64Concat, OffchainNonce, ()>;
+
     /// A reason for the Market pallet placing a hold on funds.
     #[pallet::composite_enum]
     pub enum HoldReason {
@@ -136,6 +200,57 @@ pub mod pallet {
             /// The provider.
             provider: T::AccountId,
         },
+        /// Several bids have been accepted for a redundant agreement.
+        BidsAccepted {
+            /// The order ID.
+            order_id: T::OrderId,
+            /// The providers.
+            providers: BoundedVec<T::AccountId, T::MaxAgreementProviders>,
+            /// The number of providers that must agree on a response.
+            quorum: RequestsUsize,
+        },
+        /// An auction order has been matched by a provider, and an agreement has been created.
+        OrderMatched {
+            /// The order ID.
+            order_id: T::OrderId,
+            /// The provider.
+            provider: T::AccountId,
+            /// The price per request the order has been matched at.
+            price_per_request: BalanceOf<T>,
+        },
+        /// An order and a bid signed off-chain have been settled, and an agreement has been
+        /// created.
+        MatchSettled {
+            /// The order ID assigned to the off-chain order.
+            order_id: T::OrderId,
+            /// The consumer.
+            consumer: T::AccountId,
+            /// The provider.
+            provider: T::AccountId,
+            /// The price per request of the bid.
+            price_per_request: BalanceOf<T>,
+        },
+        /// A bid has been accepted for every stage of a pipeline order.
+        PipelineBidsAccepted {
+            /// The order ID of the first stage.
+            order_id: T::OrderId,
+            /// The providers, by stage.
+            providers: BoundedVec<T::AccountId, T::MaxPipelineStages>,
+        },
+        /// A provider has been allowed to bid on a private order.
+        ProviderInvited {
+            /// The order ID.
+            order_id: T::OrderId,
+            /// The provider.
+            provider: T::AccountId,
+        },
+        /// An order has been pinned to a version of its model, or unpinned if none.
+        OrderVersionPinned {
+            /// The order ID.
+            order_id: T::OrderId,
+            /// The version.
+            version: Option<ModelVersion>,
+        },
     }
 
     /// Errors.
@@ -149,22 +264,68 @@ pub mod pallet {
         BidNotFound,
         /// Bid already exists.
         BidAlreadyExists,
+        /// Bid is invalid.
+        BidInvalid,
+        /// Provider is not registered to serve the ordered model.
+        ProviderNotRegistered,
+        /// Provider's reputation is below the minimum required by the order.
+        ProviderReputationTooLow,
+        /// Provider is not allowed to bid on the private order.
+        ProviderNotAllowed,
+        /// Private order already allows the maximum number of providers.
+        TooManyAllowedProviders,
+        /// Bid does not acknowledge the requirements of the order.
+        RequirementsNotAcknowledged,
+        /// Signature of an off-chain order or bid is invalid.
+        SignatureInvalid,
+        /// Nonce of an off-chain order or bid has already been used.
+        NonceAlreadyUsed,
+        /// Off-chain order or bid has expired.
+        OfferExpired,
+        /// Pipeline has less than two stages, or the providers do not match its stages.
+        PipelineInvalid,
+        /// Prepayment exceeds the spending limits of the consumer.
+        SpendingLimitExceeded,
+        /// Version of the ordered model is not found.
+        VersionNotFound,
+        /// Asset is not found or is not sufficient.
+        AssetInvalid,
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
-        /// Creates a new order on the market.
+        /// Creates a new order on the market. If `max_units_per_request` is set, bids are priced
+        /// per usage unit, and every request is charged for at most this many units. Bids are
+        /// priced in `asset`, or in the native currency if none. Only providers with at least
+        /// `min_reputation` can bid, if set, and only the providers in `allowlist` for a private
+        /// order. The `requirements` are copied into the agreement. The order is pinned to
+        /// `model_version`, or to the latest version of the model if none.
         #[pallet::call_index(0)]
         #[pallet::weight(T::WeightInfo::order_create())]
+        #[allow(clippy::too_many_arguments)]
         pub fn order_create(
             origin: OriginFor<T>,
             model_id: T::ModelId,
             #[pallet::compact] requests_total: RequestsUsize,
+            max_units_per_request: Option<UnitsUsize>,
+            asset: Option<T::AssetId>,
+            min_reputation: Option<Perbill>,
+            allowlist: Option<OrderAllowlist<T>>,
+            requirements: Option<OrderRequirements<T>>,
+            model_version: Option<ModelVersion>,
         ) -> DispatchResult {
             let consumer = ensure_signed(origin)?;
 
-            let order_details = OrderDetails::new(consumer, model_id.clone(), requests_total);
+            let order_details = OrderDetails::new(consumer, model_id.clone(), requests_total)
+                .with_metering(max_units_per_request)
+                .with_asset(asset)
+                .with_min_reputation(min_reputation)
+                .with_allowlist(allowlist)
+                .with_requirements(requirements)
+                .with_model_version(model_version);
             ensure!(order_details.is_valid(), Error::<T>::OrderInvalid);
+            ensure!(order_details.has_model_version(), Error::<T>::VersionNotFound);
+            Self::ensure_asset_valid(&order_details.asset)?;
 
             let order_id = Order::<T>::insert(order_details);
 
@@ -172,27 +333,25 @@ pub mod pallet {
             Ok(())
         }
 
-        /// Executed by a provider to create a bid on an order.
+        /// Executed by a registered provider of the ordered model to create a bid on an order. The
+        /// price of a bid on a subscription order is per period, and on a metered order per usage
+        /// unit. The bid has to acknowledge the `requirements` of the order.
         #[pallet::call_index(1)]
         #[pallet::weight(T::WeightInfo::bid_create())]
         pub fn bid_create(
             origin: OriginFor<T>,
             order_id: T::OrderId,
             price_per_request: BalanceOf<T>,
+            requirements: Option<OrderRequirements<T>>,
         ) -> DispatchResult {
             let provider = ensure_signed(origin)?;
 
-            ensure!(Order::<T>::exists(order_id), Error::<T>::OrderNotFound);
-            ensure!(!Bid::<T>::exists(order_id, &provider), Error::<T>::BidAlreadyExists);
-
             let bid_details = BidDetails::new(provider.clone(), price_per_request);
-            Bid::<T>::insert(order_id, &provider, bid_details);
-
-            Self::deposit_event(Event::BidCreated { order_id, provider, price_per_request });
-            Ok(())
+            Self::create_bid(order_id, provider, bid_details, requirements)
         }
 
-        /// Executed by a consumer to accept a bid on an order.
+        /// Executed by a consumer to accept a bid on an order of a model the provider still serves.
+        /// The prepayment for the bid has to be within the spending limits of the consumer.
         #[pallet::call_index(2)]
         #[pallet::weight(T::WeightInfo::bid_accept())]
         pub fn bid_accept(
@@ -203,21 +362,514 @@ pub mod pallet {
             let consumer = ensure_signed(origin)?;
 
             let order = Orders::<T>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
-            ensure!(order.is_owned_by(&consumer), Error::<T>::OrderInvalid);
+            ensure!(
+                order.is_owned_by(&consumer) && !order.is_pipeline_stage(),
+                Error::<T>::OrderInvalid
+            );
             let bid = OrderBids::<T>::get(order_id, &provider).ok_or(Error::<T>::BidNotFound)?;
+            Self::ensure_serves(&provider, &order.model_id)?;
+            Self::ensure_can_spend(&consumer, &order.asset, order.prepayment(bid.current_price()))?;
+
+            ensure!(order.has_model_version(), Error::<T>::VersionNotFound);
+            let model_version = order.model_version;
+            match (order.subscription, order.max_units_per_request) {
+                (Some(subscription), _) => T::AgreementManagement::create_subscription_agreement(
+                    consumer,
+                    provider.clone(),
+                    order_id,
+                    order.model_id,
+                    bid.current_price(),
+                    subscription.period,
+                    subscription.periods_total,
+                    subscription.requests_per_period,
+                    order.asset,
+                    order.requirements,
+                    model_version,
+                )?,
+                (None, Some(max_units_per_request)) => {
+                    T::AgreementManagement::create_metered_agreement(
+                        consumer,
+                        provider.clone(),
+                        order_id,
+                        order.model_id,
+                        bid.current_price(),
+                        order.requests_total,
+                        max_units_per_request,
+                        order.asset,
+                        order.requirements,
+                        model_version,
+                    )?
+                },
+                (None, None) => T::AgreementManagement::create_agreement(
+                    consumer,
+                    provider.clone(),
+                    order_id,
+                    order.model_id,
+                    bid.current_price(),
+                    order.requests_total,
+                    order.asset,
+                    order.requirements,
+                    model_version,
+                )?,
+            }
+            Order::<T>::remove(order_id);
 
-            T::AgreementManagement::create_agreement(
+            Self::deposit_event(Event::BidAccepted { order_id, provider });
+            Ok(())
+        }
+
+        /// Executed by a consumer to accept the bids of several providers on an order. Every
+        /// request is executed by all of them, and a response is accepted once `quorum` of them
+        /// agree on it. All the providers are paid the highest of the accepted prices.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::bids_accept(providers.len() as u32))]
+        pub fn bids_accept(
+            origin: OriginFor<T>,
+            order_id: T::OrderId,
+            providers: BoundedVec<T::AccountId, T::MaxAgreementProviders>,
+            #[pallet::compact] quorum: RequestsUsize,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let order = Orders::<T>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
+            ensure!(
+                order.is_owned_by(&consumer)
+                    && !order.is_pipeline_stage()
+                    && order.subscription.is_none()
+                    && order.max_units_per_request.is_none(),
+                Error::<T>::OrderInvalid
+            );
+            let mut price_per_request = BalanceOf::<T>::zero();
+            for provider in providers.iter() {
+                let bid = OrderBids::<T>::get(order_id, provider).ok_or(Error::<T>::BidNotFound)?;
+                Self::ensure_serves(provider, &order.model_id)?;
+                price_per_request = price_per_request.max(bid.current_price());
+            }
+            // Every provider is prepaid for every request.
+            let prepayment = order
+                .prepayment(price_per_request)
+                .saturating_mul((providers.len() as u32).into());
+            Self::ensure_can_spend(&consumer, &order.asset, prepayment)?;
+
+            ensure!(order.has_model_version(), Error::<T>::VersionNotFound);
+            let model_version = order.model_version;
+            T::AgreementManagement::create_redundant_agreement(
                 consumer,
+                &providers,
+                order_id,
+                order.model_id,
+                price_per_request,
+                order.requests_total,
+                quorum,
+                order.asset,
+                order.requirements,
+                model_version,
+            )?;
+            Order::<T>::remove(order_id);
+
+            Self::deposit_event(Event::BidsAccepted { order_id, providers, quorum });
+            Ok(())
+        }
+
+        /// Creates a new subscription order on the market, for `periods_total` periods of `period`
+        /// blocks, optionally limited to `requests_per_period`, paid in `asset` and restricted to
+        /// providers with at least `min_reputation` and in `allowlist`. The `requirements` are
+        /// copied into the agreement.
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::subscription_order_create())]
+        #[allow(clippy::too_many_arguments)]
+        pub fn subscription_order_create(
+            origin: OriginFor<T>,
+            model_id: T::ModelId,
+            period: BlockNumberFor<T>,
+            #[pallet::compact] periods_total: u32,
+            requests_per_period: Option<RequestsUsize>,
+            asset: Option<T::AssetId>,
+            min_reputation: Option<Perbill>,
+            allowlist: Option<OrderAllowlist<T>>,
+            requirements: Option<OrderRequirements<T>>,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let subscription = SubscriptionTerms::new(period, periods_total, requests_per_period);
+            let order_details = OrderDetails::new(consumer, model_id.clone(), 0)
+                .with_subscription(subscription)
+                .with_asset(asset)
+                .with_min_reputation(min_reputation)
+                .with_allowlist(allowlist)
+                .with_requirements(requirements);
+            ensure!(order_details.is_valid(), Error::<T>::OrderInvalid);
+            Self::ensure_asset_valid(&order_details.asset)?;
+
+            let order_id = Order::<T>::insert(order_details);
+
+            Self::deposit_event(Event::OrderCreated { order_id, model_id });
+            Ok(())
+        }
+
+        /// Executed by a consumer to allow another provider to bid on its private order.
+        #[pallet::call_index(5)]
+        #[pallet::weight(T::WeightInfo::order_invite())]
+        pub fn order_invite(
+            origin: OriginFor<T>,
+            order_id: T::OrderId,
+            provider: T::AccountId,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            Orders::<T>::try_mutate(order_id, |order| -> DispatchResult {
+                let order = order.as_mut().ok_or(Error::<T>::OrderNotFound)?;
+                ensure!(order.is_owned_by(&consumer), Error::<T>::OrderInvalid);
+                order.invite(provider.clone())
+            })?;
+
+            Self::deposit_event(Event::ProviderInvited { order_id, provider });
+            Ok(())
+        }
+
+        /// Creates a new auction order on the market, which price per request rises from
+        /// `start_price` by `price_step` every block up to `max_price`, until a provider matches
+        /// it. Prices are in `asset`, or in the native currency if none.
+        #[pallet::call_index(6)]
+        #[pallet::weight(T::WeightInfo::auction_order_create())]
+        pub fn auction_order_create(
+            origin: OriginFor<T>,
+            model_id: T::ModelId,
+            #[pallet::compact] requests_total: RequestsUsize,
+            start_price: BalanceOf<T>,
+            price_step: BalanceOf<T>,
+            max_price: BalanceOf<T>,
+            asset: Option<T::AssetId>,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let auction = PriceCurve::new(start_price, price_step, max_price);
+            let order_details = OrderDetails::new(consumer, model_id.clone(), requests_total)
+                .with_auction(auction)
+                .with_asset(asset);
+            ensure!(order_details.is_valid(), Error::<T>::OrderInvalid);
+            Self::ensure_asset_valid(&order_details.asset)?;
+
+            let order_id = Order::<T>::insert(order_details);
+
+            Self::deposit_event(Event::OrderCreated { order_id, model_id });
+            Ok(())
+        }
+
+        /// Executed by a registered provider of the ordered model to match an auction order at
+        /// its current price, creating the agreement right away. The provider has to acknowledge
+        /// the `requirements` of the order.
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::order_match())]
+        pub fn order_match(
+            origin: OriginFor<T>,
+            order_id: T::OrderId,
+            requirements: Option<OrderRequirements<T>>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let order = Orders::<T>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
+            let auction = order.auction.as_ref().ok_or(Error::<T>::OrderInvalid)?;
+            Self::ensure_can_bid(&order, &provider, &requirements)?;
+
+            let price_per_request = auction.current_price();
+            Self::ensure_can_spend(
+                &order.consumer,
+                &order.asset,
+                order.prepayment(price_per_request),
+            )?;
+            ensure!(order.has_model_version(), Error::<T>::VersionNotFound);
+            let model_version = order.model_version;
+            T::AgreementManagement::create_agreement(
+                order.consumer,
                 provider.clone(),
                 order_id,
                 order.model_id,
-                bid.price_per_request,
+                price_per_request,
                 order.requests_total,
+                order.asset,
+                order.requirements,
+                model_version,
             )?;
             Order::<T>::remove(order_id);
 
-            Self::deposit_event(Event::BidAccepted { order_id, provider });
+            Self::deposit_event(Event::OrderMatched { order_id, provider, price_per_request });
+            Ok(())
+        }
+
+        /// Executed by a registered provider of the ordered model to create a bid which price
+        /// falls from `start_price` by `price_step` every block down to `min_price`, until the
+        /// consumer accepts it.
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::bid_create())]
+        pub fn auction_bid_create(
+            origin: OriginFor<T>,
+            order_id: T::OrderId,
+            start_price: BalanceOf<T>,
+            price_step: BalanceOf<T>,
+            min_price: BalanceOf<T>,
+            requirements: Option<OrderRequirements<T>>,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let price_curve = PriceCurve::new(start_price, price_step, min_price);
+            ensure!(price_curve.is_falling(), Error::<T>::BidInvalid);
+            let bid_details =
+                BidDetails::new(provider.clone(), start_price).with_price_curve(price_curve);
+            Self::create_bid(order_id, provider, bid_details, requirements)
+        }
+
+        /// Settles an order and a bid signed off-chain by a consumer and a provider, creating the
+        /// agreement at the price of the bid. Can be executed by anyone, such as the relayer
+        /// that matched them. Both are signed along with a domain tag and the genesis hash, and
+        /// the bid is bound to the hash of the order it was made on.
+        #[pallet::call_index(9)]
+        #[pallet::weight(T::WeightInfo::settle_match())]
+        pub fn settle_match(
+            origin: OriginFor<T>,
+            order: Box<OffchainOrder<T>>,
+            order_signature: T::OffchainSignature,
+            bid: Box<OffchainBid<T>>,
+            bid_signature: T::OffchainSignature,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            ensure!(
+                order_signature.verify(&order.signing_payload()[..], &order.consumer),
+                Error::<T>::SignatureInvalid
+            );
+            ensure!(
+                bid_signature.verify(&bid.signing_payload()[..], &bid.provider),
+                Error::<T>::SignatureInvalid
+            );
+            ensure!(order.is_valid(), Error::<T>::OrderInvalid);
+            ensure!(!order.is_expired() && !bid.is_expired(), Error::<T>::OfferExpired);
+            ensure!(order.matches(&bid), Error::<T>::BidInvalid);
+            Self::ensure_serves(&bid.provider, &bid.model_id)?;
+            ensure!(order.has_model_version(), Error::<T>::VersionNotFound);
+            Self::ensure_asset_valid(&order.asset)?;
+            Self::ensure_can_spend(
+                &order.consumer,
+                &order.asset,
+                bid.price_per_request.saturating_mul(order.requests_total.into()),
+            )?;
+            Self::use_nonce(&order.consumer, order.nonce)?;
+            Self::use_nonce(&bid.provider, bid.nonce)?;
+
+            let order_id = CurrentOrderId::<T>::next();
+            let model_version = order.resolve_model_version();
+            let OffchainOrder { consumer, model_id, requests_total, asset, requirements, .. } =
+                *order;
+            let OffchainBid { provider, price_per_request, .. } = *bid;
+            T::AgreementManagement::create_agreement(
+                consumer.clone(),
+                provider.clone(),
+                order_id,
+                model_id,
+                price_per_request,
+                requests_total,
+                asset,
+                requirements,
+                model_version,
+            )?;
+
+            Self::deposit_event(Event::MatchSettled {
+                order_id,
+                consumer,
+                provider,
+                price_per_request,
+            });
+            Ok(())
+        }
+
+        /// Creates a pipeline order, with an order for every model of `stages`, in order. The
+        /// response to a request of a stage becomes a request of the next stage. Providers bid on
+        /// the order of every stage separately.
+        #[pallet::call_index(10)]
+        #[pallet::weight(T::WeightInfo::pipeline_order_create(stages.len() as u32))]
+        pub fn pipeline_order_create(
+            origin: OriginFor<T>,
+            stages: BoundedVec<T::ModelId, T::MaxPipelineStages>,
+            #[pallet::compact] requests_total: RequestsUsize,
+            asset: Option<T::AssetId>,
+            requirements: Option<OrderRequirements<T>>,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            ensure!(stages.len() > 1, Error::<T>::PipelineInvalid);
+            let orders = stages
+                .iter()
+                .map(|model_id| {
+                    OrderDetails::new(consumer.clone(), model_id.clone(), requests_total)
+                        .with_asset(asset.clone())
+                        .with_requirements(requirements.clone())
+                })
+                .collect::<Vec<_>>();
+            ensure!(orders.iter().all(|order| order.is_valid()), Error::<T>::OrderInvalid);
+            Self::ensure_asset_valid(&asset)?;
+
+            let order_ids = Order::<T>::insert_pipeline(orders);
+
+            for (order_id, model_id) in order_ids.into_iter().zip(stages) {
+                Self::deposit_event(Event::OrderCreated { order_id, model_id });
+            }
+            Ok(())
+        }
+
+        /// Executed by a consumer to accept a bid for every stage of a pipeline order, from the
+        /// `providers` in the order of the stages, starting at `order_id`.
+        #[pallet::call_index(11)]
+        #[pallet::weight(T::WeightInfo::pipeline_bids_accept(providers.len() as u32))]
+        pub fn pipeline_bids_accept(
+            origin: OriginFor<T>,
+            order_id: T::OrderId,
+            providers: BoundedVec<T::AccountId, T::MaxPipelineStages>,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let order = Orders::<T>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
+            ensure!(order.is_owned_by(&consumer), Error::<T>::OrderInvalid);
+            ensure!(order.is_first_stage(order_id), Error::<T>::PipelineInvalid);
+
+            let mut stages = Vec::with_capacity(providers.len());
+            let mut prepayment = BalanceOf::<T>::zero();
+            let mut stage_order = Some(order_id);
+            for provider in providers.iter() {
+                let stage_order_id = stage_order.ok_or(Error::<T>::PipelineInvalid)?;
+                let stage = Orders::<T>::get(stage_order_id).ok_or(Error::<T>::OrderNotFound)?;
+                let bid =
+                    OrderBids::<T>::get(stage_order_id, provider).ok_or(Error::<T>::BidNotFound)?;
+                Self::ensure_serves(provider, &stage.model_id)?;
+                ensure!(stage.has_model_version(), Error::<T>::VersionNotFound);
+                prepayment = prepayment.saturating_add(stage.prepayment(bid.current_price()));
+
+                stage_order = stage.pipeline.and_then(|pipeline| pipeline.next_stage);
+                stages.push(PipelineStage {
+                    provider: provider.clone(),
+                    order_id: stage_order_id,
+                    model_version: stage.model_version,
+                    model_id: stage.model_id,
+                    price_per_request: bid.current_price(),
+                });
+            }
+            ensure!(stage_order.is_none(), Error::<T>::PipelineInvalid);
+            Self::ensure_can_spend(&consumer, &order.asset, prepayment)?;
+
+            T::AgreementManagement::create_pipeline_agreements(
+                consumer,
+                &stages,
+                order.requests_total,
+                order.asset,
+                order.requirements,
+            )?;
+            for stage in stages {
+                Order::<T>::remove(stage.order_id);
+            }
+
+            Self::deposit_event(Event::PipelineBidsAccepted { order_id, providers });
             Ok(())
         }
+
+        /// Executed by a consumer to pin its order to a `version` of the ordered model, or to its
+        /// current latest version if none. The order can't be pinned once it has bids, which were
+        /// made for the version it had, unless that version has been retired since. The bids are
+        /// dropped then, as they can't be accepted anymore.
+        #[pallet::call_index(12)]
+        #[pallet::weight(T::WeightInfo::order_version_pin())]
+        pub fn order_version_pin(
+            origin: OriginFor<T>,
+            order_id: T::OrderId,
+            version: Option<ModelVersion>,
+        ) -> DispatchResult {
+            let consumer = ensure_signed(origin)?;
+
+            let version = Orders::<T>::try_mutate(order_id, |order| -> Result<_, DispatchError> {
+                let order = order.as_mut().ok_or(Error::<T>::OrderNotFound)?;
+                ensure!(order.is_owned_by(&consumer), Error::<T>::OrderInvalid);
+                if OrderBids::<T>::contains_prefix(order_id) {
+                    ensure!(!order.has_model_version(), Error::<T>::OrderInvalid);
+                    Bid::<T>::remove_all(order_id);
+                }
+                order.model_version =
+                    version.or_else(|| T::ModelRegistry::latest_version(&order.model_id));
+                ensure!(order.has_model_version(), Error::<T>::VersionNotFound);
+                Ok(order.model_version)
+            })?;
+
+            Self::deposit_event(Event::OrderVersionPinned { order_id, version });
+            Ok(())
+        }
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Checks that a provider can bid on an order, acknowledging its `requirements`.
+    fn ensure_can_bid(
+        order: &OrderDetails<T>,
+        provider: &Provider<T>,
+        requirements: &Option<OrderRequirements<T>>,
+    ) -> DispatchResult {
+        ensure!(order.allows(provider), Error::<T>::ProviderNotAllowed);
+        ensure!(order.requirements == *requirements, Error::<T>::RequirementsNotAcknowledged);
+        Self::ensure_serves(provider, &order.model_id)?;
+        ensure!(
+            order
+                .min_reputation
+                .is_none_or(|min| T::ProviderRegistry::reputation(provider) >= min),
+            Error::<T>::ProviderReputationTooLow
+        );
+        Ok(())
+    }
+
+    /// Checks that a provider is still registered for a model, as it may have deregistered since
+    /// it bid.
+    fn ensure_serves(provider: &Provider<T>, model_id: &T::ModelId) -> DispatchResult {
+        ensure!(T::ProviderRegistry::serves(provider, model_id), Error::<T>::ProviderNotRegistered);
+        Ok(())
+    }
+
+    /// Checks that the prepayment of an agreement is within the spending limits of the consumer,
+    /// before any agreement is created.
+    fn ensure_can_spend(
+        consumer: &T::AccountId,
+        asset: &Option<T::AssetId>,
+        prepayment: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure!(
+            T::AgreementManagement::can_spend(consumer, asset, prepayment),
+            Error::<T>::SpendingLimitExceeded
+        );
+        Ok(())
+    }
+
+    fn ensure_asset_valid(asset: &Option<T::AssetId>) -> DispatchResult {
+        ensure!(
+            asset.as_ref().is_none_or(T::AssetRegistry::is_sufficient),
+            Error::<T>::AssetInvalid
+        );
+        Ok(())
+    }
+
+    fn use_nonce(signer: &T::AccountId, nonce: OffchainNonce) -> DispatchResult {
+        ensure!(!OffchainNonces::<T>::contains_key(signer, nonce), Error::<T>::NonceAlreadyUsed);
+        OffchainNonces::<T>::insert(signer, nonce, ());
+        Ok(())
+    }
+
+    fn create_bid(
+        order_id: T::OrderId,
+        provider: Provider<T>,
+        bid_details: BidDetails<T>,
+        requirements: Option<OrderRequirements<T>>,
+    ) -> DispatchResult {
+        let order = Orders::<T>::get(order_id).ok_or(Error::<T>::OrderNotFound)?;
+        Self::ensure_can_bid(&order, &provider, &requirements)?;
+        ensure!(!Bid::<T>::exists(order_id, &provider), Error::<T>::BidAlreadyExists);
+
+        let price_per_request = bid_details.price_per_request;
+        Bid::<T>::insert(order_id, &provider, bid_details);
+
+        Self::deposit_event(Event::BidCreated { order_id, provider, price_per_request });
+        Ok(())
     }
 }
@@ -0,0 +1,53 @@
+use frame_support::{migrations::VersionedMigration, traits::UncheckedOnRuntimeUpgrade};
+
+use crate::*;
+
+/// Orders and bids as they were stored before the storage version was set.
+pub mod v0 {
+    use super::*;
+
+    #[derive(Encode, Decode)]
+    pub struct OldOrderDetails<T: Config> {
+        pub consumer: Consumer<T>,
+        pub model_id: T::ModelId,
+        #[codec(compact)]
+        pub requests_total: RequestsUsize,
+    }
+
+    #[derive(Encode, Decode)]
+    pub struct OldBidDetails<T: Config> {
+        pub provider: Provider<T>,
+        pub price_per_request: BalanceOf<T>,
+    }
+}
+
+/// Translates the orders and bids to their current layout, none of the terms added since being
+/// set.
+pub struct InnerMigrateV0ToV1<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV0ToV1<T> {
+    fn on_runtime_upgrade() -> Weight {
+        let mut translated = 0u64;
+
+        Orders::<T>::translate::<v0::OldOrderDetails<T>, _>(|_, old| {
+            translated.saturating_inc();
+            Some(OrderDetails::new(old.consumer, old.model_id, old.requests_total))
+        });
+
+        OrderBids::<T>::translate::<v0::OldBidDetails<T>, _>(|_, _, old| {
+            translated.saturating_inc();
+            Some(BidDetails::new(old.provider, old.price_per_request))
+        });
+
+        T::DbWeight::get().reads_writes(translated, translated)
+    }
+}
+
+/// Migrates the pallet storage from version 0 to 1.
+pub type MigrateV0ToV1<T> = VersionedMigration<
+    0,
+    1,
+    InnerMigrateV0ToV1<T>,
+    Pallet<T>,
+    <T as frame_system::Config>::DbWeight,
+>;
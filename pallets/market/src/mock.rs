@@ -1,17 +1,25 @@
 use frame_support::{
     derive_impl,
     dispatch::DispatchResult,
+    parameter_types,
     traits::{ConstU16, ConstU32, ConstU64},
     BoundedVec,
 };
 use sp_core::H256;
 use sp_runtime::{
+    testing::{TestSignature, UintAuthorityId},
     traits::{BlakeTwo256, IdentityLookup},
-    BuildStorage,
+    BuildStorage, Perbill,
 };
 
-use airo_primitives::agreement::AgreementManagement;
-use airo_primitives::RequestsUsize;
+use airo_primitives::{
+    agreement::{AgreementManagement, PipelineStage},
+    model::{ModelRegistry, ModelVersion},
+    payment::AssetRegistry,
+    provider::ProviderRegistry,
+    requirements::ExecutionRequirements,
+};
+use airo_primitives::{RequestsUsize, UnitsUsize};
 
 use crate as pallet_market;
 
@@ -20,6 +28,7 @@ pub type AccountId = u64;
 pub type Balance = u64;
 type ModelId = BoundedVec<u8, ConstU32<128>>;
 pub type OrderId = u32;
+pub type AssetId = u32;
 
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
@@ -85,6 +94,96 @@ impl crate::benchmarking::ModelFactory<ModelId> for AiroMarketBenchmarkHelper {
     }
 }
 
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::ModelVersionFactory<ModelId> for AiroMarketBenchmarkHelper {
+    fn create_version(_model_id: &ModelId) -> ModelVersion {
+        1
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::ProviderFactory<AccountId, ModelId> for AiroMarketBenchmarkHelper {
+    fn register_provider(_provider: &AccountId, _model_id: ModelId) {}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::SignerFactory<UintAuthorityId, AccountId, TestSignature>
+    for AiroMarketBenchmarkHelper
+{
+    fn signer(index: u32) -> (UintAuthorityId, AccountId) {
+        (UintAuthorityId(index.into()), index.into())
+    }
+
+    fn sign(signer: &UintAuthorityId, message: &[u8]) -> TestSignature {
+        TestSignature(signer.0, message.to_vec())
+    }
+}
+
+parameter_types! {
+    /// Provider deregistered after bidding.
+    pub static DeregisteredProvider: Option<AccountId> = None;
+}
+
+/// Every provider but `PROVIDER_UNREGISTERED` and the deregistered one serves every model.
+pub struct MockProviderRegistry;
+
+impl ProviderRegistry for MockProviderRegistry {
+    type AccountId = AccountId;
+    type ModelId = ModelId;
+
+    fn serves(provider: &AccountId, _model_id: &ModelId) -> bool {
+        *provider != PROVIDER_UNREGISTERED && DeregisteredProvider::get() != Some(*provider)
+    }
+
+    fn reputation(provider: &AccountId) -> Perbill {
+        if *provider == PROVIDER_1 {
+            Perbill::from_percent(90)
+        } else {
+            Perbill::from_percent(50)
+        }
+    }
+
+    fn capacity(_provider: &AccountId) -> Option<RequestsUsize> {
+        None
+    }
+}
+
+parameter_types! {
+    pub static LatestVersion: Option<ModelVersion> = Some(LATEST_VERSION);
+    /// Oldest version which has not been retired.
+    pub static OldestVersion: ModelVersion = 1;
+}
+
+pub struct MockAssetRegistry;
+
+impl AssetRegistry<AssetId> for MockAssetRegistry {
+    fn is_sufficient(asset: &AssetId) -> bool {
+        *asset == ASSET
+    }
+}
+
+/// Every model has the versions from `OldestVersion` up to `LatestVersion`.
+pub struct MockModelRegistry;
+
+impl ModelRegistry for MockModelRegistry {
+    type ModelId = ModelId;
+
+    fn latest_version(_model_id: &ModelId) -> Option<ModelVersion> {
+        LatestVersion::get()
+    }
+
+    fn has_version(_model_id: &ModelId, version: ModelVersion) -> bool {
+        LatestVersion::get()
+            .is_some_and(|latest| (OldestVersion::get()..=latest).contains(&version))
+    }
+}
+
+parameter_types! {
+    pub static SpendingLimit: Option<Balance> = None;
+    /// Version of the model the last agreement has been created for.
+    pub static AgreementVersion: Option<ModelVersion> = None;
+}
+
 pub struct MockAgreementManagement;
 
 impl AgreementManagement for MockAgreementManagement {
@@ -92,7 +191,10 @@ impl AgreementManagement for MockAgreementManagement {
     type OrderId = OrderId;
     type ModelId = ModelId;
     type Balance = Balance;
+    type BlockNumber = u64;
+    type AssetId = AssetId;
 
+    #[allow(clippy::too_many_arguments)]
     fn create_agreement(
         _consumer: Self::AccountId,
         _provider: Self::AccountId,
@@ -100,9 +202,82 @@ impl AgreementManagement for MockAgreementManagement {
         _model_id: Self::ModelId,
         _price_per_request: Self::Balance,
         _requests_total: RequestsUsize,
+        _asset: Option<Self::AssetId>,
+        _requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult {
+        AgreementVersion::set(model_version);
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_redundant_agreement(
+        _consumer: Self::AccountId,
+        _providers: &[Self::AccountId],
+        _order_id: Self::OrderId,
+        _model_id: Self::ModelId,
+        _price_per_request: Self::Balance,
+        _requests_total: RequestsUsize,
+        _quorum: RequestsUsize,
+        _asset: Option<Self::AssetId>,
+        _requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult {
+        AgreementVersion::set(model_version);
+        Ok(())
+    }
+
+    fn create_subscription_agreement(
+        _consumer: Self::AccountId,
+        _provider: Self::AccountId,
+        _order_id: Self::OrderId,
+        _model_id: Self::ModelId,
+        _price_per_period: Self::Balance,
+        _period: Self::BlockNumber,
+        _periods_total: u32,
+        _requests_per_period: Option<RequestsUsize>,
+        _asset: Option<Self::AssetId>,
+        _requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult {
+        AgreementVersion::set(model_version);
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_metered_agreement(
+        _consumer: Self::AccountId,
+        _provider: Self::AccountId,
+        _order_id: Self::OrderId,
+        _model_id: Self::ModelId,
+        _price_per_unit: Self::Balance,
+        _requests_total: RequestsUsize,
+        _max_units_per_request: UnitsUsize,
+        _asset: Option<Self::AssetId>,
+        _requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
     ) -> DispatchResult {
+        AgreementVersion::set(model_version);
         Ok(())
     }
+
+    fn create_pipeline_agreements(
+        _consumer: Self::AccountId,
+        _stages: &[PipelineStage<Self::AccountId, Self::OrderId, Self::ModelId, Self::Balance>],
+        _requests_total: RequestsUsize,
+        _asset: Option<Self::AssetId>,
+        _requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+    ) -> DispatchResult {
+        Ok(())
+    }
+
+    fn can_spend(
+        _consumer: &Self::AccountId,
+        _asset: &Option<Self::AssetId>,
+        amount: Self::Balance,
+    ) -> bool {
+        SpendingLimit::get().is_none_or(|limit| amount <= limit)
+    }
 }
 
 impl pallet_market::Config for Test {
@@ -112,15 +287,31 @@ impl pallet_market::Config for Test {
     type Currency = Balances;
     type ModelId = ModelId;
     type OrderId = OrderId;
+    type AssetId = AssetId;
+    type MaxAgreementProviders = ConstU32<3>;
+    type MaxAllowedProviders = ConstU32<MAX_ALLOWED_PROVIDERS>;
+    type MaxPipelineStages = ConstU32<MAX_PIPELINE_STAGES>;
     type AgreementManagement = MockAgreementManagement;
+    type ProviderRegistry = MockProviderRegistry;
+    type ModelRegistry = MockModelRegistry;
+    type AssetRegistry = MockAssetRegistry;
+    type OffchainSignature = TestSignature;
+    type OffchainPublic = UintAuthorityId;
     #[cfg(feature = "runtime-benchmarks")]
     type BenchmarkHelper = AiroMarketBenchmarkHelper;
 }
 
+pub const MAX_ALLOWED_PROVIDERS: u32 = 2;
+pub const MAX_PIPELINE_STAGES: u32 = 3;
 pub const CONSUMER_1: AccountId = 1;
 pub const CONSUMER_2: AccountId = 2;
 pub const PROVIDER_1: AccountId = 11;
 pub const PROVIDER_2: AccountId = 12;
+pub const PROVIDER_3: AccountId = 13;
+pub const PROVIDER_UNREGISTERED: AccountId = 19;
+pub const ASSET: AssetId = 1;
+pub const ASSET_NOT_SUFFICIENT: AssetId = 2;
+pub const LATEST_VERSION: ModelVersion = 2;
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
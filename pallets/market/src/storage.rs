@@ -26,10 +26,6 @@ impl<T: Config> IncrementalStorageValue for CurrentOrderId<T> {
 pub struct Order<T>(PhantomData<T>);
 
 impl<T: Config> Order<T> {
-    pub fn exists(order_id: T::OrderId) -> bool {
-        Orders::<T>::contains_key(order_id)
-    }
-
     pub fn insert(order: OrderDetails<T>) -> T::OrderId {
         let order_id = CurrentOrderId::<T>::next();
         ConsumerOrders::<T>::insert(&order.consumer, order_id, ());
@@ -37,13 +33,21 @@ impl<T: Config> Order<T> {
         order_id
     }
 
+    /// Inserts the orders of the stages of a pipeline, linking every stage to the next one.
+    pub fn insert_pipeline(stages: Vec<OrderDetails<T>>) -> Vec<T::OrderId> {
+        let order_ids: Vec<_> = stages.iter().map(|_| CurrentOrderId::<T>::next()).collect();
+        for (index, (order_id, order)) in order_ids.iter().zip(stages).enumerate() {
+            let order = order.with_pipeline(order_ids[0], order_ids.get(index + 1).copied());
+            ConsumerOrders::<T>::insert(&order.consumer, order_id, ());
+            Orders::<T>::insert(order_id, order);
+        }
+        order_ids
+    }
+
     pub fn remove(order_id: T::OrderId) {
         if let Some(OrderDetails { consumer, .. }) = Orders::<T>::take(order_id) {
             ConsumerOrders::<T>::remove(&consumer, order_id);
-
-            OrderBids::<T>::drain_prefix(order_id).for_each(|(provider, _)| {
-                ProviderOrders::<T>::remove(provider, order_id);
-            });
+            Bid::<T>::remove_all(order_id);
         }
     }
 }
@@ -59,4 +63,10 @@ impl<T: Config> Bid<T> {
         ProviderOrders::<T>::insert(provider, order_id, ());
         OrderBids::<T>::insert(order_id, provider, bid);
     }
+
+    pub fn remove_all(order_id: T::OrderId) {
+        OrderBids::<T>::drain_prefix(order_id).for_each(|(provider, _)| {
+            ProviderOrders::<T>::remove(provider, order_id);
+        });
+    }
 }
@@ -1,6 +1,11 @@
 use frame_support::*;
 
-use airo_primitives::RequestsUsize;
+use airo_primitives::{
+    requirements::{DataRetention, ExecutionRequirements, HardwareClass},
+    RequestsUsize,
+};
+
+use sp_runtime::testing::TestSignature;
 
 use crate::{mock::*, *};
 
@@ -9,15 +14,67 @@ fn create_order(consumer: AccountId, model_id: &str, requests_total: RequestsUsi
         RuntimeOrigin::signed(consumer),
         BoundedVec::try_from(model_id.as_bytes().to_vec()).unwrap(),
         requests_total,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
     ));
     CurrentOrderId::<Test>::get()
 }
 
+fn offchain_order(consumer: AccountId, max_price_per_request: Balance) -> OffchainOrder<Test> {
+    OffchainOrder {
+        consumer,
+        model_id: BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+        requests_total: 10,
+        max_price_per_request,
+        asset: None,
+        requirements: None,
+        model_version: None,
+        nonce: 0,
+        expires_at: 10,
+    }
+}
+
+fn offchain_bid(
+    provider: AccountId,
+    order: &OffchainOrder<Test>,
+    price_per_request: Balance,
+) -> OffchainBid<Test> {
+    OffchainBid {
+        provider,
+        order_hash: order.hash(),
+        model_id: order.model_id.clone(),
+        model_version: order.resolve_model_version(),
+        requests_total: order.requests_total,
+        price_per_request,
+        asset: None,
+        requirements: None,
+        nonce: 0,
+        expires_at: 10,
+    }
+}
+
+fn settle_match(order: OffchainOrder<Test>, bid: OffchainBid<Test>) -> DispatchResult {
+    let order_signature = TestSignature(order.consumer, order.signing_payload());
+    let bid_signature = TestSignature(bid.provider, bid.signing_payload());
+    AiroMarket::settle_match(
+        RuntimeOrigin::signed(CONSUMER_2),
+        Box::new(order),
+        order_signature,
+        Box::new(bid),
+        bid_signature,
+    )
+}
+
 fn create_bid(provider: AccountId, order_id: OrderId, price_per_request: Balance) {
     assert_ok!(AiroMarket::bid_create(
         RuntimeOrigin::signed(provider),
         order_id,
-        price_per_request
+        price_per_request,
+        None
     ));
 }
 
@@ -47,7 +104,13 @@ fn fail_order_zero() {
             AiroMarket::order_create(
                 RuntimeOrigin::signed(CONSUMER_1),
                 BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
-                0
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None
             ),
             Error::<Test>::OrderInvalid
         );
@@ -77,12 +140,90 @@ fn fail_bid_missing_order() {
         let order_id = create_order(CONSUMER_1, "model_id", 1);
 
         assert_noop!(
-            AiroMarket::bid_create(RuntimeOrigin::signed(PROVIDER_1), order_id + 1, 1000),
+            AiroMarket::bid_create(RuntimeOrigin::signed(PROVIDER_1), order_id + 1, 1000, None),
             Error::<Test>::OrderNotFound
         );
     });
 }
 
+#[test]
+fn fail_bid_unregistered_provider() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+
+        assert_noop!(
+            AiroMarket::bid_create(
+                RuntimeOrigin::signed(PROVIDER_UNREGISTERED),
+                order_id,
+                1000,
+                None
+            ),
+            Error::<Test>::ProviderNotRegistered
+        );
+    });
+}
+
+#[test]
+fn fail_accept_bid_deregistered_provider() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+        create_bid(PROVIDER_1, order_id, 1000);
+        create_bid(PROVIDER_2, order_id, 1000);
+        let pipeline_id = create_pipeline_order(CONSUMER_1, &["speech_to_text", "summarization"]);
+        create_bid(PROVIDER_2, pipeline_id, 1000);
+        create_bid(PROVIDER_1, pipeline_id + 1, 500);
+
+        DeregisteredProvider::set(Some(PROVIDER_1));
+
+        assert_noop!(
+            AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1),
+            Error::<Test>::ProviderNotRegistered
+        );
+        assert_noop!(
+            AiroMarket::bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                order_id,
+                BoundedVec::try_from(vec![PROVIDER_2, PROVIDER_1]).unwrap(),
+                2
+            ),
+            Error::<Test>::ProviderNotRegistered
+        );
+        assert_noop!(
+            AiroMarket::pipeline_bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                pipeline_id,
+                BoundedVec::try_from(vec![PROVIDER_2, PROVIDER_1]).unwrap()
+            ),
+            Error::<Test>::ProviderNotRegistered
+        );
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_2));
+    });
+}
+
+#[test]
+fn fail_bid_below_min_reputation() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(AiroMarket::order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+            1,
+            None,
+            None,
+            Some(Perbill::from_percent(80)),
+            None,
+            None,
+            None,
+        ));
+        let order_id = CurrentOrderId::<Test>::get();
+
+        assert_noop!(
+            AiroMarket::bid_create(RuntimeOrigin::signed(PROVIDER_2), order_id, 1000, None),
+            Error::<Test>::ProviderReputationTooLow
+        );
+        create_bid(PROVIDER_1, order_id, 1000);
+    });
+}
+
 #[test]
 fn fail_bid_same_order() {
     new_test_ext().execute_with(|| {
@@ -90,7 +231,7 @@ fn fail_bid_same_order() {
         create_bid(PROVIDER_1, order_id, 1000);
 
         assert_noop!(
-            AiroMarket::bid_create(RuntimeOrigin::signed(PROVIDER_1), order_id, 200),
+            AiroMarket::bid_create(RuntimeOrigin::signed(PROVIDER_1), order_id, 200, None),
             Error::<Test>::BidAlreadyExists
         );
     });
@@ -116,6 +257,71 @@ fn can_accept_bid() {
     });
 }
 
+#[test]
+fn fail_accept_bid_above_spending_limit() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 5);
+        create_bid(PROVIDER_1, order_id, 2000);
+        create_bid(PROVIDER_2, order_id, 1000);
+        SpendingLimit::set(Some(5_000));
+
+        assert_noop!(
+            AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1),
+            Error::<Test>::SpendingLimitExceeded
+        );
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_2));
+    });
+}
+
+#[test]
+fn fail_match_above_spending_limit() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 5);
+        create_bid(PROVIDER_1, order_id, 600);
+        create_bid(PROVIDER_2, order_id, 400);
+        let pipeline_id = create_pipeline_order(CONSUMER_1, &["speech_to_text", "summarization"]);
+        create_bid(PROVIDER_1, pipeline_id, 300);
+        create_bid(PROVIDER_2, pipeline_id + 1, 300);
+        assert_ok!(AiroMarket::auction_order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+            10,
+            600,
+            10,
+            1000,
+            None
+        ));
+        let auction_id = CurrentOrderId::<Test>::get();
+        // Within the limit for a single provider or stage only.
+        SpendingLimit::set(Some(5_000));
+
+        assert_noop!(
+            AiroMarket::bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                order_id,
+                BoundedVec::try_from(vec![PROVIDER_1, PROVIDER_2]).unwrap(),
+                2
+            ),
+            Error::<Test>::SpendingLimitExceeded
+        );
+        assert_noop!(
+            AiroMarket::pipeline_bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                pipeline_id,
+                BoundedVec::try_from(vec![PROVIDER_1, PROVIDER_2]).unwrap()
+            ),
+            Error::<Test>::SpendingLimitExceeded
+        );
+        assert_noop!(
+            AiroMarket::order_match(RuntimeOrigin::signed(PROVIDER_1), auction_id, None),
+            Error::<Test>::SpendingLimitExceeded
+        );
+        let order = offchain_order(CONSUMER_1, 600);
+        let bid = offchain_bid(PROVIDER_1, &order, 600);
+        assert_noop!(settle_match(order, bid), Error::<Test>::SpendingLimitExceeded);
+    });
+}
+
 #[test]
 fn fail_accept_missing_order() {
     new_test_ext().execute_with(|| {
@@ -147,3 +353,751 @@ fn fail_accept_missing_bid() {
         );
     });
 }
+
+#[test]
+fn can_accept_bids() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 5);
+        create_bid(PROVIDER_1, order_id, 2000);
+        create_bid(PROVIDER_2, order_id, 1000);
+        let providers = BoundedVec::try_from(vec![PROVIDER_1, PROVIDER_2]).unwrap();
+
+        assert_ok!(AiroMarket::bids_accept(
+            RuntimeOrigin::signed(CONSUMER_1),
+            order_id,
+            providers.clone(),
+            2
+        ));
+
+        assert!(!Orders::<Test>::contains_key(order_id));
+        assert!(!OrderBids::<Test>::contains_prefix(order_id));
+        assert!(!ProviderOrders::<Test>::contains_prefix(PROVIDER_1));
+        assert!(!ProviderOrders::<Test>::contains_prefix(PROVIDER_2));
+        System::assert_last_event(Event::BidsAccepted { order_id, providers, quorum: 2 }.into());
+    });
+}
+
+#[test]
+fn fail_accept_bids_missing_bid() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+        create_bid(PROVIDER_1, order_id, 2000);
+
+        assert_noop!(
+            AiroMarket::bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                order_id,
+                BoundedVec::try_from(vec![PROVIDER_1, PROVIDER_2]).unwrap(),
+                2
+            ),
+            Error::<Test>::BidNotFound
+        );
+    });
+}
+
+#[test]
+fn can_order_subscription() {
+    new_test_ext().execute_with(|| {
+        let model_id = BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap();
+
+        assert_ok!(AiroMarket::subscription_order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            model_id.clone(),
+            100,
+            10,
+            Some(5),
+            None,
+            None,
+            None,
+            None
+        ));
+
+        let order_id = CurrentOrderId::<Test>::get();
+        let expected_order = OrderDetails::new(CONSUMER_1, model_id.clone(), 0)
+            .with_subscription(SubscriptionTerms::new(100, 10, Some(5)));
+        assert_eq!(Orders::<Test>::get(order_id), Some(expected_order));
+        System::assert_last_event(Event::OrderCreated { order_id, model_id }.into());
+
+        // Subscription bids are accepted one at a time.
+        create_bid(PROVIDER_1, order_id, 1000);
+        assert_noop!(
+            AiroMarket::bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                order_id,
+                BoundedVec::try_from(vec![PROVIDER_1]).unwrap(),
+                1
+            ),
+            Error::<Test>::OrderInvalid
+        );
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert!(!Orders::<Test>::contains_key(order_id));
+    });
+}
+
+#[test]
+fn fail_order_subscription_zero_period() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroMarket::subscription_order_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+                0,
+                10,
+                None,
+                None,
+                None,
+                None,
+                None
+            ),
+            Error::<Test>::OrderInvalid
+        );
+    });
+}
+
+#[test]
+fn can_order_metered() {
+    new_test_ext().execute_with(|| {
+        let model_id = BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap();
+
+        assert_ok!(AiroMarket::order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            model_id.clone(),
+            10,
+            Some(1000),
+            None,
+            None,
+            None,
+            None,
+            None
+        ));
+
+        let order_id = CurrentOrderId::<Test>::get();
+        let expected_order = OrderDetails::new(CONSUMER_1, model_id, 10).with_metering(Some(1000));
+        assert_eq!(Orders::<Test>::get(order_id), Some(expected_order));
+
+        create_bid(PROVIDER_1, order_id, 1);
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert!(!Orders::<Test>::contains_key(order_id));
+    });
+}
+
+#[test]
+fn fail_order_metered_zero_units() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroMarket::order_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+                10,
+                Some(0),
+                None,
+                None,
+                None,
+                None,
+                None
+            ),
+            Error::<Test>::OrderInvalid
+        );
+    });
+}
+
+#[test]
+fn can_order_in_asset() {
+    new_test_ext().execute_with(|| {
+        let model_id = BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap();
+
+        assert_ok!(AiroMarket::order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            model_id.clone(),
+            10,
+            None,
+            Some(ASSET),
+            None,
+            None,
+            None,
+            None
+        ));
+
+        let order_id = CurrentOrderId::<Test>::get();
+        let expected_order = OrderDetails::new(CONSUMER_1, model_id, 10).with_asset(Some(ASSET));
+        assert_eq!(Orders::<Test>::get(order_id), Some(expected_order));
+
+        create_bid(PROVIDER_1, order_id, 1000);
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert!(!Orders::<Test>::contains_key(order_id));
+    });
+}
+
+#[test]
+fn fail_order_in_non_sufficient_asset() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroMarket::order_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+                10,
+                None,
+                Some(ASSET_NOT_SUFFICIENT),
+                None,
+                None,
+                None,
+                None
+            ),
+            Error::<Test>::AssetInvalid
+        );
+    });
+}
+
+#[test]
+fn can_invite_provider_to_private_order() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(AiroMarket::order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+            1,
+            None,
+            None,
+            None,
+            Some(BoundedVec::try_from(vec![PROVIDER_1]).unwrap()),
+            None,
+            None,
+        ));
+        let order_id = CurrentOrderId::<Test>::get();
+        create_bid(PROVIDER_1, order_id, 1000);
+        assert_noop!(
+            AiroMarket::bid_create(RuntimeOrigin::signed(PROVIDER_2), order_id, 1000, None),
+            Error::<Test>::ProviderNotAllowed
+        );
+
+        assert_noop!(
+            AiroMarket::order_invite(RuntimeOrigin::signed(CONSUMER_2), order_id, PROVIDER_2),
+            Error::<Test>::OrderInvalid
+        );
+        assert_ok!(AiroMarket::order_invite(
+            RuntimeOrigin::signed(CONSUMER_1),
+            order_id,
+            PROVIDER_2
+        ));
+        System::assert_last_event(Event::ProviderInvited { order_id, provider: PROVIDER_2 }.into());
+        create_bid(PROVIDER_2, order_id, 900);
+
+        assert_noop!(
+            AiroMarket::order_invite(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_3),
+            Error::<Test>::TooManyAllowedProviders
+        );
+    });
+}
+
+#[test]
+fn fail_invite_provider_to_open_order() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+
+        assert_noop!(
+            AiroMarket::order_invite(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1),
+            Error::<Test>::OrderInvalid
+        );
+        assert_noop!(
+            AiroMarket::order_invite(RuntimeOrigin::signed(CONSUMER_1), 99, PROVIDER_1),
+            Error::<Test>::OrderNotFound
+        );
+    });
+}
+
+#[test]
+fn can_pin_order_version() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+
+        assert_ok!(AiroMarket::order_version_pin(
+            RuntimeOrigin::signed(CONSUMER_1),
+            order_id,
+            Some(1)
+        ));
+
+        assert_eq!(Orders::<Test>::get(order_id).unwrap().model_version, Some(1));
+        System::assert_last_event(Event::OrderVersionPinned { order_id, version: Some(1) }.into());
+
+        create_bid(PROVIDER_1, order_id, 1000);
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert_eq!(AgreementVersion::get(), Some(1));
+    });
+}
+
+#[test]
+fn can_order_version() {
+    new_test_ext().execute_with(|| {
+        let model_id = BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap();
+        let create_versioned_order = |model_version| {
+            AiroMarket::order_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                model_id.clone(),
+                1,
+                None,
+                None,
+                None,
+                None,
+                None,
+                model_version,
+            )
+        };
+
+        assert_ok!(create_versioned_order(Some(1)));
+        let order_id = CurrentOrderId::<Test>::get();
+        assert_eq!(Orders::<Test>::get(order_id).unwrap().model_version, Some(1));
+
+        assert_noop!(
+            create_versioned_order(Some(LATEST_VERSION + 1)),
+            Error::<Test>::VersionNotFound
+        );
+        OldestVersion::set(2);
+        assert_noop!(create_versioned_order(Some(1)), Error::<Test>::VersionNotFound);
+    });
+}
+
+#[test]
+fn order_pinned_to_latest_version_when_created() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+        assert_eq!(Orders::<Test>::get(order_id).unwrap().model_version, Some(LATEST_VERSION));
+        create_bid(PROVIDER_1, order_id, 1000);
+
+        // A version released after the order was created is not the one the bids were made for
+        LatestVersion::set(Some(LATEST_VERSION + 1));
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert_eq!(AgreementVersion::get(), Some(LATEST_VERSION));
+
+        // Unversioned models have no version to run
+        LatestVersion::set(None);
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+        create_bid(PROVIDER_1, order_id, 1000);
+
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert_eq!(AgreementVersion::get(), None);
+    });
+}
+
+#[test]
+fn fail_pin_order_version() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+
+        assert_noop!(
+            AiroMarket::order_version_pin(
+                RuntimeOrigin::signed(CONSUMER_1),
+                order_id,
+                Some(LATEST_VERSION + 1)
+            ),
+            Error::<Test>::VersionNotFound
+        );
+        assert_noop!(
+            AiroMarket::order_version_pin(RuntimeOrigin::signed(CONSUMER_2), order_id, Some(1)),
+            Error::<Test>::OrderInvalid
+        );
+        assert_noop!(
+            AiroMarket::order_version_pin(RuntimeOrigin::signed(CONSUMER_1), 99, Some(1)),
+            Error::<Test>::OrderNotFound
+        );
+
+        // Bids are made for the version the order had
+        create_bid(PROVIDER_1, order_id, 1000);
+        assert_noop!(
+            AiroMarket::order_version_pin(RuntimeOrigin::signed(CONSUMER_1), order_id, Some(1)),
+            Error::<Test>::OrderInvalid
+        );
+    });
+}
+
+#[test]
+fn fail_accept_bid_on_retired_version() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 1);
+        assert_ok!(AiroMarket::order_version_pin(
+            RuntimeOrigin::signed(CONSUMER_1),
+            order_id,
+            Some(1)
+        ));
+        create_bid(PROVIDER_1, order_id, 1000);
+
+        OldestVersion::set(2);
+        assert_noop!(
+            AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1),
+            Error::<Test>::VersionNotFound
+        );
+
+        // The order can be pinned again, dropping the bids made for the retired version
+        assert_ok!(AiroMarket::order_version_pin(
+            RuntimeOrigin::signed(CONSUMER_1),
+            order_id,
+            None
+        ));
+        assert!(!OrderBids::<Test>::contains_prefix(order_id));
+        assert!(!ProviderOrders::<Test>::contains_key(PROVIDER_1, order_id));
+        create_bid(PROVIDER_1, order_id, 1000);
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert_eq!(AgreementVersion::get(), Some(LATEST_VERSION));
+    });
+}
+
+#[test]
+fn bid_acknowledges_requirements() {
+    new_test_ext().execute_with(|| {
+        let requirements = ExecutionRequirements {
+            max_latency: Some(10),
+            min_hardware_class: Some(HardwareClass::DatacenterGpu),
+            region: Some(BoundedVec::try_from(b"EU".to_vec()).unwrap()),
+            retention: Some(DataRetention::None),
+        };
+        assert_ok!(AiroMarket::order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap(),
+            1,
+            None,
+            None,
+            None,
+            None,
+            Some(requirements.clone()),
+            None,
+        ));
+        let order_id = CurrentOrderId::<Test>::get();
+        assert_eq!(Orders::<Test>::get(order_id).unwrap().requirements, Some(requirements.clone()));
+
+        assert_noop!(
+            AiroMarket::bid_create(RuntimeOrigin::signed(PROVIDER_1), order_id, 1000, None),
+            Error::<Test>::RequirementsNotAcknowledged
+        );
+        let mut other_requirements = requirements.clone();
+        other_requirements.retention = Some(DataRetention::Days(30));
+        assert_noop!(
+            AiroMarket::bid_create(
+                RuntimeOrigin::signed(PROVIDER_1),
+                order_id,
+                1000,
+                Some(other_requirements)
+            ),
+            Error::<Test>::RequirementsNotAcknowledged
+        );
+        assert_ok!(AiroMarket::bid_create(
+            RuntimeOrigin::signed(PROVIDER_1),
+            order_id,
+            1000,
+            Some(requirements)
+        ));
+    });
+}
+
+#[test]
+fn can_match_auction_order() {
+    new_test_ext().execute_with(|| {
+        let model_id = BoundedVec::try_from("model_id".as_bytes().to_vec()).unwrap();
+        assert_ok!(AiroMarket::auction_order_create(
+            RuntimeOrigin::signed(CONSUMER_1),
+            model_id,
+            10,
+            100,
+            10,
+            150,
+            None
+        ));
+        let order_id = CurrentOrderId::<Test>::get();
+
+        // The price rises every block up to the max price
+        let auction = Orders::<Test>::get(order_id).unwrap().auction.unwrap();
+        System::set_block_number(4);
+        assert_eq!(auction.current_price(), 130);
+        System::set_block_number(20);
+        assert_eq!(auction.current_price(), 150);
+
+        System::set_block_number(3);
+        assert_ok!(AiroMarket::order_match(RuntimeOrigin::signed(PROVIDER_1), order_id, None));
+        assert!(!Orders::<Test>::contains_key(order_id));
+        assert!(!ConsumerOrders::<Test>::contains_prefix(CONSUMER_1));
+
+        System::assert_last_event(
+            Event::OrderMatched { order_id, provider: PROVIDER_1, price_per_request: 120 }.into(),
+        );
+    });
+}
+
+#[test]
+fn fail_match_non_auction_order() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 5);
+
+        assert_noop!(
+            AiroMarket::order_match(RuntimeOrigin::signed(PROVIDER_1), order_id, None),
+            Error::<Test>::OrderInvalid
+        );
+    });
+}
+
+#[test]
+fn can_accept_auction_bid() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 5);
+        assert_ok!(AiroMarket::auction_bid_create(
+            RuntimeOrigin::signed(PROVIDER_1),
+            order_id,
+            1000,
+            100,
+            700,
+            None
+        ));
+
+        // The price falls every block down to the min price
+        let bid = OrderBids::<Test>::get(order_id, PROVIDER_1).unwrap();
+        assert_eq!(bid.current_price(), 1000);
+        System::set_block_number(3);
+        assert_eq!(bid.current_price(), 800);
+        System::set_block_number(10);
+        assert_eq!(bid.current_price(), 700);
+
+        assert_ok!(AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1));
+        assert!(!Orders::<Test>::contains_key(order_id));
+    });
+}
+
+#[test]
+fn fail_auction_bid_rising_price() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_order(CONSUMER_1, "model_id", 5);
+
+        assert_noop!(
+            AiroMarket::auction_bid_create(
+                RuntimeOrigin::signed(PROVIDER_1),
+                order_id,
+                1000,
+                100,
+                1500,
+                None
+            ),
+            Error::<Test>::BidInvalid
+        );
+    });
+}
+
+#[test]
+fn can_settle_match() {
+    new_test_ext().execute_with(|| {
+        let order = offchain_order(CONSUMER_1, 1000);
+        assert_ok!(settle_match(order.clone(), offchain_bid(PROVIDER_1, &order, 800)));
+
+        let order_id = CurrentOrderId::<Test>::get();
+        assert!(OffchainNonces::<Test>::contains_key(CONSUMER_1, 0));
+        assert!(OffchainNonces::<Test>::contains_key(PROVIDER_1, 0));
+        System::assert_last_event(
+            Event::MatchSettled {
+                order_id,
+                consumer: CONSUMER_1,
+                provider: PROVIDER_1,
+                price_per_request: 800,
+            }
+            .into(),
+        );
+
+        // Neither the order nor the bid can be replayed
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_2, &order, 800)),
+            Error::<Test>::NonceAlreadyUsed
+        );
+        let order = offchain_order(CONSUMER_2, 1000);
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_1, &order, 800)),
+            Error::<Test>::NonceAlreadyUsed
+        );
+    });
+}
+
+#[test]
+fn settle_match_pinned_version() {
+    new_test_ext().execute_with(|| {
+        let mut order = offchain_order(CONSUMER_1, 1000);
+        order.model_version = Some(LATEST_VERSION + 1);
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_1, &order, 800)),
+            Error::<Test>::VersionNotFound
+        );
+
+        order.model_version = Some(1);
+        assert_ok!(settle_match(order.clone(), offchain_bid(PROVIDER_1, &order, 800)));
+        assert_eq!(AgreementVersion::get(), Some(1));
+    });
+}
+
+#[test]
+fn fail_settle_match_invalid_signature() {
+    new_test_ext().execute_with(|| {
+        let order = offchain_order(CONSUMER_1, 1000);
+        let bid = offchain_bid(PROVIDER_1, &order, 800);
+        let order_signature = TestSignature(CONSUMER_1, order.signing_payload());
+        // Signed by another account than the bid's provider
+        let bid_signature = TestSignature(PROVIDER_2, bid.signing_payload());
+        assert_noop!(
+            AiroMarket::settle_match(
+                RuntimeOrigin::signed(CONSUMER_2),
+                Box::new(order.clone()),
+                order_signature.clone(),
+                Box::new(bid.clone()),
+                bid_signature,
+            ),
+            Error::<Test>::SignatureInvalid
+        );
+
+        // Signed without the domain tag and the genesis hash
+        let bid_signature = TestSignature(PROVIDER_1, bid.encode());
+        assert_noop!(
+            AiroMarket::settle_match(
+                RuntimeOrigin::signed(CONSUMER_2),
+                Box::new(order),
+                order_signature,
+                Box::new(bid),
+                bid_signature,
+            ),
+            Error::<Test>::SignatureInvalid
+        );
+    });
+}
+
+#[test]
+fn fail_settle_match_expired_or_mismatched() {
+    new_test_ext().execute_with(|| {
+        let order = offchain_order(CONSUMER_1, 1000);
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_1, &order, 1200)),
+            Error::<Test>::BidInvalid
+        );
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_UNREGISTERED, &order, 800)),
+            Error::<Test>::ProviderNotRegistered
+        );
+
+        System::set_block_number(11);
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_1, &order, 800)),
+            Error::<Test>::OfferExpired
+        );
+    });
+}
+
+#[test]
+fn fail_settle_match_bid_on_another_order() {
+    new_test_ext().execute_with(|| {
+        let order = offchain_order(CONSUMER_1, 1000);
+        let mut other_order = order.clone();
+        other_order.nonce = 1;
+
+        // The bid is bound to the order it was made on, even if it matches another one
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_1, &other_order, 800)),
+            Error::<Test>::BidInvalid
+        );
+
+        let mut bid = offchain_bid(PROVIDER_1, &order, 800);
+        bid.requests_total = order.requests_total + 1;
+        assert_noop!(settle_match(order.clone(), bid), Error::<Test>::BidInvalid);
+
+        let mut bid = offchain_bid(PROVIDER_1, &order, 800);
+        bid.model_version = Some(1);
+        assert_noop!(settle_match(order.clone(), bid), Error::<Test>::BidInvalid);
+
+        let mut order = order;
+        order.requests_total = 0;
+        assert_noop!(
+            settle_match(order.clone(), offchain_bid(PROVIDER_1, &order, 800)),
+            Error::<Test>::OrderInvalid
+        );
+    });
+}
+
+fn create_pipeline_order(consumer: AccountId, models: &[&str]) -> OrderId {
+    let order_id = CurrentOrderId::<Test>::get() + 1;
+    let stages: Vec<_> = models
+        .iter()
+        .map(|model_id| BoundedVec::try_from(model_id.as_bytes().to_vec()).unwrap())
+        .collect();
+    assert_ok!(AiroMarket::pipeline_order_create(
+        RuntimeOrigin::signed(consumer),
+        BoundedVec::try_from(stages).unwrap(),
+        10,
+        None,
+        None
+    ));
+    order_id
+}
+
+#[test]
+fn can_order_pipeline() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_pipeline_order(CONSUMER_1, &["speech_to_text", "summarization"]);
+
+        let first_stage = Orders::<Test>::get(order_id).unwrap();
+        let last_stage = Orders::<Test>::get(order_id + 1).unwrap();
+        assert_eq!(first_stage.model_id.as_slice(), b"speech_to_text");
+        assert_eq!(
+            first_stage.pipeline,
+            Some(PipelineLink { first_stage: order_id, next_stage: Some(order_id + 1) })
+        );
+        assert_eq!(
+            last_stage.pipeline,
+            Some(PipelineLink { first_stage: order_id, next_stage: None })
+        );
+        System::assert_last_event(
+            Event::OrderCreated { order_id: order_id + 1, model_id: last_stage.model_id }.into(),
+        );
+
+        assert_noop!(
+            AiroMarket::pipeline_order_create(
+                RuntimeOrigin::signed(CONSUMER_1),
+                BoundedVec::try_from(vec![first_stage.model_id]).unwrap(),
+                10,
+                None,
+                None
+            ),
+            Error::<Test>::PipelineInvalid
+        );
+    });
+}
+
+#[test]
+fn can_accept_pipeline_bids() {
+    new_test_ext().execute_with(|| {
+        let order_id = create_pipeline_order(CONSUMER_1, &["speech_to_text", "summarization"]);
+        create_bid(PROVIDER_1, order_id, 1000);
+        create_bid(PROVIDER_2, order_id + 1, 500);
+
+        // Stages can't be accepted on their own
+        assert_noop!(
+            AiroMarket::bid_accept(RuntimeOrigin::signed(CONSUMER_1), order_id, PROVIDER_1),
+            Error::<Test>::OrderInvalid
+        );
+        // Every stage needs a provider
+        assert_noop!(
+            AiroMarket::pipeline_bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                order_id,
+                BoundedVec::try_from(vec![PROVIDER_1]).unwrap()
+            ),
+            Error::<Test>::PipelineInvalid
+        );
+        assert_noop!(
+            AiroMarket::pipeline_bids_accept(
+                RuntimeOrigin::signed(CONSUMER_1),
+                order_id + 1,
+                BoundedVec::try_from(vec![PROVIDER_2]).unwrap()
+            ),
+            Error::<Test>::PipelineInvalid
+        );
+
+        let providers = BoundedVec::try_from(vec![PROVIDER_1, PROVIDER_2]).unwrap();
+        assert_ok!(AiroMarket::pipeline_bids_accept(
+            RuntimeOrigin::signed(CONSUMER_1),
+            order_id,
+            providers.clone()
+        ));
+
+        assert!(!Orders::<Test>::contains_key(order_id));
+        assert!(!Orders::<Test>::contains_key(order_id + 1));
+        assert!(!ConsumerOrders::<Test>::contains_prefix(CONSUMER_1));
+        System::assert_last_event(Event::PipelineBidsAccepted { order_id, providers }.into());
+    });
+}
@@ -1,4 +1,5 @@
-use airo_primitives::RequestsUsize;
+use airo_primitives::{model::ModelVersion, RequestsUsize, UnitsUsize};
+use sp_runtime::traits::Hash;
 
 use crate::*;
 
@@ -9,28 +10,189 @@ pub type BalanceOf<T> =
 pub type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
 pub type Consumer<T> = AccountIdOf<T>;
 pub type Provider<T> = AccountIdOf<T>;
+pub type OrderRequirements<T> = ExecutionRequirements<BlockNumberFor<T>>;
+pub type OrderAllowlist<T> = BoundedVec<AccountIdOf<T>, <T as Config>::MaxAllowedProviders>;
+pub type OffchainNonce = u64;
+
+/// Tag the orders and bids signed off-chain are prefixed with, so that their signatures can't be
+/// taken for the signatures of anything else.
+pub const OFFCHAIN_SIGNING_DOMAIN: &[u8] = b"airo/market/offchain";
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
 #[scale_info(skip_type_params(T))]
 pub struct OrderDetails<T: Config> {
     pub consumer: Consumer<T>,
     pub model_id: T::ModelId,
+    /// Version of the model the order is pinned to, the latest one when the order is created
+    /// unless pinned to another one. None if the model has no versions.
+    pub model_version: Option<ModelVersion>,
     #[codec(compact)]
     pub requests_total: RequestsUsize,
+    /// Terms of a subscription order, which bids are priced per period.
+    pub subscription: Option<SubscriptionTerms<T>>,
+    /// Set for orders priced per usage unit, limiting the units charged per request.
+    pub max_units_per_request: Option<UnitsUsize>,
+    /// Asset bids are priced in, or the native currency if none.
+    pub asset: Option<T::AssetId>,
+    /// Reputation score a provider needs to bid on the order.
+    pub min_reputation: Option<Perbill>,
+    /// Providers allowed to bid on a private order, or none if the order is open to everyone.
+    pub allowlist: Option<OrderAllowlist<T>>,
+    /// Requirements on how the requests are executed, which bids have to acknowledge.
+    pub requirements: Option<OrderRequirements<T>>,
+    /// Rising price of an auction order, which providers can match at any time.
+    pub auction: Option<PriceCurve<T>>,
+    /// Set for the orders of the stages of a pipeline, which bids are accepted all at once.
+    pub pipeline: Option<PipelineLink<T>>,
 }
 
 impl<T: Config> OrderDetails<T> {
     pub fn new(consumer: Consumer<T>, model_id: T::ModelId, requests_total: RequestsUsize) -> Self {
-        Self { consumer, model_id, requests_total }
+        Self {
+            consumer,
+            model_version: T::ModelRegistry::latest_version(&model_id),
+            model_id,
+            requests_total,
+            subscription: None,
+            max_units_per_request: None,
+            asset: None,
+            min_reputation: None,
+            allowlist: None,
+            requirements: None,
+            auction: None,
+            pipeline: None,
+        }
+    }
+
+    pub fn with_pipeline(
+        mut self,
+        first_stage: T::OrderId,
+        next_stage: Option<T::OrderId>,
+    ) -> Self {
+        self.pipeline = Some(PipelineLink { first_stage, next_stage });
+        self
+    }
+
+    pub fn with_auction(mut self, auction: PriceCurve<T>) -> Self {
+        self.auction = Some(auction);
+        self
+    }
+
+    pub fn with_requirements(mut self, requirements: Option<OrderRequirements<T>>) -> Self {
+        self.requirements = requirements;
+        self
+    }
+
+    pub fn with_allowlist(mut self, allowlist: Option<OrderAllowlist<T>>) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    pub fn with_min_reputation(mut self, min_reputation: Option<Perbill>) -> Self {
+        self.min_reputation = min_reputation;
+        self
+    }
+
+    pub fn with_asset(mut self, asset: Option<T::AssetId>) -> Self {
+        self.asset = asset;
+        self
+    }
+
+    /// Pins the order to `model_version` instead of the latest version, if set.
+    pub fn with_model_version(mut self, model_version: Option<ModelVersion>) -> Self {
+        if model_version.is_some() {
+            self.model_version = model_version;
+        }
+        self
+    }
+
+    pub fn with_metering(mut self, max_units_per_request: Option<UnitsUsize>) -> Self {
+        self.max_units_per_request = max_units_per_request;
+        self
+    }
+
+    pub fn with_subscription(mut self, subscription: SubscriptionTerms<T>) -> Self {
+        self.subscription = Some(subscription);
+        self
     }
 
     pub fn is_valid(&self) -> bool {
-        self.requests_total > 0
+        match &self.subscription {
+            Some(subscription) => subscription.is_valid(),
+            None => {
+                self.requests_total > 0
+                    && self.max_units_per_request != Some(0)
+                    && self.auction.as_ref().is_none_or(|auction| !auction.is_falling())
+            },
+        }
+    }
+
+    pub fn is_pipeline_stage(&self) -> bool {
+        self.pipeline.is_some()
+    }
+
+    pub fn is_first_stage(&self, order_id: T::OrderId) -> bool {
+        self.pipeline.as_ref().is_some_and(|pipeline| pipeline.first_stage == order_id)
+    }
+
+    /// Whether the version the order is pinned to, if any, has not been retired.
+    pub fn has_model_version(&self) -> bool {
+        self.model_version
+            .is_none_or(|version| T::ModelRegistry::has_version(&self.model_id, version))
     }
 
     pub fn is_owned_by(&self, consumer: &Consumer<T>) -> bool {
         self.consumer == *consumer
     }
+
+    pub fn allows(&self, provider: &Provider<T>) -> bool {
+        self.allowlist.as_ref().is_none_or(|allowlist| allowlist.contains(provider))
+    }
+
+    /// Amount prepaid to a provider bidding `price`, royalties aside.
+    pub fn prepayment(&self, price: BalanceOf<T>) -> BalanceOf<T> {
+        match (&self.subscription, self.max_units_per_request) {
+            (Some(subscription), _) => price.saturating_mul(subscription.periods_total.into()),
+            (None, Some(max_units_per_request)) => price
+                .saturating_mul(max_units_per_request.into())
+                .saturating_mul(self.requests_total.into()),
+            (None, None) => price.saturating_mul(self.requests_total.into()),
+        }
+    }
+
+    /// Adds a provider to the allowlist of a private order. Open orders can't be made private.
+    pub fn invite(&mut self, provider: Provider<T>) -> DispatchResult {
+        let allowlist = self.allowlist.as_mut().ok_or(Error::<T>::OrderInvalid)?;
+        if !allowlist.contains(&provider) {
+            allowlist.try_push(provider).map_err(|_| Error::<T>::TooManyAllowedProviders)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct SubscriptionTerms<T: Config> {
+    /// Length of a period in blocks.
+    pub period: BlockNumberFor<T>,
+    /// Number of periods paid in advance.
+    #[codec(compact)]
+    pub periods_total: u32,
+    pub requests_per_period: Option<RequestsUsize>,
+}
+
+impl<T: Config> SubscriptionTerms<T> {
+    pub fn new(
+        period: BlockNumberFor<T>,
+        periods_total: u32,
+        requests_per_period: Option<RequestsUsize>,
+    ) -> Self {
+        Self { period, periods_total, requests_per_period }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.period.is_zero() && self.periods_total > 0
+    }
 }
 
 #[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
@@ -39,10 +201,168 @@ pub struct BidDetails<T: Config> {
     // TODO. This might be redundant as `OrderBids` already contains this provider.
     pub provider: Provider<T>,
     pub price_per_request: BalanceOf<T>,
+    /// Falling price of an auction bid, starting at `price_per_request`.
+    pub price_curve: Option<PriceCurve<T>>,
 }
 
 impl<T: Config> BidDetails<T> {
     pub fn new(provider: Provider<T>, price_per_request: BalanceOf<T>) -> Self {
-        Self { provider, price_per_request }
+        Self { provider, price_per_request, price_curve: None }
+    }
+
+    pub fn with_price_curve(mut self, price_curve: PriceCurve<T>) -> Self {
+        self.price_curve = Some(price_curve);
+        self
+    }
+
+    pub fn current_price(&self) -> BalanceOf<T> {
+        self.price_curve
+            .as_ref()
+            .map_or(self.price_per_request, |price_curve| price_curve.current_price())
     }
 }
+
+/// Links the order of a stage of a pipeline to the other stages.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct PipelineLink<T: Config> {
+    pub first_stage: T::OrderId,
+    pub next_stage: Option<T::OrderId>,
+}
+
+/// Price moving by a step every block, from the start price towards the limit price.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct PriceCurve<T: Config> {
+    pub start_price: BalanceOf<T>,
+    pub price_step: BalanceOf<T>,
+    /// Price the curve stops at, above the start price for a rising curve and below for a
+    /// falling one.
+    pub limit_price: BalanceOf<T>,
+    pub starts_at: BlockNumberFor<T>,
+}
+
+impl<T: Config> PriceCurve<T> {
+    pub fn new(
+        start_price: BalanceOf<T>,
+        price_step: BalanceOf<T>,
+        limit_price: BalanceOf<T>,
+    ) -> Self {
+        let starts_at = frame_system::Pallet::<T>::block_number();
+        Self { start_price, price_step, limit_price, starts_at }
+    }
+
+    pub fn is_falling(&self) -> bool {
+        self.limit_price < self.start_price
+    }
+
+    pub fn current_price(&self) -> BalanceOf<T> {
+        let now = frame_system::Pallet::<T>::block_number();
+        let blocks = now.saturating_sub(self.starts_at).saturated_into::<u32>();
+        let change = self.price_step.saturating_mul(blocks.into());
+        if self.is_falling() {
+            self.start_price.saturating_sub(change).max(self.limit_price)
+        } else {
+            self.start_price.saturating_add(change).min(self.limit_price)
+        }
+    }
+}
+
+/// Order signed off-chain by a consumer, settled on-chain along with a matching signed bid.
+#[derive(
+    CloneNoBound, Encode, Decode, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct OffchainOrder<T: Config> {
+    pub consumer: Consumer<T>,
+    pub model_id: T::ModelId,
+    /// Version of the model the order is pinned to, or the latest one when settled if none.
+    pub model_version: Option<ModelVersion>,
+    pub requests_total: RequestsUsize,
+    /// Highest price per request the consumer accepts to pay.
+    pub max_price_per_request: BalanceOf<T>,
+    pub asset: Option<T::AssetId>,
+    pub requirements: Option<OrderRequirements<T>>,
+    /// Single-use nonce of the consumer, preventing the order from being replayed.
+    pub nonce: OffchainNonce,
+    /// Last block the order can be settled at.
+    pub expires_at: BlockNumberFor<T>,
+}
+
+impl<T: Config> OffchainOrder<T> {
+    pub fn is_valid(&self) -> bool {
+        self.requests_total > 0
+    }
+
+    pub fn hash(&self) -> T::Hash {
+        T::Hashing::hash_of(self)
+    }
+
+    pub fn signing_payload(&self) -> Vec<u8> {
+        offchain_signing_payload::<T>(self)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        frame_system::Pallet::<T>::block_number() > self.expires_at
+    }
+
+    /// Whether the version the order is pinned to, if any, has been released.
+    pub fn has_model_version(&self) -> bool {
+        self.model_version
+            .is_none_or(|version| T::ModelRegistry::has_version(&self.model_id, version))
+    }
+
+    pub fn resolve_model_version(&self) -> Option<ModelVersion> {
+        self.model_version.or_else(|| T::ModelRegistry::latest_version(&self.model_id))
+    }
+
+    /// Whether `bid` has been made on this order, for the version of the model it resolves to.
+    pub fn matches(&self, bid: &OffchainBid<T>) -> bool {
+        bid.order_hash == self.hash()
+            && self.model_id == bid.model_id
+            && self.requests_total == bid.requests_total
+            && self.resolve_model_version() == bid.model_version
+            && self.asset == bid.asset
+            && self.requirements == bid.requirements
+            && bid.price_per_request <= self.max_price_per_request
+    }
+}
+
+/// Bid signed off-chain by a provider on a single order signed off-chain.
+#[derive(
+    CloneNoBound, Encode, Decode, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct OffchainBid<T: Config> {
+    pub provider: Provider<T>,
+    /// Hash of the order the bid is made on.
+    pub order_hash: T::Hash,
+    pub model_id: T::ModelId,
+    /// Version of the model the provider executes the requests on.
+    pub model_version: Option<ModelVersion>,
+    pub requests_total: RequestsUsize,
+    pub price_per_request: BalanceOf<T>,
+    pub asset: Option<T::AssetId>,
+    /// Requirements the provider acknowledges, which have to be the ones of the order.
+    pub requirements: Option<OrderRequirements<T>>,
+    /// Single-use nonce of the provider, preventing the bid from being replayed.
+    pub nonce: OffchainNonce,
+    /// Last block the bid can be settled at.
+    pub expires_at: BlockNumberFor<T>,
+}
+
+impl<T: Config> OffchainBid<T> {
+    pub fn signing_payload(&self) -> Vec<u8> {
+        offchain_signing_payload::<T>(self)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        frame_system::Pallet::<T>::block_number() > self.expires_at
+    }
+}
+
+/// Payload an order or a bid is signed off-chain as, bound to the market and to the chain.
+fn offchain_signing_payload<T: Config>(offer: &impl Encode) -> Vec<u8> {
+    let genesis_hash = frame_system::Pallet::<T>::block_hash(BlockNumberFor::<T>::zero());
+    (OFFCHAIN_SIGNING_DOMAIN, genesis_hash, offer).encode()
+}
@@ -5,6 +5,15 @@ pub trait WeightInfo {
     fn order_create() -> Weight;
     fn bid_create() -> Weight;
     fn bid_accept() -> Weight;
+    fn bids_accept(p: u32) -> Weight;
+    fn subscription_order_create() -> Weight;
+    fn order_invite() -> Weight;
+    fn auction_order_create() -> Weight;
+    fn order_match() -> Weight;
+    fn settle_match() -> Weight;
+    fn pipeline_order_create(s: u32) -> Weight;
+    fn pipeline_bids_accept(p: u32) -> Weight;
+    fn order_version_pin() -> Weight;
 }
 
 /// Weights used for tests only.
@@ -20,4 +29,40 @@ impl WeightInfo for () {
     fn bid_accept() -> Weight {
         Weight::from_parts(10_000_000, 0)
     }
+
+    fn bids_accept(_p: u32) -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn subscription_order_create() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn order_invite() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn auction_order_create() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn order_match() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn settle_match() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn pipeline_order_create(_s: u32) -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn pipeline_bids_accept(_p: u32) -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn order_version_pin() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
 }
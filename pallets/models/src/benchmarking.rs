@@ -0,0 +1,65 @@
+#![cfg(feature = "runtime-benchmarks")]
+
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+use sp_std::prelude::*;
+
+pub use airo_primitives::benchmarking::{ContentFactory, ModelOwnerFactory};
+
+#[allow(unused)]
+use crate::Pallet as AiroModels;
+
+use super::*;
+
+#[benchmarks]
+mod benchmarks {
+    use super::*;
+
+    #[benchmark]
+    fn version_create() {
+        let caller: T::AccountId = whitelisted_caller();
+        let model_id = T::BenchmarkHelper::create_model(&caller);
+        let weights = T::BenchmarkHelper::get_content_id();
+        // Decoding the versions of a model is the worst case once it has as many as it can.
+        let versions: Vec<_> = (1..T::MaxVersions::get())
+            .map(|_| VersionDetails::<T>::new(weights.clone(), T::Hash::default()))
+            .collect();
+        Models::<T>::insert(
+            &model_id,
+            ModelDetails::<T> {
+                versions: BoundedVec::try_from(versions).unwrap(),
+                retired_count: 0,
+            },
+        );
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), model_id.clone(), weights, T::Hash::default());
+
+        assert_eq!(AiroModels::<T>::latest_version(&model_id), Some(T::MaxVersions::get()));
+    }
+
+    #[benchmark]
+    fn version_retire() {
+        let caller: T::AccountId = whitelisted_caller();
+        let model_id = T::BenchmarkHelper::create_model(&caller);
+        let weights = T::BenchmarkHelper::get_content_id();
+        // Shifting the versions left is the worst case once the model has as many as it can.
+        let versions: Vec<_> = (0..T::MaxVersions::get())
+            .map(|_| VersionDetails::<T>::new(weights.clone(), T::Hash::default()))
+            .collect();
+        Models::<T>::insert(
+            &model_id,
+            ModelDetails::<T> {
+                versions: BoundedVec::try_from(versions).unwrap(),
+                retired_count: 0,
+            },
+        );
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller), model_id.clone());
+
+        assert!(!AiroModels::<T>::has_version(&model_id, 1));
+    }
+
+    impl_benchmark_test_suite!(AiroModels, mock::new_test_ext(), mock::Test);
+}
@@ -0,0 +1,171 @@
+//! # Models Pallet
+
+// We make sure this pallet uses `no_std` for compiling to Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::pallet_prelude::*;
+use frame_system::pallet_prelude::*;
+use sp_runtime::Saturating;
+
+use airo_primitives::model::{ModelOwnership, ModelRegistry, ModelVersion};
+pub use pallet::*;
+pub use types::*;
+pub use weights::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+// Every callable function or "dispatchable" a pallet exposes must have weight values that correctly
+// estimate a dispatchable's execution time. The benchmarking module is used to calculate weights
+// for each dispatchable and generates this pallet's weight.rs file. Learn more about benchmarking here: https://docs.substrate.io/test/benchmark/
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+
+mod types;
+pub mod weights;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// A type representing the weights required by the dispatchables of this pallet.
+        type WeightInfo: WeightInfo;
+
+        /// The overarching runtime event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Model ID type.
+        type ModelId: Member + Parameter + MaxEncodedLen;
+
+        /// Content ID type, identifying the weights of a version on DX.
+        type ContentId: Member + Parameter + MaxEncodedLen;
+
+        /// Used to find the owners of models, which are allowed to release their versions.
+        type ModelOwnership: ModelOwnership<AccountId = Self::AccountId, ModelId = Self::ModelId>;
+
+        /// The maximum number of versions a model can have.
+        #[pallet::constant]
+        type MaxVersions: Get<u32>;
+
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: benchmarking::ModelOwnerFactory<Self::AccountId, Self::ModelId>
+            + benchmarking::ContentFactory<Self::ContentId>;
+    }
+
+    /// Models which have released versions.
+    #[pallet::storage]
+    pub type Models<T: Config> = StorageMap<_, Blake2_128Concat, T::ModelId, ModelDetails<T>>;
+
+    /// Events.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A new version of a model has been released.
+        VersionCreated {
+            /// The model ID.
+            model_id: T::ModelId,
+            /// The version.
+            version: ModelVersion,
+            /// The content ID of the weights on DX.
+            weights: T::ContentId,
+            /// The hash of the configuration the weights are run with.
+            config_hash: T::Hash,
+        },
+        /// The oldest version of a model has been retired.
+        VersionRetired {
+            /// The model ID.
+            model_id: T::ModelId,
+            /// The version.
+            version: ModelVersion,
+        },
+    }
+
+    /// Errors.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Model is not found.
+        ModelNotFound,
+        /// Model is invalid.
+        ModelInvalid,
+        /// Model has reached the maximum number of versions.
+        TooManyVersions,
+        /// Version is not found.
+        VersionNotFound,
+        /// Version is invalid.
+        VersionInvalid,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Executed by the owner of a model to release a new version of it, which becomes its
+        /// latest version. Once the model has `T::MaxVersions` versions, the oldest ones have to be
+        /// retired to release new ones.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::WeightInfo::version_create())]
+        pub fn version_create(
+            origin: OriginFor<T>,
+            model_id: T::ModelId,
+            weights: T::ContentId,
+            config_hash: T::Hash,
+        ) -> DispatchResult {
+            let owner = ensure_signed(origin)?;
+
+            let model_owner =
+                T::ModelOwnership::owner(&model_id).ok_or(Error::<T>::ModelNotFound)?;
+            ensure!(model_owner == owner, Error::<T>::ModelInvalid);
+
+            let version = Models::<T>::try_mutate(&model_id, |model| {
+                model
+                    .get_or_insert_with(Default::default)
+                    .push_version(VersionDetails::new(weights.clone(), config_hash))
+            })?;
+
+            Self::deposit_event(Event::VersionCreated { model_id, version, weights, config_hash });
+            Ok(())
+        }
+
+        /// Executed by the owner of a model to retire its oldest version, which new orders can't
+        /// be pinned to anymore. Agreements already made on it keep the version they recorded.
+        /// The latest version can't be retired.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::WeightInfo::version_retire())]
+        pub fn version_retire(origin: OriginFor<T>, model_id: T::ModelId) -> DispatchResult {
+            let owner = ensure_signed(origin)?;
+
+            let model_owner =
+                T::ModelOwnership::owner(&model_id).ok_or(Error::<T>::ModelNotFound)?;
+            ensure!(model_owner == owner, Error::<T>::ModelInvalid);
+
+            let version = Models::<T>::try_mutate(&model_id, |model| {
+                model.as_mut().ok_or(Error::<T>::VersionNotFound)?.retire_oldest_version()
+            })?;
+
+            Self::deposit_event(Event::VersionRetired { model_id, version });
+            Ok(())
+        }
+    }
+}
+
+impl<T: Config> ModelRegistry for Pallet<T> {
+    type ModelId = T::ModelId;
+
+    fn latest_version(model_id: &Self::ModelId) -> Option<ModelVersion> {
+        Models::<T>::get(model_id).and_then(|model| model.latest_version())
+    }
+
+    fn has_version(model_id: &Self::ModelId, version: ModelVersion) -> bool {
+        Models::<T>::get(model_id).is_some_and(|model| model.version(version).is_some())
+    }
+}
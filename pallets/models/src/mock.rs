@@ -0,0 +1,113 @@
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{ConstU16, ConstU32, ConstU64},
+    BoundedVec,
+};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, IdentityLookup},
+    BuildStorage,
+};
+
+use airo_primitives::model::ModelOwnership;
+
+use crate as pallet_models;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+pub type AccountId = u64;
+pub type ModelId = BoundedVec<u8, ConstU32<128>>;
+pub type ContentId = H256;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+    pub enum Test
+    {
+        System: frame_system,
+        AiroModels: pallet_models,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Nonce = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Block = Block;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+parameter_types! {
+    pub static ModelOwner: AccountId = OWNER;
+}
+
+pub struct TestModelOwnership;
+
+impl ModelOwnership for TestModelOwnership {
+    type AccountId = AccountId;
+    type ModelId = ModelId;
+
+    fn owner(model_id: &ModelId) -> Option<AccountId> {
+        (model_id.as_slice() != UNOWNED_MODEL.as_bytes()).then(ModelOwner::get)
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct AiroModelsBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::ModelOwnerFactory<AccountId, ModelId> for AiroModelsBenchmarkHelper {
+    fn create_model(owner: &AccountId) -> ModelId {
+        ModelOwner::set(*owner);
+        sp_core::bounded_vec![1; 128]
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::ContentFactory<ContentId> for AiroModelsBenchmarkHelper {
+    fn get_content_id() -> ContentId {
+        ContentId::repeat_byte(1)
+    }
+}
+
+impl pallet_models::Config for Test {
+    type WeightInfo = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ModelId = ModelId;
+    type ContentId = ContentId;
+    type ModelOwnership = TestModelOwnership;
+    type MaxVersions = ConstU32<MAX_VERSIONS>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = AiroModelsBenchmarkHelper;
+}
+
+pub const MAX_VERSIONS: u32 = 2;
+pub const OWNER: AccountId = 1;
+pub const OTHER: AccountId = 2;
+pub const UNOWNED_MODEL: &str = "unowned_model";
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+    let mut ext = sp_io::TestExternalities::new(storage);
+    // Go past genesis block so events get deposited
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
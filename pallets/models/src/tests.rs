@@ -0,0 +1,128 @@
+use frame_support::*;
+use sp_core::H256;
+
+use airo_primitives::model::ModelRegistry;
+
+use crate::{mock::*, *};
+
+fn model_id(model_id: &str) -> ModelId {
+    BoundedVec::try_from(model_id.as_bytes().to_vec()).unwrap()
+}
+
+fn create_version(model_id: ModelId, weights: ContentId) {
+    assert_ok!(AiroModels::version_create(
+        RuntimeOrigin::signed(OWNER),
+        model_id,
+        weights,
+        H256::repeat_byte(1),
+    ));
+}
+
+#[test]
+fn can_create_version() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(AiroModels::latest_version(&model_id("model")), None);
+
+        create_version(model_id("model"), ContentId::repeat_byte(1));
+        System::set_block_number(2);
+        create_version(model_id("model"), ContentId::repeat_byte(2));
+
+        let model = Models::<Test>::get(model_id("model")).unwrap();
+        assert_eq!(model.version(0), None);
+        assert_eq!(model.version(1).unwrap().weights, ContentId::repeat_byte(1));
+        assert_eq!(model.version(2).unwrap().weights, ContentId::repeat_byte(2));
+        assert_eq!(model.version(2).unwrap().released_at, 2);
+        assert_eq!(AiroModels::latest_version(&model_id("model")), Some(2));
+        assert!(AiroModels::has_version(&model_id("model"), 1));
+        assert!(!AiroModels::has_version(&model_id("model"), 3));
+        System::assert_last_event(
+            Event::VersionCreated {
+                model_id: model_id("model"),
+                version: 2,
+                weights: ContentId::repeat_byte(2),
+                config_hash: H256::repeat_byte(1),
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn fail_create_version() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroModels::version_create(
+                RuntimeOrigin::signed(OWNER),
+                model_id(UNOWNED_MODEL),
+                ContentId::default(),
+                H256::default(),
+            ),
+            Error::<Test>::ModelNotFound
+        );
+        assert_noop!(
+            AiroModels::version_create(
+                RuntimeOrigin::signed(OTHER),
+                model_id("model"),
+                ContentId::default(),
+                H256::default(),
+            ),
+            Error::<Test>::ModelInvalid
+        );
+
+        for _ in 0..MAX_VERSIONS {
+            create_version(model_id("model"), ContentId::default());
+        }
+        assert_noop!(
+            AiroModels::version_create(
+                RuntimeOrigin::signed(OWNER),
+                model_id("model"),
+                ContentId::default(),
+                H256::default(),
+            ),
+            Error::<Test>::TooManyVersions
+        );
+    });
+}
+
+#[test]
+fn can_retire_version() {
+    new_test_ext().execute_with(|| {
+        for _ in 0..MAX_VERSIONS {
+            create_version(model_id("model"), ContentId::repeat_byte(1));
+        }
+
+        assert_ok!(AiroModels::version_retire(RuntimeOrigin::signed(OWNER), model_id("model")));
+        System::assert_last_event(
+            Event::VersionRetired { model_id: model_id("model"), version: 1 }.into(),
+        );
+        assert!(!AiroModels::has_version(&model_id("model"), 1));
+        assert!(AiroModels::has_version(&model_id("model"), 2));
+
+        // Retiring a version makes room for a new one, which keeps counting.
+        create_version(model_id("model"), ContentId::repeat_byte(3));
+        let model = Models::<Test>::get(model_id("model")).unwrap();
+        assert_eq!(model.version(3).unwrap().weights, ContentId::repeat_byte(3));
+        assert_eq!(AiroModels::latest_version(&model_id("model")), Some(MAX_VERSIONS + 1));
+    });
+}
+
+#[test]
+fn fail_retire_version() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroModels::version_retire(RuntimeOrigin::signed(OWNER), model_id("model")),
+            Error::<Test>::VersionNotFound
+        );
+
+        create_version(model_id("model"), ContentId::default());
+        assert_noop!(
+            AiroModels::version_retire(RuntimeOrigin::signed(OTHER), model_id("model")),
+            Error::<Test>::ModelInvalid
+        );
+        // The latest version can't be retired.
+        assert_noop!(
+            AiroModels::version_retire(RuntimeOrigin::signed(OWNER), model_id("model")),
+            Error::<Test>::VersionInvalid
+        );
+    });
+}
@@ -0,0 +1,53 @@
+use crate::*;
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct VersionDetails<T: Config> {
+    /// Content ID of the weights on DX.
+    pub weights: T::ContentId,
+    /// Hash of the configuration the weights are run with.
+    pub config_hash: T::Hash,
+    pub released_at: BlockNumberFor<T>,
+}
+
+impl<T: Config> VersionDetails<T> {
+    pub fn new(weights: T::ContentId, config_hash: T::Hash) -> Self {
+        Self { weights, config_hash, released_at: frame_system::Pallet::<T>::block_number() }
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo, DefaultNoBound)]
+#[scale_info(skip_type_params(T))]
+pub struct ModelDetails<T: Config> {
+    /// Released versions which have not been retired, the oldest first.
+    pub versions: BoundedVec<VersionDetails<T>, T::MaxVersions>,
+    /// Number of the oldest versions which have been retired, which keep their numbers.
+    pub retired_count: ModelVersion,
+}
+
+impl<T: Config> ModelDetails<T> {
+    pub fn latest_version(&self) -> Option<ModelVersion> {
+        (!self.versions.is_empty())
+            .then_some(self.retired_count.saturating_add(self.versions.len() as ModelVersion))
+    }
+
+    pub fn version(&self, version: ModelVersion) -> Option<&VersionDetails<T>> {
+        version
+            .checked_sub(self.retired_count.saturating_add(1))
+            .and_then(|index| self.versions.get(index as usize))
+    }
+
+    /// Releases a version, returning its number.
+    pub fn push_version(&mut self, version: VersionDetails<T>) -> Result<ModelVersion, Error<T>> {
+        self.versions.try_push(version).map_err(|_| Error::<T>::TooManyVersions)?;
+        self.latest_version().ok_or(Error::<T>::VersionNotFound)
+    }
+
+    /// Retires the oldest version, returning its number. The latest version can't be retired.
+    pub fn retire_oldest_version(&mut self) -> Result<ModelVersion, Error<T>> {
+        ensure!(self.versions.len() > 1, Error::<T>::VersionInvalid);
+        self.versions.remove(0);
+        self.retired_count.saturating_inc();
+        Ok(self.retired_count)
+    }
+}
@@ -0,0 +1,17 @@
+use frame_support::weights::Weight;
+
+/// Weight functions needed for pallet_models.
+pub trait WeightInfo {
+    fn version_create() -> Weight;
+    fn version_retire() -> Weight;
+}
+
+/// Weights used for tests only.
+impl WeightInfo for () {
+    fn version_create() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+    fn version_retire() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+}
@@ -0,0 +1,22 @@
+//! Runtime API definition for the providers pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use scale_codec::Codec;
+use sp_runtime::Perbill;
+
+pub use pallet_providers::ReputationDetails;
+
+sp_api::decl_runtime_apis! {
+    pub trait ProvidersApi<AccountId, BlockNumber>
+    where
+        AccountId: Codec,
+        BlockNumber: Codec,
+    {
+        /// Outcomes of the executions of `provider` its reputation is derived from.
+        fn reputation(provider: AccountId) -> ReputationDetails<BlockNumber>;
+
+        /// Reputation score of `provider`.
+        fn reputation_score(provider: AccountId) -> Perbill;
+    }
+}
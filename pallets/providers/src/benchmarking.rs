@@ -0,0 +1,103 @@
+#![cfg(feature = "runtime-benchmarks")]
+
+use frame_benchmarking::v2::*;
+use frame_support::assert_ok;
+use frame_system::RawOrigin;
+use sp_std::prelude::*;
+
+pub use airo_primitives::benchmarking::ModelOwnerFactory;
+
+#[allow(unused)]
+use crate::Pallet as AiroProviders;
+
+use super::*;
+
+fn prefund_account<T: Config>(account: &T::AccountId) {
+    T::Currency::set_balance(account, BalanceOf::<T>::from(100_000_000u32));
+}
+
+fn get_metadata<T: Config>() -> ProviderMetadata<T> {
+    ProviderMetadata::new(
+        vec![0u8; T::MaxNameLength::get() as usize].try_into().unwrap(),
+        vec![0u8; T::MaxEndpointLength::get() as usize].try_into().unwrap(),
+        vec![0u8; T::MaxPeerIdLength::get() as usize].try_into().unwrap(),
+    )
+}
+
+fn get_models<T: Config>(owner: &T::AccountId) -> ProviderModels<T> {
+    vec![T::BenchmarkHelper::create_model(owner); T::MaxModels::get() as usize]
+        .try_into()
+        .unwrap()
+}
+
+fn register<T: Config>(provider: T::AccountId) -> ProviderModels<T> {
+    let models = get_models::<T>(&provider);
+    assert_ok!(AiroProviders::<T>::provider_register(
+        RawOrigin::Signed(provider).into(),
+        get_metadata::<T>(),
+        models.clone(),
+        10,
+    ));
+    models
+}
+
+#[benchmarks]
+mod benchmarks {
+    use super::*;
+
+    #[benchmark]
+    fn provider_register() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let models = get_models::<T>(&caller);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), get_metadata::<T>(), models, 10);
+
+        assert!(Providers::<T>::contains_key(caller));
+    }
+
+    #[benchmark]
+    fn provider_update() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        let models = register::<T>(caller.clone());
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), get_metadata::<T>(), models, 20);
+
+        assert_eq!(Providers::<T>::get(caller).unwrap().capacity, 20);
+    }
+
+    #[benchmark]
+    fn provider_deregister() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        register::<T>(caller.clone());
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()));
+
+        assert!(!Providers::<T>::contains_key(caller));
+    }
+
+    #[benchmark]
+    fn bond_withdraw() {
+        let caller: T::AccountId = whitelisted_caller();
+        prefund_account::<T>(&caller);
+        register::<T>(caller.clone());
+        assert_ok!(AiroProviders::<T>::provider_deregister(
+            RawOrigin::Signed(caller.clone()).into()
+        ));
+        frame_system::Pallet::<T>::set_block_number(
+            frame_system::Pallet::<T>::block_number() + T::UnbondingPeriod::get(),
+        );
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()));
+
+        assert!(!Unbondings::<T>::contains_key(caller));
+    }
+
+    impl_benchmark_test_suite!(AiroProviders, mock::new_test_ext(), mock::Test);
+}
@@ -0,0 +1,323 @@
+//! # Providers Pallet
+
+// We make sure this pallet uses `no_std` for compiling to Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{
+    pallet_prelude::*,
+    traits::{
+        fungible::{hold::Mutate as FunHoldMutate, Inspect as FunInspect, Mutate as FunMutate},
+        tokens::{Fortitude::Polite, Precision::BestEffort, Restriction::Free},
+    },
+};
+use frame_system::pallet_prelude::*;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, Zero},
+    Perbill, Saturating,
+};
+
+use airo_primitives::{
+    agreement::AgreementRegistry,
+    model::ModelOwnership,
+    provider::{ExecutionObserver, ProviderBonds, ProviderRegistry},
+    RequestsUsize,
+};
+pub use pallet::*;
+use storage::*;
+pub use types::*;
+pub use weights::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+// Every callable function or "dispatchable" a pallet exposes must have weight values that correctly
+// estimate a dispatchable's execution time. The benchmarking module is used to calculate weights
+// for each dispatchable and generates this pallet's weight.rs file. Learn more about benchmarking here: https://docs.substrate.io/test/benchmark/
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+
+mod storage;
+mod types;
+pub mod weights;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// A type representing the weights required by the dispatchables of this pallet.
+        type WeightInfo: WeightInfo;
+
+        /// The overarching runtime event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Overarching hold reason.
+        type RuntimeHoldReason: From<HoldReason>;
+
+        /// Currency type that this works on.
+        type Currency: FunMutate<Self::AccountId>
+            + FunHoldMutate<Self::AccountId, Reason = Self::RuntimeHoldReason>;
+
+        /// Model ID type.
+        type ModelId: Member + Parameter + MaxEncodedLen;
+
+        /// Owners of the models, which only existing models have.
+        type ModelOwnership: ModelOwnership<AccountId = Self::AccountId, ModelId = Self::ModelId>;
+
+        /// Agreements of the providers, which they can't deregister with.
+        type AgreementRegistry: AgreementRegistry<Self::AccountId>;
+
+        /// Bond held from a provider for as long as it is registered, slashed when a challenge of
+        /// one of its responses is upheld.
+        #[pallet::constant]
+        type ProviderBond: Get<BalanceOf<Self>>;
+
+        /// Number of blocks the bond stays held after a provider deregisters.
+        #[pallet::constant]
+        type UnbondingPeriod: Get<BlockNumberFor<Self>>;
+
+        /// Average number of blocks providers are expected to respond within. Providers responding
+        /// slower on average have their reputation scaled down.
+        #[pallet::constant]
+        type LatencyTarget: Get<BlockNumberFor<Self>>;
+
+        /// The maximum length of a provider's name.
+        #[pallet::constant]
+        type MaxNameLength: Get<u32>;
+
+        /// The maximum length of a provider's endpoint.
+        #[pallet::constant]
+        type MaxEndpointLength: Get<u32>;
+
+        /// The maximum length of a provider's DX peer ID.
+        #[pallet::constant]
+        type MaxPeerIdLength: Get<u32>;
+
+        /// The maximum number of models a provider can serve.
+        #[pallet::constant]
+        type MaxModels: Get<u32>;
+
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: benchmarking::ModelOwnerFactory<Self::AccountId, Self::ModelId>;
+    }
+
+    /// Registered providers.
+    #[pallet::storage]
+    pub type Providers<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, ProviderDetails<T>>;
+
+    /// Registered providers serving a model.
+    #[pallet::storage]
+    pub type ModelProviders<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::ModelId, Twox64Concat, T::AccountId, ()>;
+
+    /// Bonds of deregistered providers, held until the unbonding period elapses.
+    #[pallet::storage]
+    pub type Unbondings<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, UnbondingDetails<T>>;
+
+    /// Outcomes of the executions of providers, kept after they deregister.
+    #[pallet::storage]
+    pub type Reputations<T: Config> =
+        StorageMap<_, Twox64Concat, T::AccountId, ReputationDetails<BlockNumberFor<T>>, ValueQuery>;
+
+    /// A reason for the Providers pallet placing a hold on funds.
+    #[pallet::composite_enum]
+    pub enum HoldReason {
+        /// Bond of a registered provider.
+        ProviderBond,
+    }
+
+    /// Events.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A provider has been registered.
+        ProviderRegistered {
+            /// The provider.
+            provider: T::AccountId,
+        },
+        /// A provider has updated its registration.
+        ProviderUpdated {
+            /// The provider.
+            provider: T::AccountId,
+        },
+        /// A provider has been deregistered.
+        ProviderDeregistered {
+            /// The provider.
+            provider: T::AccountId,
+            /// The block the bond can be withdrawn at.
+            unlocks_at: BlockNumberFor<T>,
+        },
+        /// The bond of a deregistered provider has been withdrawn.
+        BondWithdrawn {
+            /// The provider.
+            provider: T::AccountId,
+            /// The amount released.
+            amount: BalanceOf<T>,
+        },
+    }
+
+    /// Errors.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Provider is not found.
+        ProviderNotFound,
+        /// Provider is invalid.
+        ProviderInvalid,
+        /// Provider already exists.
+        ProviderAlreadyExists,
+        /// Unbonding is not found.
+        UnbondingNotFound,
+        /// Unbonding period has not elapsed yet.
+        UnbondingActive,
+        /// Model is not found.
+        ModelNotFound,
+        /// Provider still has agreements.
+        AgreementsActive,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Registers the caller as a provider serving `models`, holding the provider bond.
+        /// `capacity` is the number of requests it can have unanswered at once.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::WeightInfo::provider_register())]
+        pub fn provider_register(
+            origin: OriginFor<T>,
+            metadata: ProviderMetadata<T>,
+            models: ProviderModels<T>,
+            #[pallet::compact] capacity: RequestsUsize,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            ensure!(!Provider::<T>::exists(&provider), Error::<T>::ProviderAlreadyExists);
+            let provider_details = ProviderDetails::new(metadata, models, capacity);
+            ensure!(provider_details.is_valid(), Error::<T>::ProviderInvalid);
+            ensure!(provider_details.models_exist(), Error::<T>::ModelNotFound);
+
+            provider_details.hold_bond(&provider)?;
+            Provider::<T>::insert(&provider, provider_details);
+
+            Self::deposit_event(Event::ProviderRegistered { provider });
+            Ok(())
+        }
+
+        /// Executed by a provider to update its metadata, models and capacity.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::WeightInfo::provider_update())]
+        pub fn provider_update(
+            origin: OriginFor<T>,
+            metadata: ProviderMetadata<T>,
+            models: ProviderModels<T>,
+            #[pallet::compact] capacity: RequestsUsize,
+        ) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let mut provider_details =
+                Provider::<T>::remove(&provider).ok_or(Error::<T>::ProviderNotFound)?;
+            provider_details.metadata = metadata;
+            provider_details.models = models;
+            provider_details.capacity = capacity;
+            ensure!(provider_details.is_valid(), Error::<T>::ProviderInvalid);
+            ensure!(provider_details.models_exist(), Error::<T>::ModelNotFound);
+
+            Provider::<T>::insert(&provider, provider_details);
+
+            Self::deposit_event(Event::ProviderUpdated { provider });
+            Ok(())
+        }
+
+        /// Executed by a provider without agreements to deregister. Its bond can be withdrawn
+        /// once the unbonding period elapses.
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::provider_deregister())]
+        pub fn provider_deregister(origin: OriginFor<T>) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            ensure!(!T::AgreementRegistry::has_agreements(&provider), Error::<T>::AgreementsActive);
+
+            let provider_details =
+                Provider::<T>::remove(&provider).ok_or(Error::<T>::ProviderNotFound)?;
+            let unlocks_at = Unbonding::<T>::add(&provider, provider_details.bond);
+
+            Self::deposit_event(Event::ProviderDeregistered { provider, unlocks_at });
+            Ok(())
+        }
+
+        /// Executed by a deregistered provider to release its bond after the unbonding period.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::bond_withdraw())]
+        pub fn bond_withdraw(origin: OriginFor<T>) -> DispatchResult {
+            let provider = ensure_signed(origin)?;
+
+            let unbonding = Unbondings::<T>::get(&provider).ok_or(Error::<T>::UnbondingNotFound)?;
+            ensure!(unbonding.is_unlocked(), Error::<T>::UnbondingActive);
+
+            unbonding.release(&provider)?;
+            Unbondings::<T>::remove(&provider);
+
+            Self::deposit_event(Event::BondWithdrawn { provider, amount: unbonding.amount });
+            Ok(())
+        }
+    }
+}
+
+impl<T: Config> ProviderRegistry for Pallet<T> {
+    type AccountId = T::AccountId;
+    type ModelId = T::ModelId;
+
+    fn serves(provider: &Self::AccountId, model_id: &Self::ModelId) -> bool {
+        ModelProviders::<T>::contains_key(model_id, provider)
+    }
+
+    fn reputation(provider: &Self::AccountId) -> Perbill {
+        Reputations::<T>::get(provider).score(T::LatencyTarget::get())
+    }
+
+    fn capacity(provider: &Self::AccountId) -> Option<RequestsUsize> {
+        Providers::<T>::get(provider).map(|provider| provider.capacity)
+    }
+}
+
+impl<T: Config> ProviderBonds<T::AccountId, BalanceOf<T>> for Pallet<T> {
+    fn slash(
+        provider: &T::AccountId,
+        beneficiary: &T::AccountId,
+        amount: BalanceOf<T>,
+    ) -> BalanceOf<T> {
+        let slashed = Provider::<T>::slash_bond(provider, beneficiary, amount);
+        slashed.saturating_add(Unbonding::<T>::slash(
+            provider,
+            beneficiary,
+            amount.saturating_sub(slashed),
+        ))
+    }
+}
+
+impl<T: Config> ExecutionObserver<T::AccountId, BlockNumberFor<T>> for Pallet<T> {
+    fn on_response(provider: &T::AccountId, latency: BlockNumberFor<T>) {
+        Reputations::<T>::mutate(provider, |reputation| reputation.record_response(latency));
+    }
+
+    fn on_request_expired(provider: &T::AccountId) {
+        Reputations::<T>::mutate(provider, |reputation| {
+            reputation.expirations_count.saturating_inc()
+        });
+    }
+
+    fn on_challenge_lost(provider: &T::AccountId) {
+        Reputations::<T>::mutate(provider, |reputation| {
+            reputation.challenges_lost_count.saturating_inc()
+        });
+    }
+}
@@ -0,0 +1,154 @@
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{ConstU16, ConstU32, ConstU64},
+    BoundedVec,
+};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, IdentityLookup},
+    BuildStorage,
+};
+
+use airo_primitives::{agreement::AgreementRegistry, model::ModelOwnership};
+
+use crate as pallet_providers;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+pub type AccountId = u64;
+pub type Balance = u64;
+pub type ModelId = BoundedVec<u8, ConstU32<128>>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+    pub enum Test
+    {
+        System: frame_system,
+        Balances: pallet_balances,
+        AiroProviders: pallet_providers,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type RuntimeOrigin = RuntimeOrigin;
+    type RuntimeCall = RuntimeCall;
+    type Nonce = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Block = Block;
+    type RuntimeEvent = RuntimeEvent;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<u64>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type RuntimeFreezeReason = ();
+    type WeightInfo = ();
+    type Balance = Balance;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU64<1>;
+    type AccountStore = System;
+    type ReserveIdentifier = [u8; 8];
+    type FreezeIdentifier = ();
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type MaxFreezes = ();
+    type DoneSlashHandler = ();
+}
+
+/// Every model but `UNKNOWN_MODEL` exists.
+pub struct TestModelOwnership;
+
+impl ModelOwnership for TestModelOwnership {
+    type AccountId = AccountId;
+    type ModelId = ModelId;
+
+    fn owner(model_id: &ModelId) -> Option<AccountId> {
+        (model_id.as_slice() != UNKNOWN_MODEL.as_bytes()).then_some(MODEL_OWNER)
+    }
+}
+
+parameter_types! {
+    /// Provider having agreements.
+    pub static AgreementsProvider: Option<AccountId> = None;
+}
+
+pub struct TestAgreementRegistry;
+
+impl AgreementRegistry<AccountId> for TestAgreementRegistry {
+    fn has_agreements(provider: &AccountId) -> bool {
+        AgreementsProvider::get() == Some(*provider)
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct AiroProvidersBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::ModelOwnerFactory<AccountId, ModelId> for AiroProvidersBenchmarkHelper {
+    fn create_model(_owner: &AccountId) -> ModelId {
+        sp_core::bounded_vec![1; 128]
+    }
+}
+
+impl pallet_providers::Config for Test {
+    type WeightInfo = ();
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type Currency = Balances;
+    type ModelId = ModelId;
+    type ModelOwnership = TestModelOwnership;
+    type AgreementRegistry = TestAgreementRegistry;
+    type ProviderBond = ConstU64<PROVIDER_BOND>;
+    type UnbondingPeriod = ConstU64<UNBONDING_PERIOD>;
+    type LatencyTarget = ConstU64<LATENCY_TARGET>;
+    type MaxNameLength = ConstU32<32>;
+    type MaxEndpointLength = ConstU32<64>;
+    type MaxPeerIdLength = ConstU32<64>;
+    type MaxModels = ConstU32<MAX_MODELS>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = AiroProvidersBenchmarkHelper;
+}
+
+pub const INITIAL_BALANCE: Balance = 1_000_000;
+pub const PROVIDER_BOND: Balance = 10_000;
+pub const UNBONDING_PERIOD: u64 = 10;
+pub const LATENCY_TARGET: u64 = 5;
+pub const MAX_MODELS: u32 = 2;
+pub const PROVIDER_NO_BALANCE: AccountId = 10;
+pub const PROVIDER_1: AccountId = 11;
+pub const PROVIDER_2: AccountId = 12;
+pub const MODEL_OWNER: AccountId = 21;
+pub const UNKNOWN_MODEL: &str = "unknown_model";
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![(PROVIDER_1, INITIAL_BALANCE), (PROVIDER_2, INITIAL_BALANCE)],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    // Go past genesis block so events get deposited
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,68 @@
+use crate::*;
+
+pub struct Provider<T>(PhantomData<T>);
+
+impl<T: Config> Provider<T> {
+    pub fn exists(provider: &AccountIdOf<T>) -> bool {
+        Providers::<T>::contains_key(provider)
+    }
+
+    pub fn insert(provider: &AccountIdOf<T>, provider_details: ProviderDetails<T>) {
+        for model_id in provider_details.models.iter() {
+            ModelProviders::<T>::insert(model_id, provider, ());
+        }
+        Providers::<T>::insert(provider, provider_details);
+    }
+
+    pub fn remove(provider: &AccountIdOf<T>) -> Option<ProviderDetails<T>> {
+        let provider_details = Providers::<T>::take(provider)?;
+        for model_id in provider_details.models.iter() {
+            ModelProviders::<T>::remove(model_id, provider);
+        }
+        Some(provider_details)
+    }
+
+    /// Slashes up to `amount` of the bond of a registered provider. Returns the amount slashed.
+    pub fn slash_bond(
+        provider: &AccountIdOf<T>,
+        beneficiary: &AccountIdOf<T>,
+        amount: BalanceOf<T>,
+    ) -> BalanceOf<T> {
+        Providers::<T>::mutate(provider, |provider_details| {
+            provider_details.as_mut().map_or(Zero::zero(), |provider_details| {
+                slash_held::<T>(provider, beneficiary, &mut provider_details.bond, amount)
+            })
+        })
+    }
+}
+
+pub struct Unbonding<T>(PhantomData<T>);
+
+impl<T: Config> Unbonding<T> {
+    /// Adds to the bond being unbonded, restarting the unbonding period.
+    /// Returns the block the bond can be withdrawn at.
+    pub fn add(provider: &AccountIdOf<T>, amount: BalanceOf<T>) -> BlockNumberFor<T> {
+        let previous_amount =
+            Unbondings::<T>::get(provider).map_or(Zero::zero(), |unbonding| unbonding.amount);
+        let unbonding = UnbondingDetails::<T>::new(previous_amount.saturating_add(amount));
+        let unlocks_at = unbonding.unlocks_at;
+        Unbondings::<T>::insert(provider, unbonding);
+        unlocks_at
+    }
+
+    /// Slashes up to `amount` of the bond being unbonded. Returns the amount slashed.
+    pub fn slash(
+        provider: &AccountIdOf<T>,
+        beneficiary: &AccountIdOf<T>,
+        amount: BalanceOf<T>,
+    ) -> BalanceOf<T> {
+        if amount.is_zero() {
+            return Zero::zero();
+        }
+        Unbondings::<T>::mutate(provider, |unbonding| {
+            unbonding.as_mut().map_or(Zero::zero(), |unbonding| {
+                slash_held::<T>(provider, beneficiary, &mut unbonding.amount, amount)
+            })
+        })
+    }
+}
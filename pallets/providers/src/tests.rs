@@ -0,0 +1,279 @@
+use frame_support::{traits::fungible, *};
+use sp_runtime::TokenError;
+
+use airo_primitives::provider::{ExecutionObserver, ProviderBonds, ProviderRegistry};
+
+use crate::{mock::*, *};
+
+fn model_id(model_id: &str) -> ModelId {
+    BoundedVec::try_from(model_id.as_bytes().to_vec()).unwrap()
+}
+
+fn metadata(name: &str) -> ProviderMetadata<Test> {
+    ProviderMetadata::new(
+        BoundedVec::try_from(name.as_bytes().to_vec()).unwrap(),
+        BoundedVec::try_from(b"https://provider.example".to_vec()).unwrap(),
+        BoundedVec::try_from(b"12D3KooW".to_vec()).unwrap(),
+    )
+}
+
+fn register(provider: AccountId, models: &[&str]) {
+    assert_ok!(AiroProviders::provider_register(
+        RuntimeOrigin::signed(provider),
+        metadata("provider"),
+        BoundedVec::try_from(models.iter().map(|model| model_id(model)).collect::<Vec<_>>())
+            .unwrap(),
+        10,
+    ));
+}
+
+fn bond_on_hold(provider: AccountId) -> Balance {
+    <Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+        &HoldReason::ProviderBond.into(),
+        &provider,
+    )
+}
+
+#[test]
+fn can_register() {
+    new_test_ext().execute_with(|| {
+        register(PROVIDER_1, &["model_1", "model_2"]);
+
+        let expected_provider = ProviderDetails::new(
+            metadata("provider"),
+            BoundedVec::try_from(vec![model_id("model_1"), model_id("model_2")]).unwrap(),
+            10,
+        );
+        assert_eq!(Providers::<Test>::get(PROVIDER_1), Some(expected_provider));
+        assert_eq!(bond_on_hold(PROVIDER_1), PROVIDER_BOND);
+        assert!(AiroProviders::serves(&PROVIDER_1, &model_id("model_1")));
+        assert!(AiroProviders::serves(&PROVIDER_1, &model_id("model_2")));
+        assert!(!AiroProviders::serves(&PROVIDER_1, &model_id("model_3")));
+        assert!(!AiroProviders::serves(&PROVIDER_2, &model_id("model_1")));
+        assert_eq!(AiroProviders::capacity(&PROVIDER_1), Some(10));
+        assert_eq!(AiroProviders::capacity(&PROVIDER_2), None);
+        System::assert_last_event(Event::ProviderRegistered { provider: PROVIDER_1 }.into());
+    });
+}
+
+#[test]
+fn fail_register() {
+    new_test_ext().execute_with(|| {
+        register(PROVIDER_1, &["model_1"]);
+        assert_noop!(
+            AiroProviders::provider_register(
+                RuntimeOrigin::signed(PROVIDER_1),
+                metadata("provider"),
+                BoundedVec::default(),
+                10
+            ),
+            Error::<Test>::ProviderAlreadyExists
+        );
+        assert_noop!(
+            AiroProviders::provider_register(
+                RuntimeOrigin::signed(PROVIDER_2),
+                metadata("provider"),
+                BoundedVec::default(),
+                0
+            ),
+            Error::<Test>::ProviderInvalid
+        );
+        assert_noop!(
+            AiroProviders::provider_register(
+                RuntimeOrigin::signed(PROVIDER_NO_BALANCE),
+                metadata("provider"),
+                BoundedVec::default(),
+                10
+            ),
+            TokenError::FundsUnavailable
+        );
+    });
+}
+
+#[test]
+fn fail_register_unknown_model() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroProviders::provider_register(
+                RuntimeOrigin::signed(PROVIDER_1),
+                metadata("provider"),
+                BoundedVec::try_from(vec![model_id("model_1"), model_id(UNKNOWN_MODEL)]).unwrap(),
+                10
+            ),
+            Error::<Test>::ModelNotFound
+        );
+
+        register(PROVIDER_1, &["model_1"]);
+        assert_noop!(
+            AiroProviders::provider_update(
+                RuntimeOrigin::signed(PROVIDER_1),
+                metadata("provider"),
+                BoundedVec::try_from(vec![model_id(UNKNOWN_MODEL)]).unwrap(),
+                10
+            ),
+            Error::<Test>::ModelNotFound
+        );
+    });
+}
+
+#[test]
+fn can_update() {
+    new_test_ext().execute_with(|| {
+        register(PROVIDER_1, &["model_1"]);
+
+        assert_ok!(AiroProviders::provider_update(
+            RuntimeOrigin::signed(PROVIDER_1),
+            metadata("renamed"),
+            BoundedVec::try_from(vec![model_id("model_2")]).unwrap(),
+            5
+        ));
+
+        let provider = Providers::<Test>::get(PROVIDER_1).unwrap();
+        assert_eq!(provider.metadata, metadata("renamed"));
+        assert_eq!(provider.capacity, 5);
+        assert!(!AiroProviders::serves(&PROVIDER_1, &model_id("model_1")));
+        assert!(AiroProviders::serves(&PROVIDER_1, &model_id("model_2")));
+        System::assert_last_event(Event::ProviderUpdated { provider: PROVIDER_1 }.into());
+    });
+}
+
+#[test]
+fn fail_update_missing_provider() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroProviders::provider_update(
+                RuntimeOrigin::signed(PROVIDER_1),
+                metadata("provider"),
+                BoundedVec::default(),
+                10
+            ),
+            Error::<Test>::ProviderNotFound
+        );
+    });
+}
+
+#[test]
+fn can_deregister_and_withdraw_bond() {
+    new_test_ext().execute_with(|| {
+        register(PROVIDER_1, &["model_1"]);
+
+        assert_ok!(AiroProviders::provider_deregister(RuntimeOrigin::signed(PROVIDER_1)));
+
+        let unlocks_at = System::block_number() + UNBONDING_PERIOD;
+        assert!(!Providers::<Test>::contains_key(PROVIDER_1));
+        assert!(!AiroProviders::serves(&PROVIDER_1, &model_id("model_1")));
+        assert_eq!(bond_on_hold(PROVIDER_1), PROVIDER_BOND);
+        System::assert_last_event(
+            Event::ProviderDeregistered { provider: PROVIDER_1, unlocks_at }.into(),
+        );
+
+        // The bond stays held until the unbonding period elapses.
+        assert_noop!(
+            AiroProviders::bond_withdraw(RuntimeOrigin::signed(PROVIDER_1)),
+            Error::<Test>::UnbondingActive
+        );
+        System::set_block_number(unlocks_at);
+        assert_ok!(AiroProviders::bond_withdraw(RuntimeOrigin::signed(PROVIDER_1)));
+
+        assert_eq!(bond_on_hold(PROVIDER_1), 0);
+        assert!(!Unbondings::<Test>::contains_key(PROVIDER_1));
+        System::assert_last_event(
+            Event::BondWithdrawn { provider: PROVIDER_1, amount: PROVIDER_BOND }.into(),
+        );
+    });
+}
+
+#[test]
+fn fail_deregister_missing_provider() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            AiroProviders::provider_deregister(RuntimeOrigin::signed(PROVIDER_1)),
+            Error::<Test>::ProviderNotFound
+        );
+        assert_noop!(
+            AiroProviders::bond_withdraw(RuntimeOrigin::signed(PROVIDER_1)),
+            Error::<Test>::UnbondingNotFound
+        );
+    });
+}
+
+#[test]
+fn fail_deregister_with_agreements() {
+    new_test_ext().execute_with(|| {
+        register(PROVIDER_1, &["model_1"]);
+        AgreementsProvider::set(Some(PROVIDER_1));
+
+        assert_noop!(
+            AiroProviders::provider_deregister(RuntimeOrigin::signed(PROVIDER_1)),
+            Error::<Test>::AgreementsActive
+        );
+
+        // Once its agreements are closed, the provider can deregister.
+        AgreementsProvider::set(None);
+        assert_ok!(AiroProviders::provider_deregister(RuntimeOrigin::signed(PROVIDER_1)));
+    });
+}
+
+#[test]
+fn bond_slashed_until_withdrawn() {
+    new_test_ext().execute_with(|| {
+        register(PROVIDER_1, &["model_1"]);
+
+        assert_eq!(AiroProviders::slash(&PROVIDER_1, &PROVIDER_2, 1_000), 1_000);
+        assert_eq!(Providers::<Test>::get(PROVIDER_1).unwrap().bond, PROVIDER_BOND - 1_000);
+        assert_eq!(bond_on_hold(PROVIDER_1), PROVIDER_BOND - 1_000);
+        assert_eq!(
+            <Balances as fungible::Inspect<_>>::balance(&PROVIDER_2),
+            INITIAL_BALANCE + 1_000
+        );
+
+        // The bond being unbonded can still be slashed, but not beyond what is left of it.
+        assert_ok!(AiroProviders::provider_deregister(RuntimeOrigin::signed(PROVIDER_1)));
+        assert_eq!(
+            AiroProviders::slash(&PROVIDER_1, &PROVIDER_2, PROVIDER_BOND),
+            PROVIDER_BOND - 1_000
+        );
+        assert_eq!(Unbondings::<Test>::get(PROVIDER_1).unwrap().amount, 0);
+        assert_eq!(bond_on_hold(PROVIDER_1), 0);
+    });
+}
+
+#[test]
+fn reputation_derived_from_execution_outcomes() {
+    new_test_ext().execute_with(|| {
+        // Providers without any outcomes start in the middle.
+        assert_eq!(AiroProviders::reputation(&PROVIDER_1), Perbill::from_percent(50));
+
+        for latency in [2, 4, 6] {
+            AiroProviders::on_response(&PROVIDER_1, latency);
+        }
+        assert_eq!(AiroProviders::reputation(&PROVIDER_1), Perbill::from_percent(80));
+
+        AiroProviders::on_request_expired(&PROVIDER_1);
+        AiroProviders::on_challenge_lost(&PROVIDER_1);
+
+        let reputation = Reputations::<Test>::get(PROVIDER_1);
+        assert_eq!(reputation.responses_count, 3);
+        assert_eq!(reputation.average_latency(), 4);
+        assert_eq!(reputation.expirations_count, 1);
+        assert_eq!(reputation.challenges_lost_count, 1);
+        assert_eq!(AiroProviders::reputation(&PROVIDER_1), Perbill::from_rational(4u32, 8u32));
+        assert_eq!(AiroProviders::reputation(&PROVIDER_2), Perbill::from_percent(50));
+    });
+}
+
+#[test]
+fn slower_provider_scores_lower() {
+    new_test_ext().execute_with(|| {
+        for latency in [2, 4, 6] {
+            AiroProviders::on_response(&PROVIDER_1, latency);
+        }
+        for latency in [8, 10, 12] {
+            AiroProviders::on_response(&PROVIDER_2, latency);
+        }
+
+        // Both have the same outcomes, but the second takes twice the latency target on average.
+        assert_eq!(AiroProviders::reputation(&PROVIDER_1), Perbill::from_percent(80));
+        assert_eq!(AiroProviders::reputation(&PROVIDER_2), Perbill::from_percent(40));
+    });
+}
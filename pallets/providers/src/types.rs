@@ -0,0 +1,160 @@
+use crate::*;
+
+/// Type alias for the balance type from the runtime.
+pub type BalanceOf<T> =
+    <<T as Config>::Currency as FunInspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+pub type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
+pub type ProviderModels<T> = BoundedVec<<T as Config>::ModelId, <T as Config>::MaxModels>;
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ProviderMetadata<T: Config> {
+    pub name: BoundedVec<u8, T::MaxNameLength>,
+    /// Address consumers and DX nodes reach the provider at.
+    pub endpoint: BoundedVec<u8, T::MaxEndpointLength>,
+    /// Peer ID of the provider's node on the DX network.
+    pub peer_id: BoundedVec<u8, T::MaxPeerIdLength>,
+}
+
+impl<T: Config> ProviderMetadata<T> {
+    pub fn new(
+        name: BoundedVec<u8, T::MaxNameLength>,
+        endpoint: BoundedVec<u8, T::MaxEndpointLength>,
+        peer_id: BoundedVec<u8, T::MaxPeerIdLength>,
+    ) -> Self {
+        Self { name, endpoint, peer_id }
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ProviderDetails<T: Config> {
+    pub metadata: ProviderMetadata<T>,
+    pub models: ProviderModels<T>,
+    /// Number of requests the provider can have unanswered at once.
+    #[codec(compact)]
+    pub capacity: RequestsUsize,
+    pub bond: BalanceOf<T>,
+}
+
+impl<T: Config> ProviderDetails<T> {
+    pub fn new(
+        metadata: ProviderMetadata<T>,
+        models: ProviderModels<T>,
+        capacity: RequestsUsize,
+    ) -> Self {
+        Self { metadata, models, capacity, bond: T::ProviderBond::get() }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn models_exist(&self) -> bool {
+        self.models.iter().all(|model_id| T::ModelOwnership::owner(model_id).is_some())
+    }
+
+    pub fn hold_bond(&self, provider: &AccountIdOf<T>) -> DispatchResult {
+        T::Currency::hold(&HoldReason::ProviderBond.into(), provider, self.bond)
+    }
+}
+
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct UnbondingDetails<T: Config> {
+    pub amount: BalanceOf<T>,
+    pub unlocks_at: BlockNumberFor<T>,
+}
+
+impl<T: Config> UnbondingDetails<T> {
+    pub fn new(amount: BalanceOf<T>) -> Self {
+        let now = frame_system::Pallet::<T>::block_number();
+        Self { amount, unlocks_at: now.saturating_add(T::UnbondingPeriod::get()) }
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        frame_system::Pallet::<T>::block_number() >= self.unlocks_at
+    }
+
+    pub fn release(&self, provider: &AccountIdOf<T>) -> DispatchResult {
+        T::Currency::release(&HoldReason::ProviderBond.into(), provider, self.amount, BestEffort)?;
+        Ok(())
+    }
+}
+
+/// Moves up to `amount` of the `bond` held from `provider` to `beneficiary`, reducing it by the
+/// amount moved, which is returned.
+pub fn slash_held<T: Config>(
+    provider: &AccountIdOf<T>,
+    beneficiary: &AccountIdOf<T>,
+    bond: &mut BalanceOf<T>,
+    amount: BalanceOf<T>,
+) -> BalanceOf<T> {
+    let slashed = T::Currency::transfer_on_hold(
+        &HoldReason::ProviderBond.into(),
+        provider,
+        beneficiary,
+        amount.min(*bond),
+        BestEffort,
+        Free,
+        Polite,
+    )
+    .unwrap_or_else(|_| Zero::zero());
+    bond.saturating_reduce(slashed);
+    slashed
+}
+
+/// Number of failed outcomes a lost challenge counts as, since the output was wrong rather than
+/// missing.
+const CHALLENGE_LOST_WEIGHT: u32 = 2;
+
+/// Outcomes of a provider's executions its reputation is derived from.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, Default, MaxEncodedLen, TypeInfo)]
+pub struct ReputationDetails<BlockNumber> {
+    /// Number of responses the provider has been paid for.
+    #[codec(compact)]
+    pub responses_count: u32,
+    /// Sum of the number of blocks between every request and its paid response.
+    pub latency_total: BlockNumber,
+    /// Number of requests the provider has not responded to in time.
+    #[codec(compact)]
+    pub expirations_count: u32,
+    /// Number of challenges of the provider's responses that have been upheld.
+    #[codec(compact)]
+    pub challenges_lost_count: u32,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> ReputationDetails<BlockNumber> {
+    pub fn record_response(&mut self, latency: BlockNumber) {
+        self.responses_count.saturating_inc();
+        self.latency_total.saturating_accrue(latency);
+    }
+
+    /// Share of successful outcomes. Every provider starts with one successful and one failed
+    /// outcome, so that a new provider scores a half and a single outcome does not decide it.
+    /// The share is scaled down by how far the average latency exceeds `latency_target`.
+    pub fn score(&self, latency_target: BlockNumber) -> Perbill {
+        let successes = self.responses_count.saturating_add(1);
+        let failures = self
+            .challenges_lost_count
+            .saturating_mul(CHALLENGE_LOST_WEIGHT)
+            .saturating_add(self.expirations_count)
+            .saturating_add(1);
+        let outcomes = Perbill::from_rational(successes, successes.saturating_add(failures));
+        let average_latency = self.average_latency();
+        if average_latency <= latency_target {
+            outcomes
+        } else {
+            outcomes * Perbill::from_rational(latency_target, average_latency)
+        }
+    }
+
+    pub fn average_latency(&self) -> BlockNumber {
+        if self.responses_count == 0 {
+            Zero::zero()
+        } else {
+            self.latency_total / self.responses_count.into()
+        }
+    }
+}
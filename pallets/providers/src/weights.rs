@@ -0,0 +1,28 @@
+use frame_support::weights::Weight;
+
+/// Weight functions needed for pallet_providers.
+pub trait WeightInfo {
+    fn provider_register() -> Weight;
+    fn provider_update() -> Weight;
+    fn provider_deregister() -> Weight;
+    fn bond_withdraw() -> Weight;
+}
+
+/// Weights used for tests only.
+impl WeightInfo for () {
+    fn provider_register() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn provider_update() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn provider_deregister() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+
+    fn bond_withdraw() -> Weight {
+        Weight::from_parts(10_000_000, 0)
+    }
+}
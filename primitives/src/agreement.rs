@@ -1,13 +1,28 @@
 use frame_support::dispatch::DispatchResult;
 
-use crate::RequestsUsize;
+use crate::{model::ModelVersion, requirements::ExecutionRequirements, RequestsUsize, UnitsUsize};
 
+/// A stage of a pipeline, executing one model of it.
+pub struct PipelineStage<AccountId, OrderId, ModelId, Balance> {
+    pub provider: AccountId,
+    pub order_id: OrderId,
+    pub model_id: ModelId,
+    pub model_version: Option<ModelVersion>,
+    pub price_per_request: Balance,
+}
+
+/// Agreements are priced, and paid, in the given `asset`, or in the native currency if none.
+/// Their requests are executed according to the `requirements`, if any, on the `model_version`
+/// of the model if it is versioned.
 pub trait AgreementManagement {
     type AccountId;
     type OrderId;
     type ModelId;
     type Balance;
+    type BlockNumber;
+    type AssetId;
 
+    #[allow(clippy::too_many_arguments)]
     fn create_agreement(
         consumer: Self::AccountId,
         provider: Self::AccountId,
@@ -15,5 +30,82 @@ pub trait AgreementManagement {
         model_id: Self::ModelId,
         price_per_request: Self::Balance,
         requests_total: RequestsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult;
+
+    /// Creates an agreement where every request is executed by all the `providers` and a response
+    /// is accepted once `quorum` of them have agreed on it.
+    #[allow(clippy::too_many_arguments)]
+    fn create_redundant_agreement(
+        consumer: Self::AccountId,
+        providers: &[Self::AccountId],
+        order_id: Self::OrderId,
+        model_id: Self::ModelId,
+        price_per_request: Self::Balance,
+        requests_total: RequestsUsize,
+        quorum: RequestsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult;
+
+    /// Creates a subscription agreement, paid per `period` blocks for `periods_total` periods in
+    /// advance rather than per request, optionally limited to `requests_per_period`.
+    #[allow(clippy::too_many_arguments)]
+    fn create_subscription_agreement(
+        consumer: Self::AccountId,
+        provider: Self::AccountId,
+        order_id: Self::OrderId,
+        model_id: Self::ModelId,
+        price_per_period: Self::Balance,
+        period: Self::BlockNumber,
+        periods_total: u32,
+        requests_per_period: Option<RequestsUsize>,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
     ) -> DispatchResult;
+
+    /// Creates an agreement priced per usage unit, where every request is charged for the units
+    /// consumed by its response, up to `max_units_per_request`.
+    #[allow(clippy::too_many_arguments)]
+    fn create_metered_agreement(
+        consumer: Self::AccountId,
+        provider: Self::AccountId,
+        order_id: Self::OrderId,
+        model_id: Self::ModelId,
+        price_per_unit: Self::Balance,
+        requests_total: RequestsUsize,
+        max_units_per_request: UnitsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+        model_version: Option<ModelVersion>,
+    ) -> DispatchResult;
+
+    /// Creates an agreement for every stage of a pipeline, where the response to a request of a
+    /// stage becomes a request of the next stage. Every stage is executed by its own provider and
+    /// paid at its own price, for `requests_total` requests.
+    fn create_pipeline_agreements(
+        consumer: Self::AccountId,
+        stages: &[PipelineStage<Self::AccountId, Self::OrderId, Self::ModelId, Self::Balance>],
+        requests_total: RequestsUsize,
+        asset: Option<Self::AssetId>,
+        requirements: Option<ExecutionRequirements<Self::BlockNumber>>,
+    ) -> DispatchResult;
+
+    /// Whether `consumer` can prepay `amount` in `asset`, or in the native currency if none, for
+    /// a new agreement, within its spending limits.
+    fn can_spend(
+        consumer: &Self::AccountId,
+        asset: &Option<Self::AssetId>,
+        amount: Self::Balance,
+    ) -> bool;
+}
+
+/// Agreements providers have entered into, which keep them from deregistering.
+pub trait AgreementRegistry<AccountId> {
+    /// Whether `provider` has agreements that have not been closed yet.
+    fn has_agreements(provider: &AccountId) -> bool;
 }
@@ -2,6 +2,23 @@ pub trait ModelFactory<ModelId> {
     fn get_model_id() -> ModelId;
 }
 
+pub trait ModelOwnerFactory<AccountId, ModelId> {
+    fn create_model(owner: &AccountId) -> ModelId;
+}
+
+pub trait ModelVersionFactory<ModelId> {
+    fn create_version(model_id: &ModelId) -> crate::model::ModelVersion;
+}
+
 pub trait ContentFactory<ContentId> {
     fn get_content_id() -> ContentId;
 }
+
+pub trait ProviderFactory<AccountId, ModelId> {
+    fn register_provider(provider: &AccountId, model_id: ModelId);
+}
+
+pub trait SignerFactory<Public, AccountId, Signature> {
+    fn signer(index: u32) -> (Public, AccountId);
+    fn sign(signer: &Public, message: &[u8]) -> Signature;
+}
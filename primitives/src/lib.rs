@@ -1,7 +1,11 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 pub type RequestsUsize = u32;
+pub type UnitsUsize = u32;
 
 pub mod agreement;
 pub mod benchmarking;
+pub mod model;
 pub mod payment;
+pub mod provider;
+pub mod requirements;
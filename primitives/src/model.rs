@@ -0,0 +1,20 @@
+/// Versions of a model are numbered from 1, in the order they are released.
+pub type ModelVersion = u32;
+
+pub trait ModelRegistry {
+    type ModelId;
+
+    /// Latest released version of `model_id`, if it has any.
+    fn latest_version(model_id: &Self::ModelId) -> Option<ModelVersion>;
+
+    /// Whether `version` of `model_id` has been released.
+    fn has_version(model_id: &Self::ModelId, version: ModelVersion) -> bool;
+}
+
+/// Finds the owners of models, e.g. the owners of the NFTs models are items of.
+pub trait ModelOwnership {
+    type AccountId;
+    type ModelId;
+
+    fn owner(model_id: &Self::ModelId) -> Option<Self::AccountId>;
+}
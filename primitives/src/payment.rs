@@ -5,3 +5,10 @@ pub trait RoyaltyResolver {
 
     fn get_royalty(model_id: &Self::ModelId) -> Option<(Self::AccountId, Self::Balance)>;
 }
+
+/// Assets agreements can be paid in.
+pub trait AssetRegistry<AssetId> {
+    /// Whether `asset` exists and is sufficient, so that an account can receive it without
+    /// holding the native currency, such as the escrow account of an agreement.
+    fn is_sufficient(asset: &AssetId) -> bool;
+}
@@ -0,0 +1,38 @@
+use frame_support::sp_runtime::Perbill;
+
+use crate::RequestsUsize;
+
+pub trait ProviderRegistry {
+    type AccountId;
+    type ModelId;
+
+    /// Whether `provider` is registered and serves `model_id`.
+    fn serves(provider: &Self::AccountId, model_id: &Self::ModelId) -> bool;
+
+    /// Reputation score of `provider`, derived from the outcomes of its executions.
+    fn reputation(provider: &Self::AccountId) -> Perbill;
+
+    /// Number of requests `provider` can have unanswered at once, if it is registered.
+    fn capacity(provider: &Self::AccountId) -> Option<RequestsUsize>;
+}
+
+/// Bonds held from providers, which back the responses they create.
+pub trait ProviderBonds<AccountId, Balance> {
+    /// Slashes up to `amount` of the bond of `provider` to `beneficiary`, including a bond still
+    /// being unbonded. Returns the amount slashed.
+    fn slash(provider: &AccountId, beneficiary: &AccountId, amount: Balance) -> Balance;
+}
+
+/// Notified of the outcomes of executions, e.g. to track the reputation of providers.
+pub trait ExecutionObserver<AccountId, BlockNumber> {
+    /// `provider` has been paid for a response created `latency` blocks after the request.
+    fn on_response(_provider: &AccountId, _latency: BlockNumber) {}
+
+    /// `provider` has not responded to a request in time.
+    fn on_request_expired(_provider: &AccountId) {}
+
+    /// A challenge of a response of `provider` has been upheld.
+    fn on_challenge_lost(_provider: &AccountId) {}
+}
+
+impl<AccountId, BlockNumber> ExecutionObserver<AccountId, BlockNumber> for () {}
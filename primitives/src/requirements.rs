@@ -0,0 +1,34 @@
+use frame_support::{pallet_prelude::*, traits::ConstU32};
+
+/// Region or jurisdiction tag, e.g. an ISO 3166 code.
+pub type RegionTag = BoundedVec<u8, ConstU32<16>>;
+
+/// Classes of hardware a model can be executed on, from the least to the most capable.
+#[derive(
+    Clone, Copy, Encode, Decode, Eq, PartialEq, Ord, PartialOrd, Debug, MaxEncodedLen, TypeInfo,
+)]
+pub enum HardwareClass {
+    Cpu,
+    ConsumerGpu,
+    DatacenterGpu,
+}
+
+/// How long a provider may keep the inputs and outputs of the requests it executes.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+pub enum DataRetention {
+    /// Nothing is kept once the response is delivered.
+    None,
+    /// Kept for at most this many days.
+    Days(#[codec(compact)] u32),
+}
+
+/// Requirements on how the requests of an agreement are executed, which providers acknowledge
+/// when bidding on an order.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Debug, MaxEncodedLen, TypeInfo)]
+pub struct ExecutionRequirements<BlockNumber> {
+    /// Maximum number of blocks between a request and its response.
+    pub max_latency: Option<BlockNumber>,
+    pub min_hardware_class: Option<HardwareClass>,
+    pub region: Option<RegionTag>,
+    pub retention: Option<DataRetention>,
+}
@@ -6,7 +6,11 @@
 // pub mod configs;
 
 extern crate alloc;
-use airo_primitives::payment::RoyaltyResolver;
+use airo_primitives::{
+    model::ModelOwnership,
+    payment::{AssetRegistry, RoyaltyResolver},
+    provider::ProviderRegistry,
+};
 use alloc::{vec, vec::Vec};
 pub use frame_support::{
     construct_runtime, derive_impl, parameter_types,
@@ -20,14 +24,17 @@ pub use frame_support::{
         },
         IdentityFee, Weight,
     },
-    StorageValue,
+    PalletId, StorageValue,
 };
 use frame_support::{
     genesis_builder_helper::{build_state, get_preset},
-    traits::{tokens::nonfungibles_v2::Inspect, AsEnsureOriginWithArg, VariantCountOf},
+    traits::{
+        tokens::{nonfungibles_v2::Inspect, pay::PayAssetFromAccount, UnityAssetBalanceConversion},
+        AsEnsureOriginWithArg, VariantCountOf,
+    },
 };
 pub use frame_system::Call as SystemCall;
-use frame_system::{EnsureRoot, EnsureSigned};
+use frame_system::{EnsureRoot, EnsureSigned, EnsureWithSuccess};
 pub use pallet_balances::Call as BalancesCall;
 use pallet_grandpa::AuthorityId as GrandpaId;
 pub use pallet_market;
@@ -40,7 +47,9 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 pub use sp_runtime::BuildStorage;
 use sp_runtime::{
     generic, impl_opaque_keys,
-    traits::{BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify},
+    traits::{
+        AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify,
+    },
     transaction_validity::{TransactionSource, TransactionValidity},
     ApplyExtrinsicResult, MultiSignature,
 };
@@ -110,10 +119,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     //   `spec_version`, and `authoring_version` are the same between Wasm and native.
     // This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
     //   the compatible custom types.
-    spec_version: 100,
+    spec_version: 101,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
     system_version: 1,
 };
 
@@ -301,6 +310,91 @@ impl pallet_nfts::Config for Runtime {
     type WeightInfo = pallet_nfts::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+    pub const AssetAccountDeposit: Balance = 10;
+}
+
+pub type AssetId = u32;
+
+impl pallet_assets::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Balance = Balance;
+    type RemoveItemsLimit = ConstU32<1_000>;
+    type AssetId = AssetId;
+    type AssetIdParameter = scale_codec::Compact<AssetId>;
+    type Currency = Balances;
+    type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type AssetDeposit = AssetDeposit;
+    type AssetAccountDeposit = AssetAccountDeposit;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type ApprovalDeposit = ApprovalDeposit;
+    type StringLimit = ConstU32<50>;
+    type Freezer = ();
+    type Extra = ();
+    type CallbackHandle = ();
+    type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+parameter_types! {
+    pub const TreasuryPalletId: PalletId = PalletId(*b"py/trsry");
+    pub TreasuryAccount: AccountId = Treasury::account_id();
+    pub const MaxSpendBalance: Balance = Balance::MAX;
+}
+
+impl pallet_treasury::Config for Runtime {
+    type PalletId = TreasuryPalletId;
+    type Currency = Balances;
+    type RejectOrigin = EnsureRoot<AccountId>;
+    type RuntimeEvent = RuntimeEvent;
+    type SpendPeriod = ConstU32<{ 7 * DAYS }>;
+    type Burn = ();
+    type BurnDestination = ();
+    type SpendFunds = ();
+    type WeightInfo = pallet_treasury::weights::SubstrateWeight<Runtime>;
+    type MaxApprovals = ConstU32<100>;
+    type SpendOrigin = EnsureWithSuccess<EnsureRoot<AccountId>, AccountId, MaxSpendBalance>;
+    // Native fees are spent locally, while `spend` pays out the asset fees collected from
+    // agreements, valued one to one against the native currency.
+    type AssetKind = AssetId;
+    type Beneficiary = AccountId;
+    type BeneficiaryLookup = AccountIdLookup<AccountId, ()>;
+    type Paymaster = PayAssetFromAccount<Assets, TreasuryAccount>;
+    type BalanceConverter = UnityAssetBalanceConversion;
+    type PayoutPeriod = ConstU32<{ 30 * DAYS }>;
+    type BlockNumberProvider = System;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = AiroBenchmarkHelper;
+}
+
+parameter_types! {
+    pub const ProviderBond: Balance = 1_000_000;
+}
+
+impl pallet_providers::Config for Runtime {
+    type WeightInfo = ();
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type Currency = Balances;
+    type ModelId = ModelId;
+    type ModelOwnership = NftModelOwnership;
+    // Providers can't deregister until their agreements are closed. Their bond stays held for
+    // the unbonding period after, so that the responses can still be challenged.
+    type AgreementRegistry = AiroExecution;
+    type ProviderBond = ProviderBond;
+    type UnbondingPeriod = ConstU32<{ 7 * DAYS }>;
+    type LatencyTarget = ConstU32<{ 5 * MINUTES }>;
+    type MaxNameLength = ConstU32<64>;
+    type MaxEndpointLength = ConstU32<256>;
+    type MaxPeerIdLength = ConstU32<128>;
+    type MaxModels = ConstU32<32>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = AiroBenchmarkHelper;
+}
+
 impl pallet_market::Config for Runtime {
     type WeightInfo = ();
     type RuntimeEvent = RuntimeEvent;
@@ -308,7 +402,16 @@ impl pallet_market::Config for Runtime {
     type Currency = Balances;
     type ModelId = ModelId;
     type OrderId = u32;
+    type AssetId = AssetId;
+    type MaxAgreementProviders = ConstU32<5>;
+    type MaxAllowedProviders = ConstU32<32>;
+    type MaxPipelineStages = ConstU32<8>;
     type AgreementManagement = AiroExecution;
+    type ProviderRegistry = AiroProviders;
+    type ModelRegistry = AiroModels;
+    type AssetRegistry = SufficientAssets;
+    type OffchainSignature = Signature;
+    type OffchainPublic = <Signature as Verify>::Signer;
     #[cfg(feature = "runtime-benchmarks")]
     type BenchmarkHelper = AiroBenchmarkHelper;
 }
@@ -329,15 +432,83 @@ impl RoyaltyResolver for NftRoyaltyResolver {
     }
 }
 
+/// Only sufficient assets can be received by the escrow accounts of agreements, which hold no
+/// native balance.
+pub struct SufficientAssets;
+
+impl AssetRegistry<AssetId> for SufficientAssets {
+    fn is_sufficient(asset: &AssetId) -> bool {
+        pallet_assets::Asset::<Runtime>::get(asset).is_some_and(|details| details.is_sufficient)
+    }
+}
+
+pub struct NftModelOwnership;
+
+impl ModelOwnership for NftModelOwnership {
+    type AccountId = AccountId;
+    type ModelId = ModelId;
+
+    fn owner(model_id: &Self::ModelId) -> Option<Self::AccountId> {
+        <Nfts as Inspect<AccountId>>::owner(&MODELS_COLLECTION_ID, model_id)
+    }
+}
+
+impl pallet_models::Config for Runtime {
+    type WeightInfo = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ModelId = ModelId;
+    type ContentId = Hash;
+    type ModelOwnership = NftModelOwnership;
+    type MaxVersions = ConstU32<256>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = AiroBenchmarkHelper;
+}
+
+parameter_types! {
+    pub const RequestParamsDepositPerByte: Balance = 10;
+    pub const RequestDeposit: Balance = 1_000;
+    pub const ResponseDeposit: Balance = 1_000;
+    pub const ChallengeStake: Balance = 100_000;
+    pub const ExecutionPalletId: PalletId = PalletId(*b"airo/exe");
+    pub const ProtocolFee: Perbill = Perbill::from_percent(1);
+}
+
 impl pallet_execution::Config for Runtime {
     type WeightInfo = ();
     type RuntimeEvent = RuntimeEvent;
     type RuntimeHoldReason = RuntimeHoldReason;
     type Currency = Balances;
+    // Escrow accounts only receive sufficient assets, or need the native existential deposit.
+    type Assets = Assets;
+    type PalletId = ExecutionPalletId;
     type AgreementId = u32;
     type ModelId = ModelId;
     type ContentId = Hash;
+    type MaxRequestParamsLength = ConstU32<1024>;
+    type RequestParamsDepositPerByte = RequestParamsDepositPerByte;
+    type RequestDeposit = RequestDeposit;
+    type ResponseDeposit = ResponseDeposit;
     type RoyaltyResolver = NftRoyaltyResolver;
+    type RequestTimeout = ConstU32<HOURS>;
+    type ProviderRegistry = AiroProviders;
+    type ExecutionObserver = AiroProviders;
+    type RetentionPeriod = ConstU32<{ 7 * DAYS }>;
+    type TransferApprovalRequired = ConstBool<true>;
+    type ExtensionApprovalThreshold = ConstU32<1_000>;
+    type MaxDelegates = ConstU32<16>;
+    type MaxOperators = ConstU32<64>;
+    type MaxReplicas = ConstU32<4>;
+    type ChallengeWindow = ConstU32<DAYS>;
+    type ChallengeStake = ChallengeStake;
+    // Challenges are resolved before the bond of a deregistered provider can be withdrawn.
+    type ChallengeExpiry = ConstU32<{ 3 * DAYS }>;
+    type ProviderBonds = AiroProviders;
+    type ChallengeResolverOrigin = EnsureRoot<AccountId>;
+    type SubscriptionNoticePeriod = ConstU32<DAYS>;
+    type ProtocolFee = ProtocolFee;
+    type TreasuryAccount = TreasuryAccount;
+    type SpendingPeriod = ConstU32<DAYS>;
+    type SpendingLimitDelay = ConstU32<DAYS>;
     #[cfg(feature = "runtime-benchmarks")]
     type BenchmarkHelper = AiroBenchmarkHelper;
 }
@@ -352,6 +523,92 @@ impl airo_primitives::benchmarking::ModelFactory<ModelId> for AiroBenchmarkHelpe
     }
 }
 
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_treasury::ArgumentsFactory<AssetId, AccountId> for AiroBenchmarkHelper {
+    fn create_asset_kind(seed: u32) -> AssetId {
+        seed
+    }
+
+    fn create_beneficiary(seed: [u8; 32]) -> AccountId {
+        AccountId::from(seed)
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl airo_primitives::benchmarking::ProviderFactory<AccountId, ModelId> for AiroBenchmarkHelper {
+    fn register_provider(provider: &AccountId, model_id: ModelId) {
+        use frame_support::traits::fungible::Mutate;
+
+        Balances::set_balance(provider, ProviderBond::get() * 2);
+        AiroBenchmarkHelper::mint_model(provider, model_id);
+        AiroProviders::provider_register(
+            RuntimeOrigin::signed(provider.clone()),
+            pallet_providers::ProviderMetadata::new(
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            ),
+            vec![model_id].try_into().expect("a single model fits; qed"),
+            1,
+        )
+        .expect("funded provider can register; qed");
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl airo_primitives::benchmarking::ModelOwnerFactory<AccountId, ModelId> for AiroBenchmarkHelper {
+    fn create_model(owner: &AccountId) -> ModelId {
+        let model_id = ModelId::repeat_byte(1);
+        AiroBenchmarkHelper::mint_model(owner, model_id);
+        model_id
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl AiroBenchmarkHelper {
+    /// Mints `model_id` to `owner`, unless it has already been minted.
+    fn mint_model(owner: &AccountId, model_id: ModelId) {
+        if NftModelOwnership::owner(&model_id).is_some() {
+            return;
+        }
+        if !pallet_nfts::Collection::<Runtime>::contains_key(MODELS_COLLECTION_ID) {
+            Nfts::force_create(
+                RuntimeOrigin::root(),
+                Address::Id(owner.clone()),
+                pallet_nfts::CollectionConfig {
+                    settings: Default::default(),
+                    max_supply: None,
+                    mint_settings: Default::default(),
+                },
+            )
+            .expect("root can create the models collection; qed");
+        }
+        Nfts::force_mint(
+            RuntimeOrigin::root(),
+            MODELS_COLLECTION_ID,
+            model_id,
+            Address::Id(owner.clone()),
+            Default::default(),
+        )
+        .expect("root can mint a model; qed");
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl airo_primitives::benchmarking::ModelVersionFactory<ModelId> for AiroBenchmarkHelper {
+    fn create_version(model_id: &ModelId) -> airo_primitives::model::ModelVersion {
+        pallet_models::Models::<Runtime>::mutate(model_id, |model| {
+            model
+                .get_or_insert_with(Default::default)
+                .push_version(pallet_models::VersionDetails::new(
+                    Hash::repeat_byte(26),
+                    Hash::default(),
+                ))
+                .expect("a new model has room for a version; qed")
+        })
+    }
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 impl airo_primitives::benchmarking::ContentFactory<Hash> for AiroBenchmarkHelper {
     fn get_content_id() -> Hash {
@@ -385,6 +642,35 @@ impl pallet_nfts::BenchmarkHelper<u32, ModelId, <Signature as Verify>::Signer, A
     }
 }
 
+#[cfg(feature = "runtime-benchmarks")]
+impl
+    airo_primitives::benchmarking::SignerFactory<
+        <Signature as Verify>::Signer,
+        AccountId,
+        Signature,
+    > for AiroBenchmarkHelper
+{
+    fn signer(_index: u32) -> (<Signature as Verify>::Signer, AccountId) {
+        <Self as pallet_nfts::BenchmarkHelper<
+            u32,
+            ModelId,
+            <Signature as Verify>::Signer,
+            AccountId,
+            Signature,
+        >>::signer()
+    }
+
+    fn sign(signer: &<Signature as Verify>::Signer, message: &[u8]) -> Signature {
+        <Self as pallet_nfts::BenchmarkHelper<
+            u32,
+            ModelId,
+            <Signature as Verify>::Signer,
+            AccountId,
+            Signature,
+        >>::sign(signer, message)
+    }
+}
+
 // Create the runtime by composing the FRAME pallets that were previously configured.
 #[frame_support::runtime]
 mod runtime {
@@ -431,6 +717,18 @@ mod runtime {
 
     #[runtime::pallet_index(9)]
     pub type AiroExecution = pallet_execution;
+
+    #[runtime::pallet_index(10)]
+    pub type Assets = pallet_assets;
+
+    #[runtime::pallet_index(11)]
+    pub type Treasury = pallet_treasury;
+
+    #[runtime::pallet_index(12)]
+    pub type AiroProviders = pallet_providers;
+
+    #[runtime::pallet_index(13)]
+    pub type AiroModels = pallet_models;
 }
 
 /// The address format for describing accounts.
@@ -454,8 +752,10 @@ pub type SignedExtra = (
 /// All migrations of the runtime, aside from the ones declared in the pallets.
 ///
 /// This can be a tuple of types, each implementing `OnRuntimeUpgrade`.
-#[allow(unused_parens)]
-type Migrations = ();
+type Migrations = (
+    pallet_execution::migrations::MigrateV0ToV1<Runtime>,
+    pallet_market::migrations::MigrateV0ToV1<Runtime>,
+);
 
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic =
@@ -481,8 +781,12 @@ mod benches {
         [pallet_timestamp, Timestamp]
         [pallet_sudo, Sudo]
         [pallet_nfts, Nfts]
+        [pallet_assets, Assets]
+        [pallet_treasury, Treasury]
         [pallet_market, AiroMarket]
         [pallet_execution, AiroExecution]
+        [pallet_providers, AiroProviders]
+        [pallet_models, AiroModels]
     );
 }
 
@@ -698,6 +1002,24 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_execution_runtime_api::ExecutionApi<Block, AccountId, u32, Balance, BlockNumber> for Runtime {
+        fn pending_requests(
+            provider: AccountId,
+        ) -> Vec<pallet_execution::PendingRequest<u32, Balance, BlockNumber>> {
+            AiroExecution::pending_requests(&provider)
+        }
+    }
+
+    impl pallet_providers_runtime_api::ProvidersApi<Block, AccountId, BlockNumber> for Runtime {
+        fn reputation(provider: AccountId) -> pallet_providers::ReputationDetails<BlockNumber> {
+            pallet_providers::Reputations::<Runtime>::get(provider)
+        }
+
+        fn reputation_score(provider: AccountId) -> Perbill {
+            <AiroProviders as ProviderRegistry>::reputation(&provider)
+        }
+    }
+
     #[cfg(feature = "runtime-benchmarks")]
     impl frame_benchmarking::Benchmark<Block> for Runtime {
         fn benchmark_metadata(extra: bool) -> (